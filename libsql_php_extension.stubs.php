@@ -24,6 +24,35 @@ namespace {
          */
         public function finalize() {}
 
+        /**
+         * Binds a value to a parameter, keeping its type.
+         *
+         * @param int|string $parameter The 1-based parameter index, or the parameter name with or without its prefix.
+         * @param mixed $value The value to bind.
+         * @param int|null $type Optional LibSQL::PARAM_* type to coerce the value to.
+         *
+         * @return void
+         */
+        public function bindValue(int|string $parameter, mixed $value, ?int $type = null) {}
+
+        /**
+         * Binds a variable by reference; its value is read each time the statement runs.
+         *
+         * @param int|string $parameter The 1-based parameter index, or the parameter name with or without its prefix.
+         * @param mixed $variable The variable to bind.
+         * @param int|null $type Optional LibSQL::PARAM_* type to coerce the variable to.
+         *
+         * @return void
+         */
+        public function bindParam(int|string $parameter, mixed &$variable, ?int $type = null) {}
+
+        /**
+         * Removes all values bound to the prepared statement.
+         *
+         * @return void
+         */
+        public function clearBindings() {}
+
         /**
          * Binds a value to a named parameter in the prepared statement.
          *
@@ -45,12 +74,26 @@ namespace {
         /**
          * Executes the prepared statement with given parameters.
          *
+         * Without parameters, the bound values are used. Bindings are kept after execution.
+         *
          * @param array $parameters The parameters for the statement.
          * 
          * @return int The number of affected rows.
          */
         public function execute(array $parameters = []) {}
 
+        /**
+         * Executes the prepared statement once per parameter set.
+         *
+         * Unless a transaction is already open, the executions run inside an implicit
+         * transaction that is rolled back if any of them fails.
+         *
+         * @param iterable $paramSets An array or generator of parameter arrays.
+         *
+         * @return array{rowsChanged: int, lastInsertIds: int[]} The total number of changed rows and the rowid of every execution.
+         */
+        public function executeMany(iterable $paramSets) {}
+
         /**
          * Executes the prepared statement and retrieves the result set.
          *
@@ -61,7 +104,7 @@ namespace {
         public function query(array $parameters = []) {}
 
         /**
-         * Resets the prepared statement.
+         * Resets the prepared statement and removes all values bound to it.
          *
          * @return void
          */
@@ -84,9 +127,9 @@ namespace {
         public function parameterName(int $idx) {}
 
         /**
-         * Gets the column names of the result set.
+         * Gets the column metadata of the result set.
          *
-         * @return array The column names.
+         * @return array<int, array{name: string, decl_type: ?string, table_name: ?string, origin_name: ?string, database_name: ?string}> The column metadata.
          */
         public function columns() {}
     }
@@ -100,9 +143,10 @@ namespace {
          * Creates a new LibSQLTransaction instance.
          *
          * @param string $conn_id The connection ID.
-         * @param string $trx_mode The transaction mode.
+         * @param string $trx_mode The transaction behavior: DEFERRED, IMMEDIATE, EXCLUSIVE or READONLY.
+         * @param int|null $timeout Milliseconds after which the transaction is interrupted, and rolled back by the next call on it or its connection (optional).
          */
-        public function __construct(string $conn_id, string $trx_mode) {}
+        public function __construct(string $conn_id, string $trx_mode, ?int $timeout = null) {}
 
         /**
          * Retrieves the number of rows changed by the last SQL statement.
@@ -118,6 +162,20 @@ namespace {
          */
         public function isAutocommit() {}
 
+        /**
+         * Checks whether the transaction can still be used.
+         *
+         * @return bool False once the transaction, or the transaction it is nested in, has been committed or rolled back.
+         */
+        public function isActive() {}
+
+        /**
+         * Retrieves the behavior the transaction was started with.
+         *
+         * @return string The transaction behavior.
+         */
+        public function behavior() {}
+
         /**
          * Executes an SQL statement within the transaction.
          *
@@ -130,6 +188,7 @@ namespace {
 
         /**
          * Prepares an SQL statement for execution within the transaction.
+         * The statement can only be executed while the transaction is active.
          *
          * @param string $sql The SQL statement to prepare.
          *
@@ -142,20 +201,55 @@ namespace {
          *
          * @param string $stmt The SQL statement to execute.
          * @param array $parameters The parameters for the statement (optional).
+         * @param array<string, string>|null $types PHP types keyed by column name, applied on top of the connection type map (optional).
+         *
+         * @return LibSQLResult The result of the query.
+         */
+        public function query(string $stmt, array $parameters = [], ?array $types = null) {}
+
+        /**
+         * Starts a transaction nested inside this one, backed by a savepoint.
+         *
+         * @return LibSQLTransaction The nested transaction.
+         */
+        public function transaction() {}
+
+        /**
+         * Creates a savepoint.
+         *
+         * @param string $name The name of the savepoint.
+         *
+         * @return void
+         */
+        public function savepoint(string $name) {}
+
+        /**
+         * Releases a savepoint, keeping the changes made since it was created.
          *
-         * @return array The result set.
+         * @param string $name The name of the savepoint.
+         *
+         * @return void
+         */
+        public function releaseSavepoint(string $name) {}
+
+        /**
+         * Rolls back the changes made since a savepoint was created; the savepoint stays active.
+         *
+         * @param string $name The name of the savepoint.
+         *
+         * @return void
          */
-        public function query(string $stmt, array $parameters = []) {}
+        public function rollbackTo(string $name) {}
 
         /**
-         * Commits the transaction.
+         * Commits the transaction, or releases the savepoint of a nested transaction.
          *
          * @return void
          */
         public function commit() {}
 
         /**
-         * Rolls back the transaction.
+         * Rolls back the transaction, or only the changes of a nested transaction.
          *
          * @return void
          */
@@ -218,11 +312,11 @@ namespace {
         public function columnName(int $column) {}
 
         /**
-         * Retrieves the type of a column by its index.
+         * Retrieves the declared type of a column by its index.
          *
          * @param int $column The index of the column.
          *
-         * @return string The type of the column.
+         * @return string|null The declared type of the column, or null for expressions.
          */
         public function columnType(int $column) {}
 
@@ -232,6 +326,16 @@ namespace {
          * @return int The number of columns.
          */
         public function numColumns() {}
+
+        /**
+         * Retrieves the metadata of every column in the result set.
+         *
+         * The metadata is taken from the prepared statement, so it is available
+         * even when the query returns no rows.
+         *
+         * @return array<int, array{name: string, decl_type: ?string, table_name: ?string, origin_name: ?string, database_name: ?string}> The column metadata.
+         */
+        public function columns() {}
     }
 
 
@@ -280,6 +384,148 @@ namespace {
          */
         const LIBSQL_LAZY = 5;
 
+        /**
+         * Bind the parameter as NULL
+         */
+        const PARAM_NULL = 0;
+
+        /**
+         * Bind the parameter as INTEGER
+         */
+        const PARAM_INT = 1;
+
+        /**
+         * Bind the parameter as TEXT
+         */
+        const PARAM_STR = 2;
+
+        /**
+         * Bind the parameter as BLOB
+         */
+        const PARAM_BLOB = 3;
+
+        /**
+         * Bind the parameter as 0 or 1
+         */
+        const PARAM_BOOL = 5;
+
+        /**
+         * Bind the parameter as REAL
+         */
+        const PARAM_FLOAT = 6;
+
+        /**
+         * The maximum size of a string or BLOB, or of a row, in bytes
+         */
+        const SQLITE_LIMIT_LENGTH = 0;
+
+        /**
+         * The maximum length of an SQL statement, in bytes
+         */
+        const SQLITE_LIMIT_SQL_LENGTH = 1;
+
+        /**
+         * The maximum number of columns of a table, index or view, or of a result set
+         */
+        const SQLITE_LIMIT_COLUMN = 2;
+
+        /**
+         * The maximum depth of the parse tree of an expression
+         */
+        const SQLITE_LIMIT_EXPR_DEPTH = 3;
+
+        /**
+         * The maximum number of terms of a compound SELECT statement
+         */
+        const SQLITE_LIMIT_COMPOUND_SELECT = 4;
+
+        /**
+         * The maximum number of virtual machine instructions of a statement
+         */
+        const SQLITE_LIMIT_VDBE_OP = 5;
+
+        /**
+         * The maximum number of arguments of a function
+         */
+        const SQLITE_LIMIT_FUNCTION_ARG = 6;
+
+        /**
+         * The maximum number of attached databases
+         */
+        const SQLITE_LIMIT_ATTACHED = 7;
+
+        /**
+         * The maximum length of the pattern of LIKE or GLOB
+         */
+        const SQLITE_LIMIT_LIKE_PATTERN_LENGTH = 8;
+
+        /**
+         * The maximum index of a parameter of a statement
+         */
+        const SQLITE_LIMIT_VARIABLE_NUMBER = 9;
+
+        /**
+         * The maximum depth of recursive triggers
+         */
+        const SQLITE_LIMIT_TRIGGER_DEPTH = 10;
+
+        /**
+         * The maximum number of auxiliary worker threads of a statement
+         */
+        const SQLITE_LIMIT_WORKER_THREADS = 11;
+
+        /**
+         * Returned by an authorizer to allow the action
+         */
+        const SQLITE_OK = 0;
+
+        /**
+         * Returned by an authorizer to reject the statement with an error
+         */
+        const SQLITE_DENY = 1;
+
+        /**
+         * Returned by an authorizer to disallow the action silently, e.g. reading a column as NULL
+         */
+        const SQLITE_IGNORE = 2;
+
+        /**
+         * Actions passed to an authorizer
+         */
+        const SQLITE_CREATE_INDEX = 1;
+        const SQLITE_CREATE_TABLE = 2;
+        const SQLITE_CREATE_TEMP_INDEX = 3;
+        const SQLITE_CREATE_TEMP_TABLE = 4;
+        const SQLITE_CREATE_TEMP_TRIGGER = 5;
+        const SQLITE_CREATE_TEMP_VIEW = 6;
+        const SQLITE_CREATE_TRIGGER = 7;
+        const SQLITE_CREATE_VIEW = 8;
+        const SQLITE_DELETE = 9;
+        const SQLITE_DROP_INDEX = 10;
+        const SQLITE_DROP_TABLE = 11;
+        const SQLITE_DROP_TEMP_INDEX = 12;
+        const SQLITE_DROP_TEMP_TABLE = 13;
+        const SQLITE_DROP_TEMP_TRIGGER = 14;
+        const SQLITE_DROP_TEMP_VIEW = 15;
+        const SQLITE_DROP_TRIGGER = 16;
+        const SQLITE_DROP_VIEW = 17;
+        const SQLITE_INSERT = 18;
+        const SQLITE_PRAGMA = 19;
+        const SQLITE_READ = 20;
+        const SQLITE_SELECT = 21;
+        const SQLITE_TRANSACTION = 22;
+        const SQLITE_UPDATE = 23;
+        const SQLITE_ATTACH = 24;
+        const SQLITE_DETACH = 25;
+        const SQLITE_ALTER_TABLE = 26;
+        const SQLITE_REINDEX = 27;
+        const SQLITE_ANALYZE = 28;
+        const SQLITE_CREATE_VTABLE = 29;
+        const SQLITE_DROP_VTABLE = 30;
+        const SQLITE_FUNCTION = 31;
+        const SQLITE_SAVEPOINT = 32;
+        const SQLITE_RECURSIVE = 33;
+
         /**
          * The mode of the connection.
          * @var string
@@ -528,10 +774,64 @@ namespace {
          *
          * @param string $stmt The SQL statement to execute.
          * @param array $parameters The parameters for the statement (optional).
+         * @param int|null $timeout Milliseconds after which the statement is interrupted, overriding the "queryTimeout" config option (optional).
          *
          * @return int The number of rows affected by the statement.
+         *
+         * @throws \TimeoutException If the statement is interrupted or times out.
          */
-        public function execute(string $stmt, array $parameters = []) {}
+        public function execute(string $stmt, array $parameters = [], ?int $timeout = null) {}
+
+        /**
+         * Executes an SQL statement once per parameter set.
+         *
+         * The statement is prepared once. Unless a transaction is already open, the
+         * executions run inside an implicit transaction that is rolled back if any of them fails.
+         *
+         * ## Example Usage
+         *
+         * ```
+         * $result = $db->executeMany(
+         *     "INSERT INTO users (name, age) VALUES (?, ?)",
+         *     [["Jane Doe", 30], ["John Smith", 25]]
+         * );
+         * echo "Inserted {$result['rowsChanged']} rows." . PHP_EOL;
+         * ```
+         *
+         * @param string $stmt The SQL statement to execute.
+         * @param iterable $paramSets An array or generator of parameter arrays.
+         *
+         * @return array{rowsChanged: int, lastInsertIds: int[]} The total number of changed rows and the rowid of every execution.
+         */
+        public function executeMany(string $stmt, iterable $paramSets) {}
+
+        /**
+         * Inserts many rows into a table, optionally updating rows that conflict.
+         *
+         * Rows are inserted with multi-row statements, in chunks sized under SQLite's parameter
+         * limit. Unless a transaction is already open, the chunks run inside an implicit
+         * transaction that is rolled back if any of them fails.
+         *
+         * ## Example Usage
+         *
+         * ```
+         * $result = $db->insertMany('users', [
+         *     ['email' => 'jane@example.com', 'name' => 'Jane Doe'],
+         *     ['email' => 'john@example.com', 'name' => 'John Smith'],
+         * ], ['conflict' => 'email']);
+         * echo "Inserted {$result['inserted']}, updated {$result['updated']} rows." . PHP_EOL;
+         * ```
+         *
+         * @param string $table The table to insert into.
+         * @param iterable $rows An array or generator of rows keyed by column name. Every row must have the same columns.
+         * @param array $options Options:
+         *  - `conflict` (string|string[]): the columns of the unique constraint to upsert on.
+         *  - `update` (string[]): the columns overwritten on conflict; defaults to every non-conflict column, `[]` ignores conflicting rows.
+         *  - `chunkSize` (int): the maximum number of rows per statement.
+         *
+         * @return array{inserted: int, updated: int} The number of inserted and updated rows. Conflicting rows skipped by an empty `update` list are in neither.
+         */
+        public function insertMany(string $table, iterable $rows, array $options = []) {}
 
         /**
          * Executes a batch of SQL statements on the database.
@@ -585,13 +885,126 @@ namespace {
          * @param string $stmt The SQL query to execute.
          * @param array $parameters The parameters for the query (optional).
          * @param bool $force_remote Force read from remote (only for sqld offline mode)
+         * @param array<string, string>|null $types PHP types keyed by column name, applied on top of the connection type map (optional).
+         * @param int|null $timeout Milliseconds after which the query is interrupted, overriding the "queryTimeout" config option (optional).
          *
          * @return LibSQLResult The result of the query.
+         *
+         * @throws \TimeoutException If the query is interrupted or times out.
          */
-        public function query(string $stmt, array $parameters = [], bool $force_remote = false) {}
+        public function query(string $stmt, array $parameters = [], bool $force_remote = false, ?array $types = null, ?int $timeout = null) {}
+
+        /**
+         * Enables conversion of column values based on their declared types.
+         *
+         * By default `BOOLEAN`/`BOOL` columns become `bool`, `DATETIME`/`DATE`/`TIMESTAMP`
+         * become `DateTimeImmutable`, `JSON` is decoded into arrays, `DECIMAL`/`NUMERIC`
+         * become strings and blobs become binary strings.
+         *
+         * Supported PHP types are `auto`, `int`, `float`, `string`, `bool`, `datetime`, `json` and `binary`.
+         *
+         * ## Example Usage
+         *
+         * ```
+         * $db->enableTypeMapping(["MONEY" => "string"]);
+         *
+         * $result = $db->query("SELECT id, is_active, created_at FROM users", [], false, ["id" => "string"]);
+         * $user = $result->fetchSingle(LibSQL::LIBSQL_ASSOC);
+         * // $user['is_active'] is a bool, $user['created_at'] a DateTimeImmutable
+         * ```
+         *
+         * @param array<string, string>|null $types Declared types mapped to PHP types, added to the defaults (optional).
+         *
+         * @return void
+         */
+        public function enableTypeMapping(?array $types = null) {}
+
+        /**
+         * Disables type mapping, restoring the one-to-one value conversion.
+         *
+         * @return void
+         */
+        public function disableTypeMapping() {}
+
+        /**
+         * Retrieves the statistics of the connection's prepared statement cache.
+         *
+         * The cache size is configured with the "statementCacheSize" config option (default 100, 0 disables it).
+         *
+         * @return array{hits: int, misses: int, size: int, capacity: int}
+         */
+        public function statementCacheStats() {}
+
+        /**
+         * Removes every statement from the connection's prepared statement cache.
+         *
+         * @return void
+         */
+        public function clearStatementCache() {}
+
+        /**
+         * Configures how DateTimeInterface parameters are bound on this connection.
+         *
+         * The setting lasts until the end of the request; a persistent connection then returns
+         * to its "dateTimeBinding" config option.
+         *
+         * @param string $format "iso8601" (the default), "unix" for unix timestamps, or any date() format.
+         *
+         * @return void
+         */
+        public function setDateTimeBinding(string $format) {}
+
+        /**
+         * Enables or disables leak debugging, for all connections.
+         *
+         * When enabled, a statement destroyed without finalize() raises a warning, and the handles
+         * still open at module shutdown are written to the error log. A transaction destroyed
+         * without commit() or rollback() is always rolled back with a warning.
+         *
+         * @param bool $enabled Whether to report leaked handles.
+         *
+         * @return void
+         */
+        public static function setLeakDebugging(bool $enabled) {}
+
+        /**
+         * Counts the connections, transactions and prepared statements that are currently open.
+         *
+         * @return array{connections: int, transactions: int, statements: int} The number of open handles.
+         */
+        public static function openHandles() {}
+
+        /**
+         * Releases everything the current request left open, for worker loops such as FrankenPHP or RoadRunner.
+         *
+         * Rolls back open transactions, finalizes prepared statements, syncs connections configured with
+         * "syncOnRequestEnd" and closes every connection not configured as "persistent". The same
+         * cleanup runs automatically when a PHP request ends.
+         *
+         * @return void
+         */
+        public static function resetRequestState() {}
+
+        /**
+         * Configures the Tokio runtime used by every connection, before the first connection is opened.
+         *
+         * The runtime is rebuilt in a forked child, which never uses the connections inherited from
+         * its parent. A "current_thread" runtime has no worker threads, so background work such as
+         * periodic replica syncs only runs while the extension is being called.
+         *
+         * @param string|null $flavor "multi_thread" (default) or "current_thread".
+         * @param int|null $workerThreads The number of worker threads of a "multi_thread" runtime, one per CPU core by default.
+         *
+         * @return void
+         *
+         * @throws \LibSQLException If the flavor is unknown, or the runtime is already running with a different configuration.
+         */
+        public static function configureRuntime(?string $flavor = "multi_thread", ?int $workerThreads = null) {}
 
         /**
          * Initiates a new database transaction.
+         *
+         * Inside an active transaction, this starts a nested transaction backed by a savepoint.
          * 
          * ## Example Usage
          * 
@@ -617,11 +1030,30 @@ namespace {
          * $db->close();
          * ```
          *
-         * @param string $behavior The behavior of the transaction (optional).
+         * @param string $behavior The behavior of the transaction: DEFERRED, IMMEDIATE, EXCLUSIVE or READONLY (optional).
+         * @param int|null $timeout Milliseconds after which the transaction is interrupted, and rolled back by the next call on it or its connection (optional).
          *
          * @return \LibSQLTransaction The transaction object.
          */
-        public function transaction(string $behavior = "DEFERRED") {}
+        public function transaction(string $behavior = "DEFERRED", ?int $timeout = null) {}
+
+        /**
+         * Runs a callable inside a transaction, committing when it returns and rolling back when it throws.
+         *
+         * The whole callable runs again in a new transaction when beginning, running or committing
+         * it throws a `BusyException` or `ConnectionException`, waiting `$backoff` milliseconds before
+         * the first retry and twice as long before each further one. A transaction nested in an
+         * active one is not retried.
+         *
+         * @param callable $callback Receives the `LibSQLTransaction`.
+         * @param string $behavior The behavior of the transaction (optional).
+         * @param int $retries The number of retries after the first attempt (optional).
+         * @param int $backoff The delay in milliseconds before the first retry (optional).
+         * @param int|null $timeout Milliseconds after which each attempt is rolled back if it has not ended (optional).
+         *
+         * @return mixed The value returned by the callable.
+         */
+        public function transactional(callable $callback, string $behavior = "DEFERRED", int $retries = 3, int $backoff = 50, ?int $timeout = null) {}
 
         /**
          * Prepares an SQL statement for execution.
@@ -654,6 +1086,108 @@ namespace {
          */
         public function prepare(string $sql) {}
 
+        /**
+         * Sets a handler called every $ops virtual machine instructions while a query runs.
+         *
+         * Returning true from the handler aborts the query, which throws a TimeoutException; so does a
+         * handler that throws. The handler must not use the connection. Only local and embedded replica
+         * connections support it, and it is removed when the request ends.
+         *
+         * # Example Usage
+         *
+         * ```
+         * $deadline = microtime(true) + 2;
+         * $db->setProgressHandler(1000, fn () => microtime(true) > $deadline);
+         *
+         * $db->query($report);
+         *
+         * $db->setProgressHandler(0, null);
+         * ```
+         *
+         * @param int $ops The number of virtual machine instructions between two calls, below 1 to remove the handler.
+         * @param callable|null $callback The handler, or null to remove it.
+         *
+         * @return void
+         *
+         * @throws \LibSQLException If the connection is remote.
+         */
+        public function setProgressHandler(int $ops, ?callable $callback) {}
+
+        /**
+         * Changes a runtime limit of the connection.
+         *
+         * Only local and embedded replica connections support it.
+         *
+         * # Example Usage
+         *
+         * ```
+         * $db->setLimit(LibSQL::SQLITE_LIMIT_LENGTH, 1_000_000);
+         * $db->setLimit(LibSQL::SQLITE_LIMIT_ATTACHED, 0);
+         * ```
+         *
+         * @param int $id The limit, one of the SQLITE_LIMIT_* constants.
+         * @param int $value The new value, capped at the maximum SQLite was built with.
+         *
+         * @return int The previous value of the limit.
+         *
+         * @throws \LibSQLException If the limit is unknown, the value is negative or the connection is remote.
+         */
+        public function setLimit(int $id, int $value) {}
+
+        /**
+         * Retrieves a runtime limit of the connection.
+         *
+         * Only local and embedded replica connections support it.
+         *
+         * @param int $id The limit, one of the SQLITE_LIMIT_* constants.
+         *
+         * @return int The current value of the limit.
+         *
+         * @throws \LibSQLException If the limit is unknown or the connection is remote.
+         */
+        public function getLimit(int $id) {}
+
+        /**
+         * Sets a callable that authorizes every action of the statements prepared on the connection.
+         *
+         * The authorizer receives the action, one of the SQLITE_* action constants, the two names describing
+         * it (such as a table and column name), the database name and the innermost trigger or view, and
+         * returns LibSQL::SQLITE_OK to allow the action, LibSQL::SQLITE_DENY to reject the statement with an AuthException or
+         * LibSQL::SQLITE_IGNORE to disallow the action silently. Any other value, or an exception, denies it.
+         * Only local and embedded replica connections support it, and it is removed when the request ends.
+         *
+         * # Example Usage
+         *
+         * ```
+         * $db->setAuthorizer(function (int $action, ?string $arg1, ?string $arg2) {
+         *     return match ($action) {
+         *         LibSQL::SQLITE_SELECT, LibSQL::SQLITE_READ => LibSQL::SQLITE_OK,
+         *         LibSQL::SQLITE_FUNCTION => $arg2 === 'load_extension' ? LibSQL::SQLITE_DENY : LibSQL::SQLITE_OK,
+         *         default => LibSQL::SQLITE_DENY,
+         *     };
+         * });
+         * ```
+         *
+         * @param callable|null $callback The authorizer, or null to remove it.
+         *
+         * @return void
+         *
+         * @throws \LibSQLException If the connection is remote.
+         */
+        public function setAuthorizer(?callable $callback) {}
+
+        /**
+         * Interrupts the queries running on the connection.
+         *
+         * A local query stops at its next step and a query waiting on a remote database is abandoned;
+         * either throws a TimeoutException. Only queries running when it is called are stopped, so it
+         * is called from code that runs during the query, such as another thread. The connection remains
+         * usable afterwards. To bound every query instead, set the "queryTimeout" config option.
+         *
+         * @return void
+         */
+        public function interrupt() {}
+
         /**
          * Closes the database connection.
          * 
@@ -745,6 +1279,124 @@ namespace {
         public function captureIt(string $event_type, ?string $query, ?string $message) {}
     }
 
+    /**
+     * The base class of every exception thrown for a failed database operation.
+     *
+     * The exception code is the extended SQLite result code, or `0` if SQLite did not report one.
+     */
+    class LibSQLException extends \Exception
+    {
+        /**
+         * Returns the primary SQLite result code, or `0` if SQLite did not report one.
+         *
+         * @return int
+         */
+        public function getSqliteCode() {}
+
+        /**
+         * Returns the extended SQLite result code, or `0` if SQLite did not report one.
+         *
+         * @return int
+         */
+        public function getExtendedCode() {}
+
+        /**
+         * Returns the SQL that failed, if the error came from a statement.
+         *
+         * @return string|null
+         */
+        public function getSql() {}
+
+        /**
+         * Returns the mode of the connection, e.g. `local` or `remote`.
+         *
+         * @return string
+         */
+        public function getMode() {}
+    }
+
+    /**
+     * Thrown when a database cannot be opened or reached.
+     */
+    class ConnectionException extends LibSQLException {}
+
+    /**
+     * Thrown when a statement violates a constraint.
+     */
+    class ConstraintViolationException extends LibSQLException {}
+
+    /**
+     * Thrown when a statement violates a `UNIQUE` or `PRIMARY KEY` constraint.
+     */
+    class UniqueConstraintViolationException extends ConstraintViolationException {}
+
+    /**
+     * Thrown when a statement violates a `FOREIGN KEY` constraint.
+     */
+    class ForeignKeyConstraintViolationException extends ConstraintViolationException {}
+
+    /**
+     * Thrown when a statement violates a `NOT NULL` constraint.
+     */
+    class NotNullConstraintViolationException extends ConstraintViolationException {}
+
+    /**
+     * Thrown when a statement violates a `CHECK` constraint.
+     */
+    class CheckConstraintViolationException extends ConstraintViolationException {}
+
+    /**
+     * Thrown when the database or a table is locked by another connection.
+     */
+    class BusyException extends LibSQLException {}
+
+    /**
+     * Thrown when SQL cannot be parsed.
+     */
+    class SyntaxException extends LibSQLException {}
+
+    /**
+     * Thrown when an operation is not authorized or the credentials are rejected.
+     */
+    class AuthException extends LibSQLException {}
+
+    /**
+     * Thrown when syncing an embedded replica or offline writes fails.
+     */
+    class SyncException extends LibSQLException {}
+
+    /**
+     * Thrown when an operation exceeds its time limit.
+     */
+    class TimeoutException extends LibSQLException {}
+
+    /**
+     * Wraps a binary string so it is bound as a BLOB instead of TEXT.
+     */
+    class LibSQLBlob
+    {
+        /**
+         * Creates a new LibSQLBlob instance.
+         *
+         * @param string $data The binary string to bind.
+         */
+        public function __construct(string $data) {}
+
+        /**
+         * Returns the wrapped binary string.
+         *
+         * @return string
+         */
+        public function getData() {}
+
+        /**
+         * Returns the length of the blob in bytes.
+         *
+         * @return int
+         */
+        public function length() {}
+    }
+
     /**
      * Class LibSQLIterator
      *
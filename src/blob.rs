@@ -0,0 +1,36 @@
+#[allow(non_snake_case, deprecated, unused_attributes)]
+#[cfg_attr(windows, feature(abi_vectorcall))]
+use ext_php_rs::{binary::Binary, prelude::*};
+
+/// Wraps a binary string so it is bound as a BLOB instead of TEXT.
+#[php_class]
+pub struct LibSQLBlob {
+    /// The raw bytes of the blob.
+    pub data: Vec<u8>,
+}
+
+#[php_impl]
+impl LibSQLBlob {
+    /// Constructs a new `LibSQLBlob` object.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The binary string to bind.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of LibSQLBlob.
+    pub fn __construct(data: Binary<u8>) -> Self {
+        Self { data: data.into() }
+    }
+
+    /// Returns the wrapped binary string.
+    pub fn get_data(&self) -> Binary<u8> {
+        Binary::from(self.data.clone())
+    }
+
+    /// Returns the length of the blob in bytes.
+    pub fn length(&self) -> usize {
+        self.data.len()
+    }
+}
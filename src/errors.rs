@@ -0,0 +1,403 @@
+#[allow(non_snake_case, deprecated, unused_attributes)]
+#[cfg_attr(windows, feature(abi_vectorcall))]
+use ext_php_rs::{
+    class::RegisteredClass, convert::IntoZval, exception::PhpException, prelude::*,
+    types::ZendObject, zend::ce, zend::ClassEntry,
+};
+
+use crate::CONNECTION_MODE_REGISTRY;
+
+/// Primary SQLite result codes.
+const SQLITE_ERROR: i32 = 1;
+const SQLITE_BUSY: i32 = 5;
+const SQLITE_LOCKED: i32 = 6;
+const SQLITE_INTERRUPT: i32 = 9;
+const SQLITE_CANTOPEN: i32 = 14;
+const SQLITE_CONSTRAINT: i32 = 19;
+const SQLITE_AUTH: i32 = 23;
+const SQLITE_NOTADB: i32 = 26;
+
+/// Extended SQLite result codes of constraint violations.
+const SQLITE_CONSTRAINT_CHECK: i32 = 275;
+const SQLITE_CONSTRAINT_FOREIGNKEY: i32 = 787;
+const SQLITE_CONSTRAINT_NOTNULL: i32 = 1299;
+const SQLITE_CONSTRAINT_PRIMARYKEY: i32 = 1555;
+const SQLITE_CONSTRAINT_UNIQUE: i32 = 2067;
+
+/// The kind of a failure, selecting the exception class it is thrown as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Generic,
+    Connection,
+    Constraint,
+    Unique,
+    ForeignKey,
+    NotNull,
+    Check,
+    Busy,
+    Syntax,
+    Auth,
+    Sync,
+    Timeout,
+}
+
+/// The details exposed by every LibSQL exception.
+#[derive(Debug, Clone, Default)]
+pub struct ErrorInfo {
+    sqlite_code: i32,
+    extended_code: i32,
+    sql: Option<String>,
+    mode: String,
+}
+
+/// A failed database operation, converted into the matching exception class when returned to PHP.
+#[derive(Debug, Clone)]
+pub struct LibSQLError {
+    kind: ErrorKind,
+    message: String,
+    info: ErrorInfo,
+}
+
+impl LibSQLError {
+    /// Creates an error of the given kind without SQLite result codes.
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            info: ErrorInfo::default(),
+        }
+    }
+
+    /// Maps a libsql error to its kind and SQLite result codes.
+    ///
+    /// Local errors carry their result code; errors from remote databases only carry a message,
+    /// so their codes are recovered from the SQLite error text it contains.
+    pub fn from_libsql(error: &libsql::Error) -> Self {
+        let message = error.to_string();
+
+        let (kind, extended_code) = match error {
+            libsql::Error::SqliteFailure(code, sqlite_message) => {
+                let code = refine_code(*code, sqlite_message);
+                (kind_for_code(code, sqlite_message), code)
+            }
+            libsql::Error::ConnectionFailed(_) => (ErrorKind::Connection, 0),
+            libsql::Error::Sync(_) | libsql::Error::Replication(_) => (ErrorKind::Sync, 0),
+            _ => {
+                let code = refine_code(0, &message);
+                match kind_for_code(code, &message) {
+                    ErrorKind::Generic if is_transient_error(&message) => {
+                        (ErrorKind::Connection, code)
+                    }
+                    kind => (kind, code),
+                }
+            }
+        };
+
+        Self {
+            kind,
+            message,
+            info: ErrorInfo {
+                sqlite_code: extended_code & 0xff,
+                extended_code,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Uses the given kind unless a more specific one was recognised.
+    pub fn or_kind(mut self, kind: ErrorKind) -> Self {
+        if self.kind == ErrorKind::Generic {
+            self.kind = kind;
+        }
+        self
+    }
+
+    /// Prefixes the message with what was being done, e.g. `Execution error`.
+    pub fn context(mut self, context: &str) -> Self {
+        self.message = format!("{}: {}", context, self.message);
+        self
+    }
+
+    /// Records the SQL that failed.
+    pub fn with_sql(mut self, sql: &str) -> Self {
+        self.info.sql = Some(sql.to_string());
+        self
+    }
+
+    /// Records the mode of the connection the error occurred on.
+    pub fn with_connection(mut self, conn_id: &str) -> Self {
+        if let Some(mode) = CONNECTION_MODE_REGISTRY.get(conn_id) {
+            self.info.mode = mode;
+        }
+        self
+    }
+
+    /// Records the connection mode directly, for connections that are not registered yet.
+    pub fn with_mode(mut self, mode: &str) -> Self {
+        self.info.mode = mode.to_string();
+        self
+    }
+
+    /// Returns `true` if running the transaction again may succeed, because the database was
+    /// busy or the connection to a remote database failed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.kind, ErrorKind::Busy | ErrorKind::Connection)
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::fmt::Display for LibSQLError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<LibSQLError> for PhpException {
+    fn from(error: LibSQLError) -> Self {
+        let LibSQLError {
+            kind,
+            message,
+            info,
+        } = error;
+        let code = info.extended_code as i64;
+
+        let object = match kind {
+            ErrorKind::Generic => LibSQLException { info }.into_zval(false),
+            ErrorKind::Connection => ConnectionException { info }.into_zval(false),
+            ErrorKind::Constraint => ConstraintViolationException { info }.into_zval(false),
+            ErrorKind::Unique => UniqueConstraintViolationException { info }.into_zval(false),
+            ErrorKind::ForeignKey => {
+                ForeignKeyConstraintViolationException { info }.into_zval(false)
+            }
+            ErrorKind::NotNull => NotNullConstraintViolationException { info }.into_zval(false),
+            ErrorKind::Check => CheckConstraintViolationException { info }.into_zval(false),
+            ErrorKind::Busy => BusyException { info }.into_zval(false),
+            ErrorKind::Syntax => SyntaxException { info }.into_zval(false),
+            ErrorKind::Auth => AuthException { info }.into_zval(false),
+            ErrorKind::Sync => SyncException { info }.into_zval(false),
+            ErrorKind::Timeout => TimeoutException { info }.into_zval(false),
+        };
+
+        match object {
+            Ok(object) => {
+                // The object is created without running a constructor, so let
+                // `Exception::__construct` set the message and code.
+                let _ = object.try_call_method("__construct", vec![&message, &code]);
+                PhpException::default(message).with_object(object)
+            }
+            Err(_) => PhpException::default(message),
+        }
+    }
+}
+
+/// Recovers the extended result code of a constraint violation, busy database or syntax error
+/// from its message when only the primary code, or no code, is known.
+fn refine_code(code: i32, message: &str) -> i32 {
+    if code != 0 && code != SQLITE_CONSTRAINT {
+        return code;
+    }
+
+    let message = message.to_uppercase();
+    let known = [
+        ("SQLITE_CONSTRAINT_PRIMARYKEY", SQLITE_CONSTRAINT_PRIMARYKEY),
+        ("SQLITE_CONSTRAINT_UNIQUE", SQLITE_CONSTRAINT_UNIQUE),
+        ("SQLITE_CONSTRAINT_FOREIGNKEY", SQLITE_CONSTRAINT_FOREIGNKEY),
+        ("SQLITE_CONSTRAINT_NOTNULL", SQLITE_CONSTRAINT_NOTNULL),
+        ("SQLITE_CONSTRAINT_CHECK", SQLITE_CONSTRAINT_CHECK),
+        ("UNIQUE CONSTRAINT FAILED", SQLITE_CONSTRAINT_UNIQUE),
+        (
+            "FOREIGN KEY CONSTRAINT FAILED",
+            SQLITE_CONSTRAINT_FOREIGNKEY,
+        ),
+        ("NOT NULL CONSTRAINT FAILED", SQLITE_CONSTRAINT_NOTNULL),
+        ("CHECK CONSTRAINT FAILED", SQLITE_CONSTRAINT_CHECK),
+        ("SQLITE_CONSTRAINT", SQLITE_CONSTRAINT),
+        ("DATABASE IS LOCKED", SQLITE_BUSY),
+        ("SQLITE_BUSY", SQLITE_BUSY),
+        ("DATABASE TABLE IS LOCKED", SQLITE_LOCKED),
+        ("SQLITE_LOCKED", SQLITE_LOCKED),
+        ("NOT AUTHORIZED", SQLITE_AUTH),
+        ("SQLITE_AUTH", SQLITE_AUTH),
+        ("SQLITE_INTERRUPT", SQLITE_INTERRUPT),
+        ("INTERRUPTED", SQLITE_INTERRUPT),
+    ];
+
+    known
+        .iter()
+        .find(|(pattern, _)| message.contains(pattern))
+        .map(|(_, code)| *code)
+        .unwrap_or(code)
+}
+
+/// Selects the kind of an error from its extended result code.
+fn kind_for_code(code: i32, message: &str) -> ErrorKind {
+    match code {
+        SQLITE_CONSTRAINT_UNIQUE | SQLITE_CONSTRAINT_PRIMARYKEY => ErrorKind::Unique,
+        SQLITE_CONSTRAINT_FOREIGNKEY => ErrorKind::ForeignKey,
+        SQLITE_CONSTRAINT_NOTNULL => ErrorKind::NotNull,
+        SQLITE_CONSTRAINT_CHECK => ErrorKind::Check,
+        _ => match code & 0xff {
+            SQLITE_CONSTRAINT => ErrorKind::Constraint,
+            SQLITE_BUSY | SQLITE_LOCKED => ErrorKind::Busy,
+            SQLITE_INTERRUPT => ErrorKind::Timeout,
+            SQLITE_AUTH => ErrorKind::Auth,
+            SQLITE_CANTOPEN | SQLITE_NOTADB => ErrorKind::Connection,
+            SQLITE_ERROR | 0 if is_syntax_error(message) => ErrorKind::Syntax,
+            0 if is_auth_error(message) => ErrorKind::Auth,
+            _ => ErrorKind::Generic,
+        },
+    }
+}
+
+fn is_syntax_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    [
+        "syntax error",
+        "incomplete input",
+        "unrecognized token",
+        "sql_parse_error",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// Detects remote failures that do not depend on the statement, such as a dropped connection
+/// or an expired stream.
+fn is_transient_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    [
+        "stream expired",
+        "stream not found",
+        "connection reset",
+        "connection refused",
+        "connection closed",
+        "broken pipe",
+        "timed out",
+        "502 bad gateway",
+        "503 service unavailable",
+        "504 gateway timeout",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// Detects rejected credentials of remote databases, which have no SQLite result code.
+///
+/// Only the HTTP status of the failed request is matched, e.g. `status=401 Unauthorized`, since
+/// a bare `401` or `unauthorized` may come from a table name or a value in the message.
+fn is_auth_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    ["status=401", "http status 401", "401 unauthorized"]
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+/// Returns `true` if an exception thrown by PHP code is a `BusyException` or a
+/// `ConnectionException`, after which the transaction may be retried.
+pub fn is_retryable_exception(exception: &ZendObject) -> bool {
+    exception.instance_of(BusyException::get_metadata().ce())
+        || exception.instance_of(ConnectionException::get_metadata().ce())
+}
+
+fn libsql_exception_ce() -> &'static ClassEntry {
+    LibSQLException::get_metadata().ce()
+}
+
+fn constraint_violation_exception_ce() -> &'static ClassEntry {
+    ConstraintViolationException::get_metadata().ce()
+}
+
+/// Declares an exception class exposing the SQLite result codes, the SQL and the connection mode.
+///
+/// Each class implements the accessors itself, because the methods of a parent class cannot
+/// read the data of a subclass instance.
+macro_rules! libsql_exception {
+    ($(#[$meta:meta])* $name:ident extends $parent:path, $stub:literal) => {
+        $(#[$meta])*
+        #[php_class]
+        #[php(extends(ce = $parent, stub = $stub))]
+        #[derive(Default)]
+        pub struct $name {
+            info: ErrorInfo,
+        }
+
+        #[php_impl]
+        impl $name {
+            /// Returns the primary SQLite result code, or `0` if SQLite did not report one.
+            pub fn get_sqlite_code(&self) -> i32 {
+                self.info.sqlite_code
+            }
+
+            /// Returns the extended SQLite result code, or `0` if SQLite did not report one.
+            pub fn get_extended_code(&self) -> i32 {
+                self.info.extended_code
+            }
+
+            /// Returns the SQL that failed, if the error came from a statement.
+            pub fn get_sql(&self) -> Option<String> {
+                self.info.sql.clone()
+            }
+
+            /// Returns the mode of the connection, e.g. `local` or `remote`.
+            pub fn get_mode(&self) -> String {
+                self.info.mode.clone()
+            }
+        }
+    };
+}
+
+libsql_exception!(
+    /// The base class of every exception thrown for a failed database operation.
+    LibSQLException extends ce::exception, "\\Exception"
+);
+libsql_exception!(
+    /// Thrown when a database cannot be opened or reached.
+    ConnectionException extends libsql_exception_ce, "LibSQLException"
+);
+libsql_exception!(
+    /// Thrown when a statement violates a constraint.
+    ConstraintViolationException extends libsql_exception_ce, "LibSQLException"
+);
+libsql_exception!(
+    /// Thrown when a statement violates a `UNIQUE` or `PRIMARY KEY` constraint.
+    UniqueConstraintViolationException extends constraint_violation_exception_ce, "ConstraintViolationException"
+);
+libsql_exception!(
+    /// Thrown when a statement violates a `FOREIGN KEY` constraint.
+    ForeignKeyConstraintViolationException extends constraint_violation_exception_ce, "ConstraintViolationException"
+);
+libsql_exception!(
+    /// Thrown when a statement violates a `NOT NULL` constraint.
+    NotNullConstraintViolationException extends constraint_violation_exception_ce, "ConstraintViolationException"
+);
+libsql_exception!(
+    /// Thrown when a statement violates a `CHECK` constraint.
+    CheckConstraintViolationException extends constraint_violation_exception_ce, "ConstraintViolationException"
+);
+libsql_exception!(
+    /// Thrown when the database or a table is locked by another connection.
+    BusyException extends libsql_exception_ce, "LibSQLException"
+);
+libsql_exception!(
+    /// Thrown when SQL cannot be parsed.
+    SyntaxException extends libsql_exception_ce, "LibSQLException"
+);
+libsql_exception!(
+    /// Thrown when an operation is not authorized or the credentials are rejected.
+    AuthException extends libsql_exception_ce, "LibSQLException"
+);
+libsql_exception!(
+    /// Thrown when syncing an embedded replica or offline writes fails.
+    SyncException extends libsql_exception_ce, "LibSQLException"
+);
+libsql_exception!(
+    /// Thrown when an operation exceeds its time limit.
+    TimeoutException extends libsql_exception_ce, "LibSQLException"
+);
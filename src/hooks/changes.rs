@@ -16,13 +16,7 @@ use ext_php_rs::exception::PhpException;
 ///
 /// Returns a `PhpException` if the connection is not found or an error occurs during retrieval.
 pub fn get_changes(conn_id: String) -> Result<u64, PhpException> {
-    let conn_registry = CONNECTION_REGISTRY.lock().map_err(|e| {
-        let err_msg = format!("Mutex lock error: {}", e);
-        log_error_to_tmp(&err_msg);
-        PhpException::default(err_msg)
-    })?;
-
-    let conn = conn_registry.get(&conn_id).ok_or_else(|| {
+    let conn = CONNECTION_REGISTRY.get(&conn_id).ok_or_else(|| {
         let err_msg = "Connection not found".to_string();
         log_error_to_tmp(&err_msg);
         PhpException::from(err_msg)
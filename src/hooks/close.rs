@@ -1,5 +1,8 @@
 use crate::utils::log_error::log_error_to_tmp;
-use crate::{utils::runtime::runtime, CONNECTION_REGISTRY};
+use crate::{
+    utils::runtime::runtime, CONNECTION_MODE_REGISTRY, CONNECTION_REGISTRY,
+    STATEMENT_CACHE_REGISTRY,
+};
 use ext_php_rs::exception::PhpException;
 
 /// Disconnects the specified connection.
@@ -17,13 +20,10 @@ use ext_php_rs::exception::PhpException;
 /// Returns a `PhpException` if the connection ID is not found or
 /// an error occurs during disconnection.
 pub fn disconnect(conn_id: String) -> Result<(), PhpException> {
-    let mut registry = CONNECTION_REGISTRY.lock().map_err(|e| {
-        let err_msg = format!("Mutex lock error: {}", e);
-        log_error_to_tmp(&err_msg);
-        PhpException::default(err_msg)
-    })?;
+    STATEMENT_CACHE_REGISTRY.remove(&conn_id);
+    CONNECTION_MODE_REGISTRY.remove(&conn_id);
 
-    if let Some(conn) = registry.remove(&conn_id) {
+    if let Some(conn) = CONNECTION_REGISTRY.remove(&conn_id) {
         runtime().block_on(async { conn.reset().await });
         Ok(())
     } else {
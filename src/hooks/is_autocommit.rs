@@ -16,9 +16,7 @@ use crate::{utils::runtime::runtime, CONNECTION_REGISTRY};
 ///
 /// Returns a `PhpException` if the connection is not found or an error occurs during retrieval.
 pub fn get_is_autocommit(conn_id: String) -> Result<bool, PhpException> {
-    let conn_registry = CONNECTION_REGISTRY.lock().unwrap();
-
-    let conn = conn_registry
+    let conn = CONNECTION_REGISTRY
         .get(&conn_id)
         .ok_or_else(|| PhpException::from("Connection not found"))?;
 
@@ -1,6 +1,6 @@
 use std::path::Path;
 
-use crate::CONNECTION_REGISTRY;
+use crate::{errors::LibSQLError, CONNECTION_REGISTRY};
 use ext_php_rs::prelude::PhpException;
 
 #[derive(Debug, Clone)]
@@ -34,18 +34,21 @@ impl<'a> ext_php_rs::convert::FromZval<'a> for ExtensionParams {
 }
 
 pub fn enable_load_extension(conn_id: String, onoff: Option<bool>) -> Result<(), PhpException> {
-    let conn_registry = CONNECTION_REGISTRY.lock().unwrap();
-    let conn = conn_registry
+    let conn = CONNECTION_REGISTRY
         .get(&conn_id)
         .ok_or_else(|| PhpException::from("Connection not found"))?;
 
-    if Some(onoff.unwrap_or(false)).is_some() {
-        conn.load_extension_enable().unwrap();
+    let result = if Some(onoff.unwrap_or(false)).is_some() {
+        conn.load_extension_enable()
     } else {
-        conn.load_extension_disable().unwrap();
-    }
+        conn.load_extension_disable()
+    };
 
-    Ok(())
+    result.map_err(|e| {
+        LibSQLError::from_libsql(&e)
+            .with_connection(&conn_id)
+            .into()
+    })
 }
 
 pub fn load_extension(
@@ -53,11 +56,17 @@ pub fn load_extension(
     dylib_path: &Path,
     entry_point: Option<&str>,
 ) -> Result<(), PhpException> {
-    let conn_registry = CONNECTION_REGISTRY.lock().unwrap();
-    let conn = conn_registry
+    let conn = CONNECTION_REGISTRY
         .get(&conn_id)
         .ok_or_else(|| PhpException::from("Connection not found"))?;
 
-    conn.load_extension(dylib_path, entry_point).unwrap();
-    Ok(())
+    conn.load_extension(dylib_path, entry_point).map_err(|e| {
+        LibSQLError::from_libsql(&e)
+            .context(&format!(
+                "Failed to load extension {}",
+                dylib_path.display()
+            ))
+            .with_connection(&conn_id)
+            .into()
+    })
 }
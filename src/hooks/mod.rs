@@ -2,7 +2,11 @@ pub mod changes;
 pub mod close;
 pub mod is_autocommit;
 pub mod load_extensions;
+pub mod request_state;
 pub mod use_exec;
 pub mod use_exec_batch;
+pub mod use_execute_many;
+pub mod use_insert_many;
 pub mod use_query;
+pub mod use_transactional;
 pub mod version;
@@ -0,0 +1,205 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    utils::{
+        fork::release_after_fork, guard::LockExt, log_error::log_error_to_tmp, native_handle,
+        query_params::DateTimeBinding, runtime::runtime,
+    },
+    CONNECTION_LIFECYCLE_REGISTRY, CONNECTION_MODE_REGISTRY, CONNECTION_REGISTRY,
+    DATETIME_BINDING_REGISTRY, INTERRUPT_REGISTRY, OFFLINE_CONNECTION_REGISTRY,
+    STATEMENT_CACHE_REGISTRY, STATEMENT_REGISTRY, TRANSACTION_REGISTRY,
+};
+
+/// How a connection is treated when the request that opened it ends.
+#[derive(Clone, Default)]
+pub struct ConnectionLifecycle {
+    /// Whether the connection stays open across requests.
+    pub persistent: bool,
+
+    /// Whether to sync the embedded replica, or flush the offline write queue, when a request ends.
+    pub sync_on_request_end: bool,
+
+    /// The configured `dateTimeBinding`, restored when a request ends.
+    pub datetime_binding: DateTimeBinding,
+
+    /// The database of an embedded replica, used to sync it.
+    pub database: Option<Arc<libsql::Database>>,
+}
+
+/// The kind of a registered handle.
+#[derive(Clone, Copy)]
+pub enum Handle {
+    Connection,
+    Transaction,
+    Statement,
+}
+
+/// The handles opened by the request running on a thread.
+#[derive(Default)]
+struct RequestHandles {
+    connections: HashSet<String>,
+    transactions: HashSet<String>,
+    statements: HashSet<String>,
+}
+
+impl RequestHandles {
+    fn of_kind(&mut self, handle: Handle) -> &mut HashSet<String> {
+        match handle {
+            Handle::Connection => &mut self.connections,
+            Handle::Transaction => &mut self.transactions,
+            Handle::Statement => &mut self.statements,
+        }
+    }
+}
+
+thread_local! {
+    // Requests of a threaded PHP build run on their own thread, so each only releases what it opened.
+    static REQUEST_HANDLES: RefCell<RequestHandles> = RefCell::default();
+
+    // Like PHP's own persistent resources, persistent connections are reused by the requests of
+    // the thread that opened them, keyed by their configuration.
+    static PERSISTENT_CONNECTIONS: RefCell<HashMap<String, String>> = RefCell::default();
+}
+
+/// Records a handle opened by the current request.
+///
+/// # Arguments
+///
+/// * `handle` - The kind of the handle.
+/// * `id` - The ID of the handle in its registry.
+pub fn track(handle: Handle, id: &str) {
+    let _ = REQUEST_HANDLES.try_with(|handles| {
+        handles.borrow_mut().of_kind(handle).insert(id.to_string());
+    });
+}
+
+/// Forgets a handle that was released explicitly.
+///
+/// # Arguments
+///
+/// * `handle` - The kind of the handle.
+/// * `id` - The ID of the handle in its registry.
+pub fn untrack(handle: Handle, id: &str) {
+    let _ = REQUEST_HANDLES.try_with(|handles| {
+        handles.borrow_mut().of_kind(handle).remove(id);
+    });
+}
+
+/// Finds the persistent connection the current thread opened with a configuration.
+///
+/// # Arguments
+///
+/// * `key` - The normalized configuration of the connection.
+///
+/// # Returns
+///
+/// The ID of the connection, unless none was opened or it was closed since.
+pub fn find_persistent(key: &str) -> Option<String> {
+    PERSISTENT_CONNECTIONS
+        .try_with(|connections| connections.borrow().get(key).cloned())
+        .ok()
+        .flatten()
+        // A connection closed explicitly, or inherited from the parent of a fork, is gone
+        .filter(|conn_id| CONNECTION_LIFECYCLE_REGISTRY.contains(conn_id))
+}
+
+/// Records a persistent connection, to be reused when the same configuration is opened again.
+///
+/// # Arguments
+///
+/// * `key` - The normalized configuration of the connection.
+/// * `conn_id` - The ID of the connection.
+pub fn register_persistent(key: String, conn_id: &str) {
+    let _ = PERSISTENT_CONNECTIONS.try_with(|connections| {
+        connections.borrow_mut().insert(key, conn_id.to_string());
+    });
+}
+
+/// Forgets a persistent connection that was closed.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection.
+pub fn forget_persistent(conn_id: &str) {
+    let _ = PERSISTENT_CONNECTIONS.try_with(|connections| {
+        connections.borrow_mut().retain(|_, id| id != conn_id);
+    });
+}
+
+/// Releases everything the current request left open.
+///
+/// Open transactions are rolled back and prepared statements are finalized. Connections
+/// configured with `syncOnRequestEnd` are synced, then every connection that is not
+/// `persistent` is closed. Failures are logged, since there is no request left to report
+/// them to.
+pub fn reset_request_state() {
+    release_after_fork();
+    native_handle::release_callbacks();
+
+    let Ok(handles) = REQUEST_HANDLES.try_with(|handles| handles.take()) else {
+        return;
+    };
+
+    for trx_id in handles.transactions {
+        if let Some(handle) = TRANSACTION_REGISTRY.remove(&trx_id) {
+            if let Err(e) = runtime().block_on(async { handle.trx.rollback().await }) {
+                log_error_to_tmp(&format!("Failed to roll back open transaction: {}", e));
+            }
+        }
+    }
+
+    for stmt_id in handles.statements {
+        if let Some(stmt) = STATEMENT_REGISTRY.remove(&stmt_id) {
+            stmt.lock_or_recover().finalize();
+        }
+    }
+
+    for conn_id in handles.connections {
+        let Some(lifecycle) = CONNECTION_LIFECYCLE_REGISTRY.get(&conn_id) else {
+            continue;
+        };
+
+        if lifecycle.sync_on_request_end {
+            sync(&conn_id, &lifecycle);
+        }
+
+        if lifecycle.persistent {
+            DATETIME_BINDING_REGISTRY.insert(conn_id.clone(), lifecycle.datetime_binding.clone());
+            track(Handle::Connection, &conn_id);
+        } else {
+            close(&conn_id);
+        }
+    }
+}
+
+/// Syncs an embedded replica, or flushes the offline write queue of a connection.
+fn sync(conn_id: &str, lifecycle: &ConnectionLifecycle) {
+    if let Some(db) = &lifecycle.database {
+        if let Err(e) = runtime().block_on(async { db.sync().await }) {
+            log_error_to_tmp(&format!("Sync at the end of the request failed: {}", e));
+        }
+    } else if let Some(offline_conn) = OFFLINE_CONNECTION_REGISTRY.get(conn_id) {
+        if let Err(e) = offline_conn.manual_sync() {
+            log_error_to_tmp(&format!("Sync at the end of the request failed: {}", e));
+        }
+    }
+}
+
+/// Closes a connection and forgets everything registered for it.
+fn close(conn_id: &str) {
+    native_handle::release(conn_id);
+    CONNECTION_LIFECYCLE_REGISTRY.remove(conn_id);
+    CONNECTION_MODE_REGISTRY.remove(conn_id);
+    INTERRUPT_REGISTRY.remove(conn_id);
+    DATETIME_BINDING_REGISTRY.remove(conn_id);
+    STATEMENT_CACHE_REGISTRY.remove(conn_id);
+    OFFLINE_CONNECTION_REGISTRY.remove(conn_id);
+
+    if let Some(conn) = CONNECTION_REGISTRY.remove(conn_id) {
+        runtime().block_on(async { conn.reset().await });
+    }
+}
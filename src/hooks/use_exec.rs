@@ -1,7 +1,15 @@
+use std::time::Duration;
+
 use ext_php_rs::exception::PhpException;
 
 use crate::{
-    utils::{log_error::log_error_to_tmp, query_params::QueryParameters, runtime::runtime},
+    errors::LibSQLError,
+    utils::{
+        interrupt,
+        log_error::log_error_to_tmp,
+        query_params::{ParameterSpec, QueryParameters},
+        statement_cache::with_cached_statement,
+    },
     CONNECTION_REGISTRY,
 };
 
@@ -12,6 +20,7 @@ use crate::{
 /// * `conn_id` - The ID of the connection.
 /// * `stmt` - The SQL statement to execute.
 /// * `parameters` - The parameters to bind to the statement.
+/// * `timeout` - The timeout of the statement, or `None` for the timeout of the connection.
 ///
 /// # Returns
 ///
@@ -24,32 +33,61 @@ pub fn exec(
     conn_id: String,
     stmt: &str,
     parameters: Option<QueryParameters>,
+    timeout: Option<Duration>,
 ) -> Result<u64, PhpException> {
-    let conn_registry = CONNECTION_REGISTRY.lock().map_err(|e| {
-        let err_msg = format!("Mutex lock error: {}", e);
-        log_error_to_tmp(&err_msg);
-        PhpException::default(err_msg)
-    })?;
-
-    let conn = conn_registry.get(&conn_id).ok_or_else(|| {
+    let conn = CONNECTION_REGISTRY.get(&conn_id).ok_or_else(|| {
         let err_msg = "Connection not found".to_string();
         log_error_to_tmp(&err_msg);
         PhpException::from(err_msg)
     })?;
 
-    let params = if let Some(p) = parameters {
-        p.to_params()
-    } else {
-        libsql::params::Params::None
-    };
-
-    let result = runtime().block_on(async { conn.execute(stmt, params).await });
-    match result {
-        Ok(eresult) => Ok(eresult),
-        Err(e) => {
-            let err_msg = format!("Execution error: {}", e);
-            log_error_to_tmp(&err_msg);
-            Err(PhpException::from(err_msg))
-        }
-    }
+    exec_on(&conn, &conn_id, stmt, parameters, timeout)
+}
+
+/// Executes an SQL statement with parameters on a connection handle, such as the one of a
+/// transaction, through the statement cache of its connection.
+///
+/// A local transaction runs on the handle of its connection, so the cached statements run
+/// inside it; remote connections do not cache statements.
+///
+/// # Arguments
+///
+/// * `conn` - The connection handle to execute the statement on.
+/// * `conn_id` - The ID of the connection whose statement cache is used.
+/// * `stmt` - The SQL statement to execute.
+/// * `parameters` - The parameters to bind to the statement.
+/// * `timeout` - The timeout of the statement, or `None` for the timeout of the connection.
+///
+/// # Returns
+///
+/// Returns the number of rows affected by the statement execution.
+pub fn exec_on(
+    conn: &libsql::Connection,
+    conn_id: &str,
+    stmt: &str,
+    parameters: Option<QueryParameters>,
+    timeout: Option<Duration>,
+) -> Result<u64, PhpException> {
+    let (stmt, parameters) = parameters.unwrap_or_default().expand_lists(stmt)?;
+
+    with_cached_statement(conn_id, conn, &stmt, |statement| {
+        let params = parameters.to_params_for(&ParameterSpec::from_statement(statement, &stmt))?;
+
+        interrupt::run(conn_id, conn, timeout, async {
+            statement
+                .execute(params)
+                .await
+                .map(|rows| rows as u64)
+                .map_err(|e| exec_error(&e, &stmt, conn_id))
+        })
+    })
+}
+
+fn exec_error(error: &libsql::Error, stmt: &str, conn_id: &str) -> PhpException {
+    let error = LibSQLError::from_libsql(error)
+        .context("Execution error")
+        .with_sql(stmt)
+        .with_connection(conn_id);
+    log_error_to_tmp(error.message());
+    error.into()
 }
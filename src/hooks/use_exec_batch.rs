@@ -1,6 +1,10 @@
 use ext_php_rs::exception::PhpException;
 
-use crate::{utils::runtime::runtime, CONNECTION_REGISTRY};
+use crate::{
+    errors::LibSQLError,
+    utils::{interrupt, statement_cache::invalidate_on_schema_change},
+    CONNECTION_REGISTRY,
+};
 
 /// Executes a batch of SQL statements on the specified connection.
 ///
@@ -17,15 +21,20 @@ use crate::{utils::runtime::runtime, CONNECTION_REGISTRY};
 ///
 /// Returns a `PhpException` if the connection is not found or an error occurs during execution.
 pub fn exec_batch(conn_id: String, stmt: &str) -> Result<bool, PhpException> {
-    let conn_registry = CONNECTION_REGISTRY.lock().unwrap();
-
-    let conn = conn_registry
+    let conn = CONNECTION_REGISTRY
         .get(&conn_id)
         .ok_or_else(|| PhpException::from("Connection not found"))?;
 
-    let result = runtime().block_on(async { conn.execute_batch(stmt).await });
-    match result {
-        Ok(_) => Ok(true),
-        Err(e) => Err(PhpException::from(e.to_string())),
-    }
+    let result = interrupt::run(&conn_id, &conn, None, async {
+        conn.execute_batch(stmt).await.map_err(|e| {
+            PhpException::from(
+                LibSQLError::from_libsql(&e)
+                    .with_sql(stmt)
+                    .with_connection(&conn_id),
+            )
+        })
+    });
+    invalidate_on_schema_change(&conn_id, stmt);
+
+    result.map(|_| true)
 }
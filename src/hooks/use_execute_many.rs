@@ -0,0 +1,188 @@
+use ext_php_rs::{exception::PhpException, types::Zval};
+
+use crate::{
+    errors::LibSQLError,
+    utils::{
+        execute_many_result::ExecuteManyResult,
+        interrupt,
+        log_error::log_error_to_tmp,
+        query_params::{DateTimeBinding, ParameterSpec, QueryParameters},
+        runtime::runtime,
+        statement_cache::{invalidate_on_schema_change, with_cached_statement},
+    },
+    CONNECTION_MODE_REGISTRY, CONNECTION_REGISTRY,
+};
+
+/// Converts every parameter set of a PHP iterable.
+///
+/// Generators are consumed completely before any statement runs, so no registry lock is held
+/// while PHP code produces the parameter sets.
+///
+/// # Arguments
+///
+/// * `param_sets` - An array or `Traversable` of parameter arrays.
+/// * `conn_id` - The ID of the connection the parameters are bound on.
+///
+/// # Returns
+///
+/// The parameter sets in iteration order, or a `PhpException` if an element is not an array.
+pub fn collect_parameter_sets(
+    param_sets: &Zval,
+    conn_id: &str,
+) -> Result<Vec<QueryParameters>, PhpException> {
+    let dates = DateTimeBinding::of_connection(conn_id);
+    let mut iterable = param_sets.iterable().ok_or_else(|| {
+        PhpException::default("executeMany expects an iterable of parameter sets".to_string())
+    })?;
+    let iter = iterable.iter().ok_or_else(|| {
+        PhpException::default("Failed to iterate over the parameter sets".to_string())
+    })?;
+
+    iter.enumerate()
+        .map(|(idx, (_, params))| {
+            QueryParameters::from_php(params, &dates).ok_or_else(|| {
+                PhpException::default(format!("Parameter set {} must be an array", idx))
+            })
+        })
+        .collect()
+}
+
+/// Runs an SQL statement once per parameter set on the specified connection.
+///
+/// The statement is taken from the connection's prepared statement cache, except on remote
+/// connections, where it is prepared once the implicit transaction is open.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection.
+/// * `stmt` - The SQL statement to execute.
+/// * `param_sets` - The parameter sets, one per execution.
+///
+/// # Returns
+///
+/// Returns the total number of rows changed and the last insert rowid of every execution.
+pub fn execute_many_sql(
+    conn_id: String,
+    stmt: &str,
+    param_sets: Vec<QueryParameters>,
+) -> Result<ExecuteManyResult, PhpException> {
+    let conn = CONNECTION_REGISTRY
+        .get(&conn_id)
+        .ok_or_else(|| PhpException::from("Connection not found"))?;
+
+    if is_remote(&conn_id) {
+        return execute_many(&conn_id, &conn, None, stmt, param_sets);
+    }
+
+    with_cached_statement(&conn_id, &conn, stmt, |statement| {
+        execute_many(&conn_id, &conn, Some(statement), stmt, param_sets)
+    })
+}
+
+/// Runs a statement once per parameter set.
+///
+/// Every parameter set is validated before the first execution. Unless a transaction is
+/// already open, the executions run inside an implicit transaction that is rolled back if
+/// any of them fails. The query timeout of the connection applies to the executions as a whole.
+///
+/// A remote statement runs on the stream it was prepared on, and closes that stream after
+/// running if it was prepared outside a transaction. Remote statements are therefore prepared
+/// again once the transaction is open, so every execution runs inside it.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection.
+/// * `conn` - The connection to run the statement on.
+/// * `stmt` - The statement prepared from `sql`, or `None` to prepare it when it runs.
+/// * `sql` - The SQL of the statement.
+/// * `param_sets` - The parameter sets, one per execution.
+///
+/// # Returns
+///
+/// Returns the total number of rows changed and the last insert rowid of every execution.
+pub fn execute_many(
+    conn_id: &str,
+    conn: &libsql::Connection,
+    stmt: Option<&mut libsql::Statement>,
+    sql: &str,
+    param_sets: Vec<QueryParameters>,
+) -> Result<ExecuteManyResult, PhpException> {
+    let spec = match &stmt {
+        Some(stmt) => ParameterSpec::from_statement(stmt, sql),
+        None => ParameterSpec::from_sql(sql),
+    };
+    let params = param_sets
+        .iter()
+        .map(|params| params.to_params_for(&spec))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let stmt = stmt.filter(|_| !is_remote(conn_id));
+    let implicit_transaction = conn.is_autocommit();
+
+    if implicit_transaction {
+        runtime()
+            .block_on(async { conn.execute("BEGIN", ()).await })
+            .map_err(|e| execution_error(&e, sql, conn_id))?;
+    }
+
+    let executed = interrupt::run(conn_id, conn, None, async {
+        execute_each(conn, stmt, sql, params)
+            .await
+            .map_err(|e| execution_error(&e, sql, conn_id))
+    });
+
+    if implicit_transaction {
+        match executed {
+            Ok(_) => {
+                runtime()
+                    .block_on(async { conn.execute("COMMIT", ()).await })
+                    .map_err(|e| execution_error(&e, sql, conn_id))?;
+            }
+            Err(_) => {
+                let _ = runtime().block_on(async { conn.execute("ROLLBACK", ()).await });
+            }
+        }
+    }
+    invalidate_on_schema_change(conn_id, sql);
+
+    executed
+}
+
+fn execution_error(error: &libsql::Error, sql: &str, conn_id: &str) -> PhpException {
+    let error = LibSQLError::from_libsql(error)
+        .context("Execution error")
+        .with_sql(sql)
+        .with_connection(conn_id);
+    log_error_to_tmp(error.message());
+    error.into()
+}
+
+/// Runs a statement once per set of parameters, stopping at the first failure.
+async fn execute_each(
+    conn: &libsql::Connection,
+    stmt: Option<&mut libsql::Statement>,
+    sql: &str,
+    params: Vec<libsql::params::Params>,
+) -> Result<ExecuteManyResult, libsql::Error> {
+    let mut prepared = None;
+    let stmt = match stmt {
+        Some(stmt) => stmt,
+        None => prepared.insert(conn.prepare(sql).await?),
+    };
+
+    let mut result = ExecuteManyResult::default();
+    for params in params {
+        let executed = stmt.execute(params).await;
+        stmt.reset();
+
+        result.rows_changed += executed? as u64;
+        result.last_insert_ids.push(conn.last_insert_rowid());
+    }
+
+    Ok(result)
+}
+
+/// Returns `true` if the connection is a remote connection.
+fn is_remote(conn_id: &str) -> bool {
+    CONNECTION_MODE_REGISTRY.get(conn_id).as_deref() == Some("remote")
+}
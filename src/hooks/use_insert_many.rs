@@ -0,0 +1,509 @@
+use ext_php_rs::{
+    exception::PhpException,
+    types::{ArrayKey, ZendHashTable, Zval},
+};
+
+use crate::{
+    errors::LibSQLError,
+    providers::sqld_offline_write::OfflineWriteConnection,
+    utils::{
+        insert_many_result::InsertManyResult,
+        log_error::log_error_to_tmp,
+        query_params::{DateTimeBinding, QueryParameters, QueryValue, MAX_VARIABLES},
+        runtime::runtime,
+        sql_lexer::quote_identifier,
+    },
+    CONNECTION_REGISTRY,
+};
+
+/// Options controlling how `insertMany` builds its statements.
+#[derive(Debug, Default)]
+pub struct InsertManyOptions {
+    /// Columns of the unique constraint to upsert on; empty for a plain insert.
+    conflict: Vec<String>,
+    /// Columns overwritten on conflict; `None` updates every inserted non-conflict column.
+    update: Option<Vec<String>>,
+    /// Maximum number of rows per statement.
+    chunk_size: Option<usize>,
+}
+
+impl InsertManyOptions {
+    /// Reads the `conflict`, `update` and `chunkSize` options.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The options array given to `insertMany`, if any.
+    ///
+    /// # Returns
+    ///
+    /// The parsed options, or a `PhpException` if an option has the wrong type.
+    pub fn from_options(options: Option<&ZendHashTable>) -> Result<Self, PhpException> {
+        let Some(options) = options else {
+            return Ok(Self::default());
+        };
+
+        let conflict = match options.get("conflict") {
+            Some(columns) => column_list("conflict", columns)?,
+            None => Vec::new(),
+        };
+
+        let update = match options.get("update") {
+            Some(columns) => Some(column_list("update", columns)?),
+            None => None,
+        };
+
+        if conflict.is_empty() && update.is_some() {
+            return Err(PhpException::default(
+                "The update option requires conflict columns".to_string(),
+            ));
+        }
+
+        let chunk_size = match options.get("chunkSize") {
+            Some(size) => match size.long() {
+                Some(size) if size > 0 => Some(size as usize),
+                _ => {
+                    return Err(PhpException::default(
+                        "The chunkSize option must be a positive integer".to_string(),
+                    ))
+                }
+            },
+            None => None,
+        };
+
+        Ok(Self {
+            conflict,
+            update,
+            chunk_size,
+        })
+    }
+}
+
+/// The statements of a bulk insert, built before anything is executed.
+pub struct InsertPlan {
+    /// One multi-row statement per chunk.
+    chunks: Vec<InsertChunk>,
+}
+
+/// A multi-row statement of a bulk insert.
+struct InsertChunk {
+    sql: String,
+    params: QueryParameters,
+    /// For an upsert, the query counting the rows of the chunk that are inserted rather than
+    /// updated, run just before the statement.
+    new_rows: Option<(String, QueryParameters)>,
+}
+
+impl InsertPlan {
+    /// Builds chunked multi-row `INSERT` statements for the given rows.
+    ///
+    /// Every row must be an array keyed by column name, with the same columns as the first row.
+    /// Rows are converted before any statement runs, so generators are consumed up front.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The table to insert into, optionally qualified by a schema name.
+    /// * `rows` - An array or `Traversable` of rows.
+    /// * `options` - The conflict handling and chunking options.
+    /// * `dates` - How the connection binds `DateTimeInterface` values.
+    ///
+    /// # Returns
+    ///
+    /// The plan, or a `PhpException` if a row is invalid.
+    pub fn build(
+        table: &str,
+        rows: &Zval,
+        options: InsertManyOptions,
+        dates: &DateTimeBinding,
+    ) -> Result<Self, PhpException> {
+        let rows = collect_rows(rows, dates)?;
+
+        let table = table
+            .split('.')
+            .map(quote_identifier)
+            .collect::<Vec<_>>()
+            .join(".");
+
+        let Some((columns, _)) = rows.first() else {
+            return Ok(Self { chunks: Vec::new() });
+        };
+        let columns = columns.clone();
+
+        if columns.len() > MAX_VARIABLES {
+            return Err(PhpException::default(format!(
+                "Rows have {} columns, more than the limit of {} parameters per statement",
+                columns.len(),
+                MAX_VARIABLES
+            )));
+        }
+
+        for column in options
+            .conflict
+            .iter()
+            .chain(options.update.iter().flatten())
+        {
+            if !columns.contains(column) {
+                return Err(PhpException::default(format!(
+                    "Column '{}' is not part of the inserted rows",
+                    column
+                )));
+            }
+        }
+
+        let update = options.update.clone().unwrap_or_else(|| {
+            columns
+                .iter()
+                .filter(|column| !options.conflict.contains(column))
+                .cloned()
+                .collect()
+        });
+        let conflict_clause = conflict_clause(&options.conflict, &update);
+        let conflict_positions = options
+            .conflict
+            .iter()
+            .filter_map(|column| columns.iter().position(|c| c == column))
+            .collect::<Vec<_>>();
+
+        let rows_per_chunk = (MAX_VARIABLES / columns.len())
+            .min(options.chunk_size.unwrap_or(usize::MAX))
+            .max(1);
+        let column_list = columns
+            .iter()
+            .map(|column| quote_identifier(column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let row_placeholders = format!("({})", vec!["?"; columns.len()].join(", "));
+
+        let mut chunks = Vec::new();
+        for chunk in rows.chunks(rows_per_chunk) {
+            let sql = format!(
+                "INSERT INTO {} ({}) VALUES {}{}",
+                table,
+                column_list,
+                vec![row_placeholders.as_str(); chunk.len()].join(", "),
+                conflict_clause
+            );
+
+            let values = chunk
+                .iter()
+                .flat_map(|(_, values)| values.iter().cloned())
+                .collect::<Vec<_>>();
+            let params = QueryParameters {
+                positional: Some(values),
+                ..Default::default()
+            };
+
+            let new_rows = (!options.conflict.is_empty())
+                .then(|| new_rows_query(&table, &options.conflict, &conflict_positions, chunk));
+
+            chunks.push(InsertChunk {
+                sql,
+                params,
+                new_rows,
+            });
+        }
+
+        Ok(Self { chunks })
+    }
+}
+
+/// Runs a bulk insert on the specified connection.
+///
+/// Unless a transaction is already open, the chunks run inside an implicit transaction that is
+/// rolled back if any of them fails.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection.
+/// * `plan` - The statements to run.
+///
+/// # Returns
+///
+/// Returns the number of rows inserted and the number of rows updated.
+pub fn insert_many(conn_id: String, plan: InsertPlan) -> Result<InsertManyResult, PhpException> {
+    let conn = CONNECTION_REGISTRY
+        .get(&conn_id)
+        .ok_or_else(|| PhpException::from("Connection not found"))?;
+
+    if plan.chunks.is_empty() {
+        return Ok(InsertManyResult::default());
+    }
+
+    let chunks = plan
+        .chunks
+        .into_iter()
+        .map(|chunk| {
+            let new_rows = match chunk.new_rows {
+                Some((sql, params)) => Some((sql, params.to_params()?)),
+                None => None,
+            };
+            Ok((chunk.sql, chunk.params.to_params()?, new_rows))
+        })
+        .collect::<Result<Vec<_>, PhpException>>()?;
+
+    let implicit_transaction = conn.is_autocommit();
+
+    let result = runtime().block_on(async {
+        if implicit_transaction {
+            conn.execute("BEGIN", ()).await?;
+        }
+
+        let run = async {
+            let mut result = InsertManyResult::default();
+            for (sql, params, new_rows) in chunks {
+                let inserted = match new_rows {
+                    Some((sql, params)) => Some(count(conn.query(&sql, params).await?).await?),
+                    None => None,
+                };
+                result.add_chunk(conn.execute(&sql, params).await?, inserted);
+            }
+
+            Ok::<_, libsql::Error>(result)
+        };
+
+        match run.await {
+            Ok(result) => {
+                if implicit_transaction {
+                    conn.execute("COMMIT", ()).await?;
+                }
+                Ok(result)
+            }
+            Err(e) => {
+                if implicit_transaction {
+                    let _ = conn.execute("ROLLBACK", ()).await;
+                }
+                Err(e)
+            }
+        }
+    });
+
+    result.map_err(|e: libsql::Error| {
+        let error = LibSQLError::from_libsql(&e)
+            .context("Execution error")
+            .with_connection(&conn_id);
+        log_error_to_tmp(error.message());
+        error.into()
+    })
+}
+
+/// Runs a bulk insert on an offline write connection.
+///
+/// Every chunk is applied to the local database and queued for sync as its own operation.
+///
+/// # Arguments
+///
+/// * `offline_conn` - The offline write connection.
+/// * `plan` - The statements to run.
+///
+/// # Returns
+///
+/// Returns the number of rows inserted and the number of rows updated.
+pub fn insert_many_offline(
+    offline_conn: &OfflineWriteConnection,
+    plan: InsertPlan,
+) -> Result<InsertManyResult, PhpException> {
+    let mut result = InsertManyResult::default();
+    for chunk in plan.chunks {
+        let inserted = match chunk.new_rows {
+            Some((sql, params)) => {
+                let rows = offline_conn.query(&sql, params.to_params()?, None)?;
+                Some(
+                    runtime()
+                        .block_on(count(rows))
+                        .map_err(|e| PhpException::from(e.to_string()))?,
+                )
+            }
+            None => None,
+        };
+        result.add_chunk(
+            offline_conn.execute(&chunk.sql, Some(chunk.params))?,
+            inserted,
+        );
+    }
+
+    Ok(result)
+}
+
+/// Reads the count returned by a `new_rows_query`.
+async fn count(mut rows: libsql::Rows) -> Result<u64, libsql::Error> {
+    let count = match rows.next().await? {
+        Some(row) => row.get::<i64>(0)?,
+        None => 0,
+    };
+
+    Ok(count.max(0) as u64)
+}
+
+/// Converts every row of a PHP iterable into its column names and values.
+fn collect_rows(
+    rows: &Zval,
+    dates: &DateTimeBinding,
+) -> Result<Vec<(Vec<String>, Vec<QueryValue>)>, PhpException> {
+    let mut iterable = rows.iterable().ok_or_else(|| {
+        PhpException::default("insertMany expects an iterable of rows".to_string())
+    })?;
+    let iter = iterable
+        .iter()
+        .ok_or_else(|| PhpException::default("Failed to iterate over the rows".to_string()))?;
+
+    let mut collected: Vec<(Vec<String>, Vec<QueryValue>)> = Vec::new();
+
+    for (idx, (_, row)) in iter.enumerate() {
+        let row = row.array().ok_or_else(|| {
+            PhpException::default(format!("Row {} must be an array of column values", idx))
+        })?;
+
+        let mut columns = Vec::new();
+        let mut values = Vec::new();
+        for (key, value) in row.iter() {
+            if matches!(key, ArrayKey::Long(_)) {
+                return Err(PhpException::default(format!(
+                    "Row {} must be keyed by column name",
+                    idx
+                )));
+            }
+
+            let value = QueryValue::from_php(value, dates).map_err(|e| {
+                PhpException::default(format!("Row {}, column '{}': {}", idx, key, e))
+            })?;
+            columns.push(key.to_string());
+            values.push(value);
+        }
+
+        if columns.is_empty() {
+            return Err(PhpException::default(format!("Row {} has no columns", idx)));
+        }
+
+        // Later rows may list their columns in any order; align them with the first row.
+        if let Some((first_columns, _)) = collected.first() {
+            if first_columns.len() != columns.len() {
+                return Err(mismatched_columns(idx));
+            }
+
+            let mut aligned = Vec::with_capacity(values.len());
+            for column in first_columns {
+                let position = columns
+                    .iter()
+                    .position(|c| c == column)
+                    .ok_or_else(|| mismatched_columns(idx))?;
+                aligned.push(values[position].clone());
+            }
+            values = aligned;
+            columns = first_columns.clone();
+        }
+
+        collected.push((columns, values));
+    }
+
+    Ok(collected)
+}
+
+fn mismatched_columns(idx: usize) -> PhpException {
+    PhpException::default(format!(
+        "Row {} has different columns than the first row",
+        idx
+    ))
+}
+
+/// Builds the query counting the rows of an upsert chunk that are inserted.
+///
+/// SQLite inserts a row unless its conflict key is already in the table, or on an earlier row
+/// of the same statement; the other rows are updated, or skipped by `DO NOTHING`. A key with a
+/// `NULL` never conflicts.
+fn new_rows_query(
+    table: &str,
+    conflict: &[String],
+    positions: &[usize],
+    chunk: &[(Vec<String>, Vec<QueryValue>)],
+) -> (String, QueryParameters) {
+    let keys = (0..conflict.len())
+        .map(|i| format!("k{}", i))
+        .collect::<Vec<_>>();
+    let key_placeholders = vec!["?"; conflict.len()].join(", ");
+    let rows = (0..chunk.len())
+        .map(|n| format!("({}, {})", n, key_placeholders))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let in_table = conflict
+        .iter()
+        .zip(&keys)
+        .map(|(column, key)| format!("t.{} = a.{}", quote_identifier(column), key))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let on_earlier_row = keys
+        .iter()
+        .map(|key| format!("b.{} = a.{}", key, key))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+
+    let sql = format!(
+        "WITH keys(n, {}) AS (VALUES {}) SELECT count(*) FROM keys AS a \
+         WHERE NOT EXISTS (SELECT 1 FROM {} AS t WHERE {}) \
+         AND NOT EXISTS (SELECT 1 FROM keys AS b WHERE b.n < a.n AND {})",
+        keys.join(", "),
+        rows,
+        table,
+        in_table,
+        on_earlier_row
+    );
+
+    let values = chunk
+        .iter()
+        .flat_map(|(_, values)| positions.iter().map(|&position| values[position].clone()))
+        .collect();
+    let params = QueryParameters {
+        positional: Some(values),
+        ..Default::default()
+    };
+
+    (sql, params)
+}
+
+/// Builds the `ON CONFLICT` clause, or an empty string for a plain insert.
+fn conflict_clause(conflict: &[String], update: &[String]) -> String {
+    if conflict.is_empty() {
+        return String::new();
+    }
+
+    let target = conflict
+        .iter()
+        .map(|column| quote_identifier(column))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if update.is_empty() {
+        return format!(" ON CONFLICT ({}) DO NOTHING", target);
+    }
+
+    let assignments = update
+        .iter()
+        .map(|column| {
+            let column = quote_identifier(column);
+            format!("{} = excluded.{}", column, column)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(" ON CONFLICT ({}) DO UPDATE SET {}", target, assignments)
+}
+
+/// Reads a column name or a list of column names.
+fn column_list(option: &str, value: &Zval) -> Result<Vec<String>, PhpException> {
+    if let Some(column) = value.string() {
+        return Ok(vec![column]);
+    }
+
+    value
+        .array()
+        .and_then(|columns| {
+            columns
+                .values()
+                .map(|column| column.string())
+                .collect::<Option<Vec<_>>>()
+        })
+        .ok_or_else(|| {
+            PhpException::default(format!(
+                "The {} option must be a column name or a list of column names",
+                option
+            ))
+        })
+}
@@ -3,9 +3,10 @@ use std::collections::HashMap;
 use ext_php_rs::{convert::IntoZval, exception::PhpException, types::Zval};
 
 use crate::{
+    errors::LibSQLError,
     utils::{
         log_error::log_error_to_tmp,
-        query_params::QueryParameters,
+        query_params::{ParameterSpec, QueryParameters},
         result_set::ResultSet,
         runtime::{remove_duplicates, runtime},
     },
@@ -32,38 +33,37 @@ pub fn query(
     stmt: &str,
     parameters: Option<QueryParameters>,
 ) -> Result<Zval, PhpException> {
-    let conn_registry = CONNECTION_REGISTRY.lock().map_err(|e| {
-        let err_msg = format!("Mutex lock error: {}", e);
-        log_error_to_tmp(&err_msg);
-        PhpException::default(err_msg)
-    })?;
+    let (stmt, parameters) = parameters.unwrap_or_default().expand_lists(stmt)?;
 
-    let conn = conn_registry.get(&conn_id).ok_or_else(|| {
+    let conn = CONNECTION_REGISTRY.get(&conn_id).ok_or_else(|| {
         let err_msg = "Connection not found".to_string();
         log_error_to_tmp(&err_msg);
         PhpException::from(err_msg)
     })?;
 
-    let params = if let Some(p) = parameters {
-        p.to_params()
-    } else {
-        libsql::params::Params::None
-    };
+    let statement = runtime()
+        .block_on(async { conn.prepare(&stmt).await })
+        .map_err(|e| query_error(&e, &stmt, &conn_id))?;
+
+    let params = parameters.to_params_for(&ParameterSpec::from_statement(&statement, &stmt))?;
 
     let query_result = runtime().block_on(async {
-        let mut rows = conn
-            .query(stmt, params)
+        let mut rows = statement
+            .query(params)
             .await
-            .map_err(|e| PhpException::from(format!("Query failed: {}", e)))?;
+            .map_err(|e| query_error(&e, &stmt, &conn_id))?;
 
         let mut results: Vec<HashMap<String, libsql::Value>> = Vec::new();
         let mut columns: Vec<String> = Vec::new();
 
-        while let Some(row) = rows
-            .next()
-            .await
-            .map_err(|e| PhpException::from(format!("Row fetch failed: {}", e)))?
-        {
+        while let Some(row) = rows.next().await.map_err(|e| {
+            PhpException::from(
+                LibSQLError::from_libsql(&e)
+                    .context("Row fetch failed")
+                    .with_sql(&stmt)
+                    .with_connection(&conn_id),
+            )
+        })? {
             let mut result = HashMap::new();
 
             for idx in 0..rows.column_count() {
@@ -100,3 +100,11 @@ pub fn query(
         }
     }
 }
+
+fn query_error(error: &libsql::Error, stmt: &str, conn_id: &str) -> PhpException {
+    LibSQLError::from_libsql(error)
+        .context("Query failed")
+        .with_sql(stmt)
+        .with_connection(conn_id)
+        .into()
+}
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use ext_php_rs::{
+    convert::IntoZval,
+    error::Error,
+    exception::PhpException,
+    types::{ZendCallable, Zval},
+};
+
+use crate::{
+    errors::is_retryable_exception, transaction::LibSQLTransaction, utils::type_map::TypeMap,
+};
+
+/// The number of times a transaction is retried unless configured otherwise.
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// The delay before the first retry unless configured otherwise; it doubles on every retry.
+pub const DEFAULT_BACKOFF_MS: u64 = 50;
+
+/// The longest delay between two attempts.
+const MAX_BACKOFF_MS: u64 = 5_000;
+
+/// Runs a PHP callable inside a transaction, retrying it when the database is busy.
+///
+/// The callable receives the `LibSQLTransaction`. The transaction is committed when the
+/// callable returns, unless the callable ended it itself, and rolled back when it throws.
+/// If beginning, running or committing fails with a `BusyException` or
+/// `ConnectionException`, the whole callable runs again in a new transaction after a delay
+/// that doubles on every attempt. A transaction nested in
+/// an active one is never retried, since only the outer transaction can recover.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection.
+/// * `type_map` - The type map applied to the results of queries run on the transaction.
+/// * `callback` - The callable to run.
+/// * `behavior` - The behavior of the transaction.
+/// * `retries` - The number of retries after the first attempt.
+/// * `backoff` - The delay before the first retry.
+/// * `timeout` - The time after which an attempt is rolled back if it has not ended.
+///
+/// # Returns
+///
+/// The value returned by the callable, or the exception of the last attempt.
+pub fn transactional(
+    conn_id: &str,
+    type_map: Option<&TypeMap>,
+    callback: &Zval,
+    behavior: &str,
+    retries: u32,
+    backoff: Duration,
+    timeout: Option<Duration>,
+) -> Result<Zval, PhpException> {
+    let callback = ZendCallable::new(callback)
+        .map_err(|_| PhpException::default("transactional expects a callable".to_string()))?;
+
+    let mut attempt = 0;
+    loop {
+        let can_retry = |nested: bool| !nested && attempt < retries;
+
+        let mut trx =
+            match LibSQLTransaction::begin(conn_id.to_string(), behavior.to_string(), timeout) {
+                Ok(trx) => trx,
+                Err(e) if can_retry(false) && e.is_retryable() => {
+                    wait(backoff, attempt);
+                    attempt += 1;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+        let nested = trx.savepoint.is_some();
+        trx.type_map = type_map.cloned();
+
+        let handle = trx.clone();
+        let trx = trx
+            .into_zval(false)
+            .map_err(|e| PhpException::default(e.to_string()))?;
+
+        match callback.try_call(vec![&trx]) {
+            // The callable ended the transaction itself
+            Ok(result) if !handle.is_active() => return Ok(result),
+            Ok(result) => match handle.finish(true) {
+                Ok(()) => return Ok(result),
+                Err(e) => {
+                    let _ = handle.finish(false);
+                    if !(can_retry(nested) && e.is_retryable()) {
+                        return Err(e.into());
+                    }
+                }
+            },
+            Err(Error::Exception(exception)) => {
+                let _ = handle.finish(false);
+                if !(can_retry(nested) && is_retryable_exception(&exception)) {
+                    return Err(rethrow(exception.into_zval(false)));
+                }
+            }
+            Err(e) => {
+                let _ = handle.finish(false);
+                return Err(PhpException::default(e.to_string()));
+            }
+        }
+
+        wait(backoff, attempt);
+        attempt += 1;
+    }
+}
+
+/// Sleeps before the retry following the given attempt.
+fn wait(backoff: Duration, attempt: u32) {
+    let delay = backoff
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(Duration::from_millis(MAX_BACKOFF_MS));
+
+    std::thread::sleep(delay);
+}
+
+/// Throws an exception object raised by PHP code again, unchanged.
+fn rethrow(exception: ext_php_rs::error::Result<Zval>) -> PhpException {
+    match exception {
+        Ok(exception) => {
+            let message = exception
+                .try_call_method("getMessage", vec![])
+                .ok()
+                .and_then(|message| message.string())
+                .unwrap_or_default();
+
+            PhpException::default(message).with_object(exception)
+        }
+        Err(e) => PhpException::default(e.to_string()),
+    }
+}
@@ -1,6 +1,8 @@
 #![feature(abi_vectorcall)]
 #[allow(non_snake_case, deprecated, unused_attributes)]
 #[cfg_attr(windows, feature(abi_vectorcall))]
+pub mod blob;
+pub mod errors;
 pub mod generator;
 pub mod hooks;
 pub mod providers;
@@ -8,28 +10,56 @@ pub mod result;
 pub mod statement;
 pub mod transaction;
 pub mod utils;
+use crate::errors::{ErrorKind, LibSQLError};
 use crate::providers::sqld_offline_write::OfflineWriteConnection;
 use crate::result::LibSQLResult;
 use crate::statement::LibSQLStatement;
-use crate::transaction::LibSQLTransaction;
+use crate::transaction::{LibSQLTransaction, TransactionHandle};
+use crate::utils::guard::catch_panic;
 use crate::utils::runtime::send_webhook_data;
 use crate::utils::runtime::WebhookPayload;
 use ext_php_rs::prelude::*;
+use ext_php_rs::{
+    boxed::ZBox,
+    types::{ZendCallable, ZendHashTable, Zval},
+};
 use ext_php_rs::{php_class, php_impl, php_module};
-use hooks::load_extensions::ExtensionParams;
-use std::{collections::HashMap, path::Path, sync::Mutex};
+use hooks::{
+    load_extensions::ExtensionParams,
+    request_state::{self, ConnectionLifecycle, Handle},
+};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
 use utils::{
     config_value::ConfigValue,
+    execute_many_result::ExecuteManyResult,
+    fork::ProcessLocal,
+    insert_many_result::InsertManyResult,
+    interrupt::{self, Interrupt},
+    leak_debug,
     log_error::log_error_to_tmp,
-    query_params::QueryParameters,
-    runtime::{get_mode, parse_dsn},
+    native_handle::{self, NativeHandle},
+    query_params::{DateTimeBinding, QueryParameters},
+    registry::Registry,
+    runtime::{configure_runtime, get_mode, parse_dsn, RuntimeConfig},
+    statement_cache::{StatementCache, DEFAULT_STATEMENT_CACHE_SIZE},
+    type_map::TypeMap,
 };
 
 lazy_static::lazy_static! {
-    static ref CONNECTION_REGISTRY: Mutex<HashMap<String, libsql::Connection>> = Mutex::new(HashMap::new());
-    static ref OFFLINE_CONNECTION_REGISTRY: Mutex<HashMap<String, OfflineWriteConnection>> = Mutex::new(HashMap::new());
-    static ref TRANSACTION_REGISTRY: Mutex<HashMap<String, libsql::Transaction>> = Mutex::new(HashMap::new());
-    static ref STATEMENT_REGISTRY: Mutex<HashMap<String, libsql::Statement>> = Mutex::new(HashMap::new());
+    static ref CONNECTION_REGISTRY: Registry<libsql::Connection> = Registry::new();
+    static ref OFFLINE_CONNECTION_REGISTRY: Registry<OfflineWriteConnection> = Registry::new();
+    static ref TRANSACTION_REGISTRY: Registry<TransactionHandle> = Registry::new();
+    static ref STATEMENT_REGISTRY: Registry<Arc<Mutex<libsql::Statement>>> = Registry::new();
+    static ref STATEMENT_CACHE_REGISTRY: Registry<StatementCache> = Registry::new();
+    static ref CONNECTION_MODE_REGISTRY: Registry<String> = Registry::new();
+    static ref CONNECTION_LIFECYCLE_REGISTRY: Registry<ConnectionLifecycle> = Registry::new();
+    static ref INTERRUPT_REGISTRY: Registry<Arc<Interrupt>> = Registry::new();
+    static ref NATIVE_HANDLE_REGISTRY: Registry<NativeHandle> = Registry::new();
+    static ref DATETIME_BINDING_REGISTRY: Registry<DateTimeBinding> = Registry::new();
 }
 
 pub const LIBSQL_PHP_VERSION: &str = "1.6.2";
@@ -49,6 +79,14 @@ pub const LIBSQL_BOTH: i32 = 3;
 pub const LIBSQL_ALL: i32 = 4;
 pub const LIBSQL_LAZY: i32 = 5;
 
+/// Represents the parameter types accepted by `LibSQLStatement::bindValue` and `bindParam`.
+pub const LIBSQL_PARAM_NULL: i32 = 0;
+pub const LIBSQL_PARAM_INT: i32 = 1;
+pub const LIBSQL_PARAM_STR: i32 = 2;
+pub const LIBSQL_PARAM_BLOB: i32 = 3;
+pub const LIBSQL_PARAM_BOOL: i32 = 5;
+pub const LIBSQL_PARAM_FLOAT: i32 = 6;
+
 /// Struct representing LibSQL PHP Class.
 #[php_class]
 #[derive(Default)]
@@ -65,8 +103,11 @@ struct LibSQL {
     conn_id: String,
 
     /// Property representing the Database object.
-    db: Option<libsql::Database>,
-    conn: Option<libsql::Connection>,
+    db: Option<ProcessLocal<Arc<libsql::Database>>>,
+    conn: Option<ProcessLocal<libsql::Connection>>,
+
+    /// Property representing the SQL-to-PHP type map, if type mapping is enabled.
+    type_map: Option<TypeMap>,
 }
 
 #[php_impl]
@@ -86,6 +127,68 @@ impl LibSQL {
     const LIBSQL_ALL: i32 = 4;
     const LIBSQL_LAZY: i32 = 5;
 
+    /// Represents the parameter types accepted by `LibSQLStatement::bindValue` and `bindParam`.
+    const PARAM_NULL: i32 = 0;
+    const PARAM_INT: i32 = 1;
+    const PARAM_STR: i32 = 2;
+    const PARAM_BLOB: i32 = 3;
+    const PARAM_BOOL: i32 = 5;
+    const PARAM_FLOAT: i32 = 6;
+
+    /// Represents the runtime limits read and changed by `getLimit` and `setLimit`.
+    const SQLITE_LIMIT_LENGTH: i32 = 0;
+    const SQLITE_LIMIT_SQL_LENGTH: i32 = 1;
+    const SQLITE_LIMIT_COLUMN: i32 = 2;
+    const SQLITE_LIMIT_EXPR_DEPTH: i32 = 3;
+    const SQLITE_LIMIT_COMPOUND_SELECT: i32 = 4;
+    const SQLITE_LIMIT_VDBE_OP: i32 = 5;
+    const SQLITE_LIMIT_FUNCTION_ARG: i32 = 6;
+    const SQLITE_LIMIT_ATTACHED: i32 = 7;
+    const SQLITE_LIMIT_LIKE_PATTERN_LENGTH: i32 = 8;
+    const SQLITE_LIMIT_VARIABLE_NUMBER: i32 = 9;
+    const SQLITE_LIMIT_TRIGGER_DEPTH: i32 = 10;
+    const SQLITE_LIMIT_WORKER_THREADS: i32 = 11;
+
+    /// Represents the decisions returned by an authorizer set with `setAuthorizer`.
+    const SQLITE_OK: i32 = 0;
+    const SQLITE_DENY: i32 = 1;
+    const SQLITE_IGNORE: i32 = 2;
+
+    /// Represents the actions passed to an authorizer set with `setAuthorizer`.
+    const SQLITE_CREATE_INDEX: i32 = 1;
+    const SQLITE_CREATE_TABLE: i32 = 2;
+    const SQLITE_CREATE_TEMP_INDEX: i32 = 3;
+    const SQLITE_CREATE_TEMP_TABLE: i32 = 4;
+    const SQLITE_CREATE_TEMP_TRIGGER: i32 = 5;
+    const SQLITE_CREATE_TEMP_VIEW: i32 = 6;
+    const SQLITE_CREATE_TRIGGER: i32 = 7;
+    const SQLITE_CREATE_VIEW: i32 = 8;
+    const SQLITE_DELETE: i32 = 9;
+    const SQLITE_DROP_INDEX: i32 = 10;
+    const SQLITE_DROP_TABLE: i32 = 11;
+    const SQLITE_DROP_TEMP_INDEX: i32 = 12;
+    const SQLITE_DROP_TEMP_TABLE: i32 = 13;
+    const SQLITE_DROP_TEMP_TRIGGER: i32 = 14;
+    const SQLITE_DROP_TEMP_VIEW: i32 = 15;
+    const SQLITE_DROP_TRIGGER: i32 = 16;
+    const SQLITE_DROP_VIEW: i32 = 17;
+    const SQLITE_INSERT: i32 = 18;
+    const SQLITE_PRAGMA: i32 = 19;
+    const SQLITE_READ: i32 = 20;
+    const SQLITE_SELECT: i32 = 21;
+    const SQLITE_TRANSACTION: i32 = 22;
+    const SQLITE_UPDATE: i32 = 23;
+    const SQLITE_ATTACH: i32 = 24;
+    const SQLITE_DETACH: i32 = 25;
+    const SQLITE_ALTER_TABLE: i32 = 26;
+    const SQLITE_REINDEX: i32 = 27;
+    const SQLITE_ANALYZE: i32 = 28;
+    const SQLITE_CREATE_VTABLE: i32 = 29;
+    const SQLITE_DROP_VTABLE: i32 = 30;
+    const SQLITE_FUNCTION: i32 = 31;
+    const SQLITE_SAVEPOINT: i32 = 32;
+    const SQLITE_RECURSIVE: i32 = 33;
+
     /// Constructs a new `LibSQLConnection` object.
     ///
     /// # Arguments
@@ -106,178 +209,288 @@ impl LibSQL {
         encryption_key: Option<String>,
         offline_writes: Option<bool>,
     ) -> Result<Self, PhpException> {
-        let db_flags = flags.unwrap_or(6);
-        let encryption_key = encryption_key.unwrap_or_default();
-        let offline_writes = offline_writes.unwrap_or(false);
-        let sqld_offline_mode = sqld_offline_mode.unwrap_or(false);
-
-        let (url, auth_token, sync_url, sync_interval, read_your_writes): (
-            String,
-            String,
-            String,
-            std::time::Duration,
-            bool,
-        ) = match config {
-            ConfigValue::String(dsn) => {
-                let dsn_parsed = match parse_dsn(&dsn) {
-                    Some(dsn) => match (dsn.dbname.is_empty(), dsn.auth_token.is_empty()) {
-                        (false, true) => Some((
-                            dsn.dbname,
-                            "".to_string(),
-                            "".to_string(),
-                            std::time::Duration::from_secs(5),
-                            true,
-                        )),
-                        (false, false) => Some((
-                            dsn.dbname,
-                            dsn.auth_token,
-                            "".to_string(),
-                            std::time::Duration::from_secs(5),
-                            true,
-                        )),
-                        (true, true) => None,
-                        (true, false) => None,
-                    },
-                    None => None,
-                };
-
-                dsn_parsed.ok_or_else(|| PhpException::default("Failed to parse DSN".into()))?
+        catch_panic(|| {
+            let db_flags = flags.unwrap_or(6);
+            let encryption_key = encryption_key.unwrap_or_default();
+            let offline_writes = offline_writes.unwrap_or(false);
+            let sqld_offline_mode = sqld_offline_mode.unwrap_or(false);
+
+            let statement_cache_size = match &config {
+                ConfigValue::Array(config) => config
+                    .get("statementCacheSize")
+                    .and_then(|v| v.to_long())
+                    .map(|size| size as usize),
+                _ => None,
             }
-            ConfigValue::Array(config) => {
-                let url = config
-                    .get("url")
-                    .and_then(|v| v.to_string())
-                    .unwrap_or_default();
-                let auth_token = config
-                    .get("authToken")
-                    .and_then(|v| v.to_string())
-                    .unwrap_or_default();
-                let sync_url = config
-                    .get("syncUrl")
+            .unwrap_or(DEFAULT_STATEMENT_CACHE_SIZE);
+
+            let query_timeout = match &config {
+                ConfigValue::Array(config) => config
+                    .get("queryTimeout")
+                    .and_then(|v| v.to_long())
+                    .filter(|timeout| *timeout > 0)
+                    .map(|timeout| std::time::Duration::from_millis(timeout as u64)),
+                _ => None,
+            };
+
+            let datetime_binding = match &config {
+                ConfigValue::Array(config) => config
+                    .get("dateTimeBinding")
                     .and_then(|v| v.to_string())
-                    .unwrap_or_default();
-                let sync_interval = config
-                    .get("syncInterval")
-                    .and_then(|s| s.to_long())
-                    .map(std::time::Duration::from_secs)
-                    .unwrap_or_else(|| std::time::Duration::from_secs(5));
-                let read_your_writes = config
-                    .get("read_your_writes")
-                    .and_then(|v| v.to_bool())
-                    .unwrap_or(true);
-
-                (url, auth_token, sync_url, sync_interval, read_your_writes)
+                    .map(|format| DateTimeBinding::parse(&format)),
+                _ => None,
             }
-        };
-
-        if url.is_empty() {
-            return Err(PhpException::default("URL is not defined!".into()));
-        }
-
-        let cleared_url = if url.starts_with("file:") {
-            url.strip_prefix("file:").unwrap().to_string()
-        } else {
-            url.clone()
-        };
-
-        let mode = get_mode(
-            Some(url.clone()),
-            Some(auth_token.clone()),
-            Some(sync_url.clone()),
-        );
-
-        let conn_id = uuid::Uuid::new_v4().to_string();
+            .unwrap_or_default();
+
+            let mut lifecycle = match &config {
+                ConfigValue::Array(config) => ConnectionLifecycle {
+                    persistent: config
+                        .get("persistent")
+                        .and_then(|v| v.to_bool())
+                        .unwrap_or(false),
+                    sync_on_request_end: config
+                        .get("syncOnRequestEnd")
+                        .and_then(|v| v.to_bool())
+                        .unwrap_or(false),
+                    datetime_binding: datetime_binding.clone(),
+                    database: None,
+                },
+                _ => ConnectionLifecycle::default(),
+            };
+
+            let (url, auth_token, sync_url, sync_interval, read_your_writes): (
+                String,
+                String,
+                String,
+                std::time::Duration,
+                bool,
+            ) = match config {
+                ConfigValue::String(dsn) => {
+                    let dsn_parsed = match parse_dsn(&dsn) {
+                        Some(dsn) => match (dsn.dbname.is_empty(), dsn.auth_token.is_empty()) {
+                            (false, true) => Some((
+                                dsn.dbname,
+                                "".to_string(),
+                                "".to_string(),
+                                std::time::Duration::from_secs(5),
+                                true,
+                            )),
+                            (false, false) => Some((
+                                dsn.dbname,
+                                dsn.auth_token,
+                                "".to_string(),
+                                std::time::Duration::from_secs(5),
+                                true,
+                            )),
+                            (true, true) => None,
+                            (true, false) => None,
+                        },
+                        None => None,
+                    };
+
+                    dsn_parsed.ok_or_else(|| PhpException::default("Failed to parse DSN".into()))?
+                }
+                ConfigValue::Array(config) => {
+                    let url = config
+                        .get("url")
+                        .and_then(|v| v.to_string())
+                        .unwrap_or_default();
+                    let auth_token = config
+                        .get("authToken")
+                        .and_then(|v| v.to_string())
+                        .unwrap_or_default();
+                    let sync_url = config
+                        .get("syncUrl")
+                        .and_then(|v| v.to_string())
+                        .unwrap_or_default();
+                    let sync_interval = config
+                        .get("syncInterval")
+                        .and_then(|s| s.to_long())
+                        .map(std::time::Duration::from_secs)
+                        .unwrap_or_else(|| std::time::Duration::from_secs(5));
+                    let read_your_writes = config
+                        .get("read_your_writes")
+                        .and_then(|v| v.to_bool())
+                        .unwrap_or(true);
+
+                    (url, auth_token, sync_url, sync_interval, read_your_writes)
+                }
+                _ => {
+                    return Err(PhpException::default(
+                        "Configuration must be a DSN string or an array".into(),
+                    ))
+                }
+            };
 
-        if sqld_offline_mode && !auth_token.is_empty() && !sync_url.is_empty() {
-            let offline_conn = providers::sqld_offline_write::create_sqld_offline_write_connection(
-                cleared_url.clone(),
-                auth_token.clone(),
-                sync_url.clone(),
-                Some(db_flags),
-                Some(encryption_key),
-            )
-            .map_err(|e| {
-                log_error_to_tmp(&format!("Offline connection creation failed: {:?}", e));
-                e
-            })?;
+            if url.is_empty() {
+                return Err(PhpException::default("URL is not defined!".into()));
+            }
 
-            OFFLINE_CONNECTION_REGISTRY
-                .lock()
-                .map_err(|e| {
-                    let err_msg = format!("Mutex lock error: {}", e);
-                    log_error_to_tmp(&err_msg);
-                    PhpException::default(err_msg)
-                })?
-                .insert(conn_id.clone(), offline_conn);
-
-            return Ok(Self {
-                mode: "offline_write".to_string(),
-                cdc_url: Some(String::new()),
-                conn_id,
-                db: None,
-                conn: None,
-            });
-        }
-
-        let (conn, db) = match mode.as_str() {
-            "local" => {
-                let conn = providers::local::create_local_connection(
-                    url,
-                    Some(db_flags),
-                    Some(encryption_key),
+            let cleared_url = if url.starts_with("file:") {
+                url.strip_prefix("file:").unwrap().to_string()
+            } else {
+                url.clone()
+            };
+
+            let mode = get_mode(
+                Some(url.clone()),
+                Some(auth_token.clone()),
+                Some(sync_url.clone()),
+            );
+
+            // A persistent connection is reused whenever the same configuration is opened again
+            let persistent_key = lifecycle.persistent.then(|| {
+                format!(
+                    "{:?}",
+                    (
+                        (
+                            &url,
+                            &auth_token,
+                            &sync_url,
+                            sync_interval,
+                            read_your_writes
+                        ),
+                        (db_flags, &encryption_key, offline_writes, sqld_offline_mode),
+                        (statement_cache_size, query_timeout, &datetime_binding),
+                        lifecycle.sync_on_request_end,
+                    )
                 )
-                .map_err(|e| {
-                    log_error_to_tmp(&format!("Local connection failed: {:?}", e));
-                    e
-                })?;
-                (conn, None)
-            }
-            "remote" => {
-                let conn = providers::remote::create_remote_connection(url, auth_token);
-                (conn, None)
+            });
+            if let Some(conn_id) = persistent_key
+                .as_deref()
+                .and_then(request_state::find_persistent)
+            {
+                request_state::track(Handle::Connection, &conn_id);
+
+                return Ok(Self {
+                    mode: CONNECTION_MODE_REGISTRY.get(&conn_id).unwrap_or_default(),
+                    cdc_url: Some(String::new()),
+                    db: CONNECTION_LIFECYCLE_REGISTRY
+                        .get(&conn_id)
+                        .and_then(|lifecycle| lifecycle.database)
+                        .map(ProcessLocal::new),
+                    conn: CONNECTION_REGISTRY.get(&conn_id).map(ProcessLocal::new),
+                    conn_id,
+                    type_map: None,
+                });
             }
-            "remote_replica" => {
-                let cleared_url = if url.starts_with("file:") {
-                    url.strip_prefix("file:").unwrap().to_string()
-                } else {
-                    url.clone()
-                };
-
-                let (db, conn) = match offline_writes {
-                    false => providers::remote_replica::create_remote_replica_connection(
+
+            let conn_id = uuid::Uuid::new_v4().to_string();
+
+            if sqld_offline_mode && !auth_token.is_empty() && !sync_url.is_empty() {
+                let offline_conn =
+                    providers::sqld_offline_write::create_sqld_offline_write_connection(
                         cleared_url.clone(),
                         auth_token.clone(),
                         sync_url.clone(),
-                        sync_interval.clone(),
-                        read_your_writes.clone(),
+                        Some(db_flags),
                         Some(encryption_key),
-                    ),
-                    true => providers::offline_write::create_offline_write_connection(
-                        cleared_url.clone(),
-                        auth_token,
-                        sync_url,
-                    ),
-                };
-                (conn, Some(db))
+                    )
+                    .map_err(|e| {
+                        log_error_to_tmp(&format!("Offline connection creation failed: {:?}", e));
+                        e
+                    })?;
+
+                OFFLINE_CONNECTION_REGISTRY.insert(conn_id.clone(), offline_conn);
+
+                CONNECTION_MODE_REGISTRY.insert(conn_id.clone(), "offline_write".to_string());
+
+                interrupt::register(&conn_id, query_timeout);
+
+                DATETIME_BINDING_REGISTRY.insert(conn_id.clone(), datetime_binding);
+
+                CONNECTION_LIFECYCLE_REGISTRY.insert(conn_id.clone(), lifecycle);
+                request_state::track(Handle::Connection, &conn_id);
+                if let Some(key) = persistent_key {
+                    request_state::register_persistent(key, &conn_id);
+                }
+
+                return Ok(Self {
+                    mode: "offline_write".to_string(),
+                    cdc_url: Some(String::new()),
+                    conn_id,
+                    db: None,
+                    conn: None,
+                    type_map: None,
+                });
+            }
+
+            let capture = native_handle::Capture::start();
+            let (conn, db) = match mode.as_str() {
+                "local" => {
+                    let conn = providers::local::create_local_connection(
+                        url,
+                        Some(db_flags),
+                        Some(encryption_key),
+                    )
+                    .map_err(|e| {
+                        log_error_to_tmp(&format!("Local connection failed: {:?}", e));
+                        e
+                    })?;
+                    (conn, None)
+                }
+                "remote" => {
+                    let conn = providers::remote::create_remote_connection(url, auth_token)?;
+                    (conn, None)
+                }
+                "remote_replica" => {
+                    let cleared_url = if url.starts_with("file:") {
+                        url.strip_prefix("file:").unwrap().to_string()
+                    } else {
+                        url.clone()
+                    };
+
+                    let (db, conn) = match offline_writes {
+                        false => providers::remote_replica::create_remote_replica_connection(
+                            cleared_url.clone(),
+                            auth_token.clone(),
+                            sync_url.clone(),
+                            sync_interval.clone(),
+                            read_your_writes.clone(),
+                            Some(encryption_key),
+                        )?,
+                        true => providers::offline_write::create_offline_write_connection(
+                            cleared_url.clone(),
+                            auth_token,
+                            sync_url,
+                        )?,
+                    };
+                    (conn, Some(Arc::new(db)))
+                }
+                _ => return Err(PhpException::default("Mode is not available!".into())),
+            };
+
+            if mode != "remote" {
+                let handle = capture.finish(&conn_id, &conn)?;
+                NATIVE_HANDLE_REGISTRY.insert(conn_id.clone(), handle);
             }
-            _ => return Err(PhpException::default("Mode is not available!".into())),
-        };
-
-        CONNECTION_REGISTRY
-            .lock()
-            .map_err(|e| {
-                let err_msg = format!("Mutex lock error: {}", e);
-                log_error_to_tmp(&err_msg);
-                PhpException::default(err_msg)
-            })?
-            .insert(conn_id.clone(), conn.clone());
-
-        Ok(Self {
-            mode,
-            cdc_url: Some(String::new()),
-            conn_id,
-            db,
-            conn: Some(conn),
+
+            CONNECTION_REGISTRY.insert(conn_id.clone(), conn.clone());
+
+            STATEMENT_CACHE_REGISTRY
+                .insert(conn_id.clone(), StatementCache::new(statement_cache_size));
+
+            CONNECTION_MODE_REGISTRY.insert(conn_id.clone(), mode.clone());
+
+            interrupt::register(&conn_id, query_timeout);
+
+            DATETIME_BINDING_REGISTRY.insert(conn_id.clone(), datetime_binding);
+
+            lifecycle.database = db.clone();
+            CONNECTION_LIFECYCLE_REGISTRY.insert(conn_id.clone(), lifecycle);
+            request_state::track(Handle::Connection, &conn_id);
+            if let Some(key) = persistent_key {
+                request_state::register_persistent(key, &conn_id);
+            }
+
+            Ok(Self {
+                mode,
+                cdc_url: Some(String::new()),
+                conn_id,
+                db: db.map(ProcessLocal::new),
+                conn: Some(ProcessLocal::new(conn)),
+                type_map: None,
+            })
         })
     }
 
@@ -296,16 +509,17 @@ impl LibSQL {
     ///
     /// Returns the number of changes made as a result of the last executed statement.
     pub fn changes(&self) -> Result<u64, PhpException> {
-        if self.mode == "offline_write" {
-            let offline_registry = OFFLINE_CONNECTION_REGISTRY.lock().unwrap();
-            let offline_conn = offline_registry
-                .get(&self.conn_id)
-                .ok_or_else(|| PhpException::from("Offline connection not found"))?;
-
-            Ok(offline_conn.changes())
-        } else {
-            hooks::changes::get_changes(self.conn_id.to_string())
-        }
+        catch_panic(|| {
+            if self.mode == "offline_write" {
+                let offline_conn = OFFLINE_CONNECTION_REGISTRY
+                    .get(&self.conn_id)
+                    .ok_or_else(|| PhpException::from("Offline connection not found"))?;
+
+                Ok(offline_conn.changes())
+            } else {
+                hooks::changes::get_changes(self.conn_id.to_string())
+            }
+        })
     }
 
     /// Checks if autocommit mode is enabled for the connection.
@@ -314,7 +528,7 @@ impl LibSQL {
     ///
     /// Returns `true` if autocommit mode is enabled, otherwise `false`.
     pub fn is_autocommit(&self) -> Result<bool, PhpException> {
-        hooks::is_autocommit::get_is_autocommit(self.conn_id.to_string())
+        catch_panic(|| hooks::is_autocommit::get_is_autocommit(self.conn_id.to_string()))
     }
 
     /// Retrieves the total number of changes made by the connection.
@@ -323,10 +537,7 @@ impl LibSQL {
     ///
     /// Returns the total number of changes made by the connection.
     pub fn total_changes(&self) -> Result<u64, PhpException> {
-        match &self.conn {
-            Some(conn) => Ok(conn.total_changes()),
-            None => Err(PhpException::from("Connection not available")),
-        }
+        catch_panic(|| Ok(self.connection()?.total_changes()))
     }
 
     /// Retrieves the rowid of the last inserted row.
@@ -335,10 +546,7 @@ impl LibSQL {
     ///
     /// Returns the rowid of the last inserted row.
     pub fn last_inserted_id(&self) -> Result<i64, PhpException> {
-        match &self.conn {
-            Some(conn) => Ok(conn.last_insert_rowid()),
-            None => Err(PhpException::from("Connection not available")),
-        }
+        catch_panic(|| Ok(self.connection()?.last_insert_rowid()))
     }
 
     /// Executes a SQL statement.
@@ -347,6 +555,7 @@ impl LibSQL {
     ///
     /// * `stmt` - The SQL statement to execute.
     /// * `parameters` - Parameters to bind to the statement.
+    /// * `timeout` - Milliseconds after which the statement is interrupted, overriding `queryTimeout`.
     ///
     /// # Returns
     ///
@@ -354,22 +563,112 @@ impl LibSQL {
     pub fn execute(
         &self,
         stmt: &str,
-        parameters: Option<QueryParameters>,
+        parameters: Option<&Zval>,
+        timeout: Option<i64>,
     ) -> Result<u64, PhpException> {
-        if self.mode == "offline_write" {
-            let offline_registry = OFFLINE_CONNECTION_REGISTRY.lock().unwrap();
-            let offline_conn = offline_registry
-                .get(&self.conn_id)
-                .ok_or_else(|| PhpException::from("Offline connection not found"))?;
-
-            // Remove the unused params variable and pass parameters directly
-            match offline_conn.execute(stmt, parameters) {
-                Ok(result) => Ok(result),
-                Err(e) => Err(PhpException::from(format!("{:?}", e))),
+        catch_panic(|| {
+            let parameters = QueryParameters::from_argument(parameters, &self.conn_id)?;
+
+            if self.mode == "offline_write" {
+                let offline_conn = OFFLINE_CONNECTION_REGISTRY
+                    .get(&self.conn_id)
+                    .ok_or_else(|| PhpException::from("Offline connection not found"))?;
+
+                offline_conn.execute(stmt, parameters)
+            } else {
+                transaction::expire_on_connection(&self.conn_id)?;
+                hooks::use_exec::exec(
+                    self.conn_id.to_string(),
+                    stmt,
+                    parameters,
+                    timeout
+                        .filter(|timeout| *timeout > 0)
+                        .map(|timeout| std::time::Duration::from_millis(timeout as u64)),
+                )
             }
-        } else {
-            hooks::use_exec::exec(self.conn_id.to_string(), stmt, parameters)
-        }
+        })
+    }
+
+    /// Executes an SQL statement once per parameter set.
+    ///
+    /// Unless a transaction is already open, the executions run inside an implicit transaction
+    /// that is rolled back if any of them fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `stmt` - The SQL statement to execute.
+    /// * `param_sets` - An array or `Traversable` (e.g. a generator) of parameter arrays.
+    ///
+    /// # Returns
+    ///
+    /// Returns `['rowsChanged' => int, 'lastInsertIds' => int[]]`.
+    pub fn execute_many(
+        &self,
+        stmt: &str,
+        param_sets: &Zval,
+    ) -> Result<ExecuteManyResult, PhpException> {
+        catch_panic(|| {
+            let param_sets =
+                hooks::use_execute_many::collect_parameter_sets(param_sets, &self.conn_id)?;
+
+            if self.mode == "offline_write" {
+                let offline_conn = OFFLINE_CONNECTION_REGISTRY
+                    .get(&self.conn_id)
+                    .ok_or_else(|| PhpException::from("Offline connection not found"))?;
+
+                offline_conn.execute_many(&self.conn_id, stmt, param_sets)
+            } else {
+                transaction::expire_on_connection(&self.conn_id)?;
+                hooks::use_execute_many::execute_many_sql(
+                    self.conn_id.to_string(),
+                    stmt,
+                    param_sets,
+                )
+            }
+        })
+    }
+
+    /// Inserts many rows into a table, optionally updating rows that conflict.
+    ///
+    /// The rows are inserted with multi-row statements, in chunks that stay under SQLite's
+    /// parameter limit. Unless a transaction is already open, the chunks run inside an implicit
+    /// transaction that is rolled back if any of them fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The table to insert into.
+    /// * `rows` - An array or `Traversable` of rows keyed by column name.
+    /// * `options` - Optional `conflict`, `update` and `chunkSize` options.
+    ///
+    /// # Returns
+    ///
+    /// Returns `['inserted' => int, 'updated' => int]`.
+    pub fn insert_many(
+        &self,
+        table: &str,
+        rows: &Zval,
+        options: Option<&ZendHashTable>,
+    ) -> Result<InsertManyResult, PhpException> {
+        catch_panic(|| {
+            let options = hooks::use_insert_many::InsertManyOptions::from_options(options)?;
+            let plan = hooks::use_insert_many::InsertPlan::build(
+                table,
+                rows,
+                options,
+                &DateTimeBinding::of_connection(&self.conn_id),
+            )?;
+
+            if self.mode == "offline_write" {
+                let offline_conn = OFFLINE_CONNECTION_REGISTRY
+                    .get(&self.conn_id)
+                    .ok_or_else(|| PhpException::from("Offline connection not found"))?;
+
+                hooks::use_insert_many::insert_many_offline(&offline_conn, plan)
+            } else {
+                transaction::expire_on_connection(&self.conn_id)?;
+                hooks::use_insert_many::insert_many(self.conn_id.to_string(), plan)
+            }
+        })
     }
 
     /// Executes a batch of SQL statements.
@@ -382,19 +681,18 @@ impl LibSQL {
     ///
     /// Returns `true` if the execution is successful, otherwise `false`.
     pub fn execute_batch(&self, stmt: &str) -> Result<bool, PhpException> {
-        if self.mode == "offline_write" {
-            let offline_registry = OFFLINE_CONNECTION_REGISTRY.lock().unwrap();
-            let offline_conn = offline_registry
-                .get(&self.conn_id)
-                .ok_or_else(|| PhpException::from("Offline connection not found"))?;
-
-            match offline_conn.execute_batch(stmt) {
-                Ok(_) => Ok(true),
-                Err(e) => Err(PhpException::from(format!("{:?}", e))),
+        catch_panic(|| {
+            if self.mode == "offline_write" {
+                let offline_conn = OFFLINE_CONNECTION_REGISTRY
+                    .get(&self.conn_id)
+                    .ok_or_else(|| PhpException::from("Offline connection not found"))?;
+
+                offline_conn.execute_batch(stmt)
+            } else {
+                transaction::expire_on_connection(&self.conn_id)?;
+                hooks::use_exec_batch::exec_batch(self.conn_id.to_string(), stmt)
             }
-        } else {
-            hooks::use_exec_batch::exec_batch(self.conn_id.to_string(), stmt)
-        }
+        })
     }
 
     /// Executes a SQL query and returns the result.
@@ -404,6 +702,8 @@ impl LibSQL {
     /// * `stmt` - The SQL query to execute.
     /// * `parameters` - Parameters to bind to the query.
     /// * `force_remote` - Whether to force using the remote connection when online (only for sqld offline mode).
+    /// * `types` - Per-query PHP types keyed by column name, applied on top of the connection type map.
+    /// * `timeout` - Milliseconds after which the query is interrupted, overriding `queryTimeout`.
     ///
     /// # Returns
     ///
@@ -411,39 +711,284 @@ impl LibSQL {
     pub fn query(
         &self,
         stmt: &str,
-        parameters: Option<QueryParameters>,
+        parameters: Option<&Zval>,
         force_remote: Option<bool>,
+        types: Option<HashMap<String, String>>,
+        timeout: Option<i64>,
     ) -> Result<LibSQLResult, PhpException> {
-        if self.mode == "offline_write" {
-            // For offline write mode, we still use the LibSQLResult but we need to handle it differently
-            // We'll create a special result that works with offline connections
-            LibSQLResult::__construct_offline(
-                self.conn_id.to_string(),
-                stmt,
-                parameters,
-                force_remote,
-            )
-        } else {
-            LibSQLResult::__construct(self.conn_id.to_string(), stmt, parameters)
-        }
+        catch_panic(|| {
+            let parameters = QueryParameters::from_argument(parameters, &self.conn_id)?;
+
+            let mut result = if self.mode == "offline_write" {
+                // For offline write mode, we still use the LibSQLResult but we need to handle it differently
+                // We'll create a special result that works with offline connections
+                LibSQLResult::__construct_offline(
+                    self.conn_id.to_string(),
+                    stmt,
+                    parameters,
+                    force_remote,
+                )?
+            } else {
+                transaction::expire_on_connection(&self.conn_id)?;
+                LibSQLResult::from_cached_statement(
+                    self.conn_id.to_string(),
+                    stmt,
+                    parameters,
+                    timeout
+                        .filter(|timeout| *timeout > 0)
+                        .map(|timeout| std::time::Duration::from_millis(timeout as u64)),
+                )?
+            };
+
+            result.type_map = TypeMap::for_query(self.type_map.as_ref(), types)?;
+
+            Ok(result)
+        })
+    }
+
+    /// Enables conversion of column values based on their declared types.
+    ///
+    /// Without custom types, `BOOLEAN` columns become `bool`, `DATETIME`/`DATE`/`TIMESTAMP`
+    /// become `DateTimeImmutable`, `JSON` is decoded into arrays, `DECIMAL`/`NUMERIC` become
+    /// strings and blobs become binary strings.
+    ///
+    /// # Arguments
+    ///
+    /// * `types` - Optional declared types mapped to PHP type names, added to the defaults.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the type map is valid, otherwise returns a `PhpException`.
+    pub fn enable_type_mapping(
+        &mut self,
+        types: Option<HashMap<String, String>>,
+    ) -> Result<(), PhpException> {
+        catch_panic(|| {
+            self.type_map = Some(TypeMap::defaults().with_declared(types.unwrap_or_default())?);
+            Ok(())
+        })
+    }
+
+    /// Disables type mapping, restoring the one-to-one value conversion.
+    pub fn disable_type_mapping(&mut self) {
+        self.type_map = None;
+    }
+
+    /// Configures how `DateTimeInterface` parameters are bound on this connection.
+    ///
+    /// The setting lasts until the end of the request; a persistent connection then returns
+    /// to its `dateTimeBinding` configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - `"iso8601"` (the default) for ISO-8601 text, `"unix"` for unix timestamps,
+    ///   or any other `date()` format string.
+    pub fn set_date_time_binding(&self, format: String) {
+        DATETIME_BINDING_REGISTRY.insert(self.conn_id.clone(), DateTimeBinding::parse(&format));
+    }
+
+    /// Enables or disables leak debugging, for all connections.
+    ///
+    /// When enabled, a statement destroyed without `finalize()` raises a warning, and the
+    /// handles still open at module shutdown are written to the error log. A transaction
+    /// destroyed without `commit()` or `rollback()` always raises a warning.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to report leaked handles.
+    pub fn set_leak_debugging(enabled: bool) {
+        leak_debug::set_leak_debugging(enabled);
+    }
+
+    /// Releases everything the current request left open, for worker loops such as FrankenPHP
+    /// or RoadRunner, where the request shutdown hook only runs when the worker stops.
+    ///
+    /// Rolls back open transactions, finalizes prepared statements, syncs connections
+    /// configured with `syncOnRequestEnd` and closes every connection that is not `persistent`.
+    pub fn reset_request_state() {
+        request_state::reset_request_state();
+    }
+
+    /// Configures the Tokio runtime used by every connection, before it is first used.
+    ///
+    /// The runtime is built when the first connection is opened, and rebuilt in a forked
+    /// child. A `current_thread` runtime has no worker threads, so background work such as
+    /// periodic replica syncs only makes progress while the extension is being called.
+    ///
+    /// # Arguments
+    ///
+    /// * `flavor` - `multi_thread` or `current_thread`.
+    /// * `worker_threads` - The number of worker threads of a `multi_thread` runtime, one per CPU core by default.
+    ///
+    /// # Errors
+    ///
+    /// Throws if the flavor is unknown, or if the runtime is already running with a different configuration.
+    pub fn configure_runtime(
+        flavor: Option<String>,
+        worker_threads: Option<i64>,
+    ) -> Result<(), PhpException> {
+        catch_panic(|| {
+            let flavor = flavor.unwrap_or_else(|| "multi_thread".to_string());
+            let config = match (flavor.to_lowercase().as_str(), worker_threads) {
+                ("multi_thread", None) => RuntimeConfig::MultiThread(None),
+                ("multi_thread", Some(n)) if n > 0 => RuntimeConfig::MultiThread(Some(n as usize)),
+                ("multi_thread", Some(n)) => {
+                    return Err(LibSQLError::new(
+                        ErrorKind::Generic,
+                        format!(
+                            "Invalid number of worker threads {}, expected at least 1",
+                            n
+                        ),
+                    )
+                    .into())
+                }
+                ("current_thread", None) => RuntimeConfig::CurrentThread,
+                ("current_thread", Some(_)) => {
+                    return Err(LibSQLError::new(
+                        ErrorKind::Generic,
+                        "A current_thread runtime has no worker threads",
+                    )
+                    .into())
+                }
+                _ => {
+                    return Err(LibSQLError::new(
+                        ErrorKind::Generic,
+                        format!(
+                            "Unknown runtime flavor '{}', expected multi_thread or current_thread",
+                            flavor
+                        ),
+                    )
+                    .into())
+                }
+            };
+
+            Ok(configure_runtime(config)?)
+        })
+    }
+
+    /// Counts the connections, transactions and prepared statements that are currently open.
+    ///
+    /// # Returns
+    ///
+    /// An array with the number of open `connections`, `transactions` and `statements`.
+    pub fn open_handles() -> Result<ZBox<ZendHashTable>, PhpException> {
+        catch_panic(|| {
+            let mut handles = ZendHashTable::new();
+            for (kind, count) in leak_debug::open_handles() {
+                handles.insert(kind, count as u64)?;
+            }
+            Ok(handles)
+        })
+    }
+
+    /// Retrieves the statistics of the connection's prepared statement cache.
+    ///
+    /// # Returns
+    ///
+    /// An array with the number of cache `hits` and `misses`, the number of cached statements
+    /// (`size`) and the maximum number of cached statements (`capacity`).
+    pub fn statement_cache_stats(&self) -> Result<ZBox<ZendHashTable>, PhpException> {
+        catch_panic(|| {
+            let (hits, misses, size, capacity) = STATEMENT_CACHE_REGISTRY
+                .with(&self.conn_id, |cache| {
+                    (cache.hits, cache.misses, cache.size(), cache.capacity())
+                })
+                .unwrap_or_default();
+
+            let mut stats = ZendHashTable::new();
+            stats.insert("hits", hits)?;
+            stats.insert("misses", misses)?;
+            stats.insert("size", size as u64)?;
+            stats.insert("capacity", capacity as u64)?;
+            Ok(stats)
+        })
+    }
+
+    /// Removes every statement from the connection's prepared statement cache.
+    pub fn clear_statement_cache(&self) {
+        STATEMENT_CACHE_REGISTRY.with(&self.conn_id, |cache| cache.clear());
     }
 
     /// Initiates a transaction with the specified behavior.
     ///
+    /// Inside an active transaction, this starts a nested transaction backed by a savepoint.
+    ///
     /// # Arguments
     ///
-    /// * `behavior` - The behavior of the transaction.
+    /// * `behavior` - The behavior of the transaction: `DEFERRED` (default), `IMMEDIATE`, `EXCLUSIVE` or `READONLY`.
+    /// * `timeout` - The time in milliseconds after which the transaction is rolled back if it has not ended.
     ///
     /// # Returns
     ///
     /// Returns a `LibSQLTransaction` instance representing the transaction.
-    pub fn transaction(&self, behavior: Option<String>) -> Result<LibSQLTransaction, PhpException> {
-        let tx_behavior = behavior
-            .as_deref()
-            .map(|s| s.to_uppercase())
-            .unwrap_or_else(|| "DEFERRED".to_string());
+    pub fn transaction(
+        &self,
+        behavior: Option<String>,
+        timeout: Option<i64>,
+    ) -> Result<LibSQLTransaction, PhpException> {
+        catch_panic(|| {
+            let tx_behavior = behavior
+                .as_deref()
+                .map(|s| s.to_uppercase())
+                .unwrap_or_else(|| "DEFERRED".to_string());
+
+            let mut transaction =
+                LibSQLTransaction::__construct(self.conn_id.clone(), tx_behavior, timeout)?;
+            transaction.type_map = self.type_map.clone();
+            Ok(transaction)
+        })
+    }
 
-        LibSQLTransaction::__construct(self.conn_id.clone(), tx_behavior)
+    /// Runs a callable inside a transaction, committing when it returns and rolling back when
+    /// it throws.
+    ///
+    /// The whole callable is retried when the database is busy or a remote connection fails
+    /// transiently, see `hooks::use_transactional::transactional`.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The callable, which receives the `LibSQLTransaction`.
+    /// * `behavior` - The behavior of the transaction.
+    /// * `retries` - The number of retries after the first attempt, `3` by default.
+    /// * `backoff` - The delay in milliseconds before the first retry, doubled on every retry; `50` by default.
+    /// * `timeout` - The time in milliseconds after which each attempt is rolled back if it has not ended.
+    ///
+    /// # Returns
+    ///
+    /// Returns the value returned by the callable.
+    pub fn transactional(
+        &self,
+        callback: &Zval,
+        behavior: Option<String>,
+        retries: Option<i64>,
+        backoff: Option<i64>,
+        timeout: Option<i64>,
+    ) -> Result<Zval, PhpException> {
+        catch_panic(|| {
+            let tx_behavior = behavior
+                .as_deref()
+                .map(|s| s.to_uppercase())
+                .unwrap_or_else(|| "DEFERRED".to_string());
+            let retries = retries
+                .map(|retries| retries.max(0) as u32)
+                .unwrap_or(hooks::use_transactional::DEFAULT_RETRIES);
+            let backoff = backoff
+                .map(|backoff| backoff.max(0) as u64)
+                .unwrap_or(hooks::use_transactional::DEFAULT_BACKOFF_MS);
+            let timeout = timeout
+                .filter(|timeout| *timeout > 0)
+                .map(|timeout| std::time::Duration::from_millis(timeout as u64));
+
+            hooks::use_transactional::transactional(
+                &self.conn_id,
+                self.type_map.as_ref(),
+                callback,
+                &tx_behavior,
+                retries,
+                std::time::Duration::from_millis(backoff),
+                timeout,
+            )
+        })
     }
 
     /// Prepares a SQL statement for execution.
@@ -456,7 +1001,110 @@ impl LibSQL {
     ///
     /// Returns a `LibSQLStatement` instance representing the prepared statement.
     pub fn prepare(&self, sql: &str) -> Result<LibSQLStatement, PhpException> {
-        LibSQLStatement::__construct(self.conn_id.clone(), sql)
+        catch_panic(|| {
+            let mut statement = LibSQLStatement::__construct(self.conn_id.clone(), sql)?;
+            statement.type_map = self.type_map.clone();
+            Ok(statement)
+        })
+    }
+
+    /// Sets a handler called periodically while a query runs, which aborts the query by returning `true`.
+    ///
+    /// # Arguments
+    ///
+    /// * `ops` - The number of virtual machine instructions between two calls of the handler.
+    /// * `callback` - The handler, or `null` to remove it. A value of `ops` below 1 removes it as well.
+    pub fn set_progress_handler(
+        &self,
+        ops: i64,
+        callback: Option<&Zval>,
+    ) -> Result<(), PhpException> {
+        catch_panic(|| {
+            let callable = callback
+                .filter(|callback| !callback.is_null())
+                .map(|callback| ZendCallable::new_owned(callback.shallow_clone()))
+                .transpose()
+                .map_err(|_| {
+                    PhpException::default("setProgressHandler expects a callable".to_string())
+                })?;
+            let ops = ops.clamp(0, i32::MAX as i64) as i32;
+
+            Ok(native_handle::set_progress_handler(
+                &self.conn_id,
+                ops,
+                callable,
+            )?)
+        })
+    }
+
+    /// Changes a runtime limit of the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The limit category, one of the `SQLITE_LIMIT_*` constants.
+    /// * `value` - The new value, capped at the maximum SQLite was built with.
+    ///
+    /// # Returns
+    ///
+    /// Returns the previous value of the limit.
+    pub fn set_limit(&self, id: i64, value: i64) -> Result<i64, PhpException> {
+        catch_panic(|| Ok(native_handle::limit(&self.conn_id, id, Some(value))?))
+    }
+
+    /// Retrieves a runtime limit of the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The limit category, one of the `SQLITE_LIMIT_*` constants.
+    ///
+    /// # Returns
+    ///
+    /// Returns the current value of the limit.
+    pub fn get_limit(&self, id: i64) -> Result<i64, PhpException> {
+        catch_panic(|| Ok(native_handle::limit(&self.conn_id, id, None)?))
+    }
+
+    /// Sets a callable that authorizes every action of the statements prepared on the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The authorizer, or `null` to remove it. It receives the action code, the
+    ///   two names describing the action, the database name and the innermost trigger or view,
+    ///   and returns `SQLITE_OK`, `SQLITE_DENY` or `SQLITE_IGNORE`.
+    pub fn set_authorizer(&self, callback: Option<&Zval>) -> Result<(), PhpException> {
+        catch_panic(|| {
+            let callable = callback
+                .filter(|callback| !callback.is_null())
+                .map(|callback| ZendCallable::new_owned(callback.shallow_clone()))
+                .transpose()
+                .map_err(|_| {
+                    PhpException::default("setAuthorizer expects a callable".to_string())
+                })?;
+
+            Ok(native_handle::set_authorizer(&self.conn_id, callable)?)
+        })
+    }
+
+    /// Interrupts the queries running on the connection.
+    ///
+    /// A local query stops at its next step and a query waiting on a remote database is
+    /// abandoned; either raises a `TimeoutException`. Since PHP waits for a query to finish,
+    /// this is meant to be called from another thread; a signal handler runs on the waiting
+    /// thread, so it cannot interrupt the query. The connection remains usable afterwards.
+    pub fn interrupt(&self) -> Result<(), PhpException> {
+        catch_panic(|| {
+            let conn = if self.mode == "offline_write" {
+                OFFLINE_CONNECTION_REGISTRY
+                    .get(&self.conn_id)
+                    .map(|offline_conn| offline_conn.local_conn.clone())
+                    .ok_or_else(|| PhpException::from("Connection not available"))?
+            } else {
+                self.connection()?.clone()
+            };
+
+            interrupt::interrupt(&self.conn_id, &conn);
+            Ok(())
+        })
     }
 
     /// Closes the database connection.
@@ -465,13 +1113,22 @@ impl LibSQL {
     ///
     /// Returns `Ok(())` if the connection is closed successfully, otherwise returns a `PhpException`.
     pub fn close(&self) -> Result<(), PhpException> {
-        if self.mode == "offline_write" {
-            let mut offline_registry = OFFLINE_CONNECTION_REGISTRY.lock().unwrap();
-            offline_registry.remove(&self.conn_id);
-            Ok(())
-        } else {
-            hooks::close::disconnect(self.conn_id.to_string())
-        }
+        catch_panic(|| {
+            CONNECTION_MODE_REGISTRY.remove(&self.conn_id);
+            CONNECTION_LIFECYCLE_REGISTRY.remove(&self.conn_id);
+            INTERRUPT_REGISTRY.remove(&self.conn_id);
+            DATETIME_BINDING_REGISTRY.remove(&self.conn_id);
+            native_handle::release(&self.conn_id);
+            request_state::untrack(Handle::Connection, &self.conn_id);
+            request_state::forget_persistent(&self.conn_id);
+
+            if self.mode == "offline_write" {
+                OFFLINE_CONNECTION_REGISTRY.remove(&self.conn_id);
+                Ok(())
+            } else {
+                hooks::close::disconnect(self.conn_id.to_string())
+            }
+        })
     }
 
     /// Synchronizes the database for remote replica connections.
@@ -498,18 +1155,19 @@ impl LibSQL {
     /// This function will not panic.
     /// Check connectivity status for offline write mode
     pub fn check_connectivity(&self) -> Result<bool, PhpException> {
-        if self.mode == "offline_write" {
-            let offline_registry = OFFLINE_CONNECTION_REGISTRY.lock().unwrap();
-            let offline_conn = offline_registry
-                .get(&self.conn_id)
-                .ok_or_else(|| PhpException::from("Offline connection not found"))?;
-
-            Ok(offline_conn.check_connectivity())
-        } else {
-            Err(PhpException::default(
-                "Connectivity check only available in offline_write mode".to_string(),
-            ))
-        }
+        catch_panic(|| {
+            if self.mode == "offline_write" {
+                let offline_conn = OFFLINE_CONNECTION_REGISTRY
+                    .get(&self.conn_id)
+                    .ok_or_else(|| PhpException::from("Offline connection not found"))?;
+
+                Ok(offline_conn.check_connectivity())
+            } else {
+                Err(PhpException::default(
+                    "Connectivity check only available in offline_write mode".to_string(),
+                ))
+            }
+        })
     }
 
     /// Returns the number of pending operations (e.g. unsent queries)
@@ -524,18 +1182,19 @@ impl LibSQL {
     ///
     /// A `PhpException` is returned if the mode is not `offline_write`.
     pub fn get_pending_operations_count(&self) -> Result<usize, PhpException> {
-        if self.mode == "offline_write" {
-            let offline_registry = OFFLINE_CONNECTION_REGISTRY.lock().unwrap();
-            let offline_conn = offline_registry
-                .get(&self.conn_id)
-                .ok_or_else(|| PhpException::from("Offline connection not found"))?;
-
-            Ok(offline_conn.get_pending_operations_count())
-        } else {
-            Err(PhpException::default(
-                "Pending operations only available in offline_write mode".to_string(),
-            ))
-        }
+        catch_panic(|| {
+            if self.mode == "offline_write" {
+                let offline_conn = OFFLINE_CONNECTION_REGISTRY
+                    .get(&self.conn_id)
+                    .ok_or_else(|| PhpException::from("Offline connection not found"))?;
+
+                Ok(offline_conn.get_pending_operations_count())
+            } else {
+                Err(PhpException::default(
+                    "Pending operations only available in offline_write mode".to_string(),
+                ))
+            }
+        })
     }
 
     /// Synchronizes the database for remote replica connections.
@@ -561,41 +1220,46 @@ impl LibSQL {
     ///
     /// This function will not panic.
     pub fn sync(&self, log_info: Option<bool>) -> Result<(), PhpException> {
-        let log_info = log_info.unwrap_or(false);
-
-        if self.mode == "remote_replica" {
-            match &self.db {
-                Some(db) => utils::runtime::runtime().block_on(async {
-                    db.sync()
-                        .await
-                        .map_err(|e| PhpException::default(format!("Sync failed: {}", e)))?;
-                    Ok(())
-                }),
-                None => Err(PhpException::default(
-                    "Database connection is not available for sync".to_string(),
-                )),
-            }
-        } else if self.mode == "offline_write" {
-            let offline_registry = OFFLINE_CONNECTION_REGISTRY.lock().unwrap();
-            let offline_conn = offline_registry
-                .get(&self.conn_id)
-                .ok_or_else(|| PhpException::from("Offline connection not found"))?;
-
-            match offline_conn.manual_sync() {
-                Ok(message) => {
-                    if log_info {
-                        println!("Sync result: {}", message);
+        catch_panic(|| {
+            let log_info = log_info.unwrap_or(false);
+
+            if self.mode == "remote_replica" {
+                match self.db.as_ref().map(ProcessLocal::get) {
+                    Some(None) => Err(PhpException::from("Connection not found")),
+                    Some(Some(db)) => utils::runtime::runtime().block_on(async {
+                        db.sync().await.map_err(|e| {
+                            LibSQLError::new(ErrorKind::Sync, format!("Sync failed: {}", e))
+                                .with_mode(&self.mode)
+                        })?;
+                        Ok::<(), PhpException>(())
+                    }),
+                    None => Err(PhpException::default(
+                        "Database connection is not available for sync".to_string(),
+                    )),
+                }
+            } else if self.mode == "offline_write" {
+                let offline_conn = OFFLINE_CONNECTION_REGISTRY
+                    .get(&self.conn_id)
+                    .ok_or_else(|| PhpException::from("Offline connection not found"))?;
+
+                match offline_conn.manual_sync() {
+                    Ok(message) => {
+                        if log_info {
+                            println!("Sync result: {}", message);
+                        }
+                        Ok(())
                     }
-                    Ok(())
+                    Err(e) => Err(LibSQLError::new(ErrorKind::Sync, e)
+                        .with_mode(&self.mode)
+                        .into()),
                 }
-                Err(e) => Err(PhpException::default(e)),
+            } else {
+                Err(PhpException::default(format!(
+                    "{} mode does not support sync",
+                    self.mode
+                )))
             }
-        } else {
-            Err(PhpException::default(format!(
-                "{} mode does not support sync",
-                self.mode
-            )))
-        }
+        })
     }
 
     /// Checks the online status of the connection.
@@ -613,18 +1277,19 @@ impl LibSQL {
     ///
     /// This function will not panic.
     pub fn is_online(&self) -> Result<bool, PhpException> {
-        if self.mode == "offline_write" {
-            let offline_registry = OFFLINE_CONNECTION_REGISTRY.lock().unwrap();
-            let offline_conn = offline_registry
-                .get(&self.conn_id)
-                .ok_or_else(|| PhpException::from("Offline connection not found"))?;
-
-            Ok(offline_conn.is_online())
-        } else {
-            Err(PhpException::default(
-                "Online status check only available in offline_write mode".to_string(),
-            ))
-        }
+        catch_panic(|| {
+            if self.mode == "offline_write" {
+                let offline_conn = OFFLINE_CONNECTION_REGISTRY
+                    .get(&self.conn_id)
+                    .ok_or_else(|| PhpException::from("Offline connection not found"))?;
+
+                Ok(offline_conn.is_online())
+            } else {
+                Err(PhpException::default(
+                    "Online status check only available in offline_write mode".to_string(),
+                ))
+            }
+        })
     }
 
     /// Enables or disables the loading of extensions for the given connection.
@@ -644,51 +1309,78 @@ impl LibSQL {
     /// - If the connection is not available.
     /// - If the operation fails.
     pub fn enable_load_extension(&self, onoff: Option<bool>) -> Result<(), PhpException> {
-        hooks::load_extensions::enable_load_extension(self.conn_id.to_string(), onoff)
+        catch_panic(|| {
+            hooks::load_extensions::enable_load_extension(self.conn_id.to_string(), onoff)
+        })
     }
 
     pub fn load_extensions(
         &self,
         extension_paths: Option<ExtensionParams>,
     ) -> Result<(), PhpException> {
-        let entry_point = None;
+        catch_panic(|| {
+            let entry_point = None;
 
-        match extension_paths {
-            Some(ExtensionParams::String(extension)) => {
-                hooks::load_extensions::load_extension(
-                    self.conn_id.to_string(),
-                    Path::new(&extension),
-                    entry_point,
-                )
-                .unwrap();
-            }
-            Some(ExtensionParams::Array(extensions)) => {
-                for extension in extensions {
+            match extension_paths {
+                Some(ExtensionParams::String(extension)) => {
                     hooks::load_extensions::load_extension(
                         self.conn_id.to_string(),
                         Path::new(&extension),
                         entry_point,
-                    )
-                    .unwrap();
+                    )?;
+                }
+                Some(ExtensionParams::Array(extensions)) => {
+                    for extension in extensions {
+                        hooks::load_extensions::load_extension(
+                            self.conn_id.to_string(),
+                            Path::new(&extension),
+                            entry_point,
+                        )?;
+                    }
+                }
+                None => {
+                    return Err(PhpException::default(
+                        "No extension paths provided".to_string(),
+                    ))
                 }
             }
-            None => Err(PhpException::default(
-                "No extension paths provided".to_string(),
-            ))
-            .unwrap(),
-        }
 
-        Ok(())
+            Ok(())
+        })
     }
 
-    pub fn capture_it(&self, event_type: String, query: Option<String>, message: Option<String>) -> Result<bool, PhpException> {
-        let payload = WebhookPayload {
-            event_type,
-            query: Some(query.unwrap_or("".to_string())),
-            message: Some(message.unwrap_or("".to_string())),
-        };
+    pub fn capture_it(
+        &self,
+        event_type: String,
+        query: Option<String>,
+        message: Option<String>,
+    ) -> Result<bool, PhpException> {
+        catch_panic(|| {
+            let payload = WebhookPayload {
+                event_type,
+                query: Some(query.unwrap_or("".to_string())),
+                message: Some(message.unwrap_or("".to_string())),
+            };
+
+            let cdc_url = self.cdc_url.clone().ok_or_else(|| {
+                PhpException::from("No CDC URL is configured for this connection")
+            })?;
 
-        Ok(send_webhook_data(self.cdc_url.clone().expect("REASON").to_string(), &payload))
+            Ok(send_webhook_data(cdc_url, &payload))
+        })
+    }
+}
+
+impl LibSQL {
+    /// Returns the connection, failing in the child of a fork, since it belongs to the parent.
+    fn connection(&self) -> Result<&libsql::Connection, PhpException> {
+        let conn = self
+            .conn
+            .as_ref()
+            .ok_or_else(|| PhpException::from("Connection not available"))?;
+
+        conn.get()
+            .ok_or_else(|| PhpException::from("Connection not found"))
     }
 }
 
@@ -755,30 +1447,30 @@ pub extern "C" fn libsql_php_extension_info(_module: *mut ext_php_rs::zend::Modu
 
 /// This function is called when the PHP module is shutdown. It is responsible for releasing
 /// any resources allocated by the module. In this case, it clears the connection, offline
-/// connection, transaction, and statement registries.
+/// connection, transaction, and statement registries, after reporting the handles left open
+/// if leak debugging is enabled.
 extern "C" fn libsql_php_shutdown(_type: i32, _module_number: i32) -> i32 {
-    if let Ok(mut registry) = CONNECTION_REGISTRY.lock() {
-        registry.clear();
-    } else {
-        log_error_to_tmp("Failed to lock CONNECTION_REGISTRY during shutdown");
-    }
-
-    if let Ok(mut registry) = OFFLINE_CONNECTION_REGISTRY.lock() {
-        registry.clear();
-    } else {
-        log_error_to_tmp("Failed to lock OFFLINE_CONNECTION_REGISTRY during shutdown");
-    }
+    leak_debug::report_open_handles();
+
+    CONNECTION_REGISTRY.clear();
+    OFFLINE_CONNECTION_REGISTRY.clear();
+    TRANSACTION_REGISTRY.clear();
+    STATEMENT_REGISTRY.clear();
+    STATEMENT_CACHE_REGISTRY.clear();
+    CONNECTION_MODE_REGISTRY.clear();
+    CONNECTION_LIFECYCLE_REGISTRY.clear();
+    INTERRUPT_REGISTRY.clear();
+    NATIVE_HANDLE_REGISTRY.clear();
+    DATETIME_BINDING_REGISTRY.clear();
 
-    if let Ok(mut registry) = TRANSACTION_REGISTRY.lock() {
-        registry.clear();
-    } else {
-        log_error_to_tmp("Failed to lock TRANSACTION_REGISTRY during shutdown");
-    }
+    0
+}
 
-    if let Ok(mut registry) = STATEMENT_REGISTRY.lock() {
-        registry.clear();
-    } else {
-        log_error_to_tmp("Failed to lock STATEMENT_REGISTRY during shutdown");
+/// This function is called at the end of every request. It rolls back the transactions,
+/// finalizes the statements and closes the non-persistent connections the request left open.
+extern "C" fn libsql_php_request_shutdown(_type: i32, _module_number: i32) -> i32 {
+    if std::panic::catch_unwind(request_state::reset_request_state).is_err() {
+        log_error_to_tmp("Failed to release the request state");
     }
 
     0
@@ -788,10 +1480,24 @@ extern "C" fn libsql_php_shutdown(_type: i32, _module_number: i32) -> i32 {
 pub fn get_module(module: ModuleBuilder) -> ModuleBuilder {
     module
         .class::<LibSQL>()
+        .class::<errors::LibSQLException>()
+        .class::<errors::ConnectionException>()
+        .class::<errors::ConstraintViolationException>()
+        .class::<errors::UniqueConstraintViolationException>()
+        .class::<errors::ForeignKeyConstraintViolationException>()
+        .class::<errors::NotNullConstraintViolationException>()
+        .class::<errors::CheckConstraintViolationException>()
+        .class::<errors::BusyException>()
+        .class::<errors::SyntaxException>()
+        .class::<errors::AuthException>()
+        .class::<errors::SyncException>()
+        .class::<errors::TimeoutException>()
+        .class::<blob::LibSQLBlob>()
         .class::<result::LibSQLResult>()
         .class::<generator::LibSQLIterator>()
         .class::<transaction::LibSQLTransaction>()
         .class::<statement::LibSQLStatement>()
         .info_function(libsql_php_extension_info)
         .shutdown_function(libsql_php_shutdown)
+        .request_shutdown_function(libsql_php_request_shutdown)
 }
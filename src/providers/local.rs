@@ -1,7 +1,8 @@
 use ext_php_rs::prelude::PhpException;
 
 use crate::{
-    utils::runtime::runtime, LIBSQL_OPEN_CREATE, LIBSQL_OPEN_READONLY, LIBSQL_OPEN_READWRITE,
+    providers::connection_error, utils::runtime::runtime, LIBSQL_OPEN_CREATE, LIBSQL_OPEN_READONLY,
+    LIBSQL_OPEN_READWRITE,
 };
 
 /// Creates a local database connection.
@@ -40,14 +41,17 @@ pub fn create_local_connection(
             None
         };
 
-        let db = libsql::Builder::new_local(url)
-            .flags(db_flags)
-            .encryption_config(encryption_config.unwrap())
+        let mut builder = libsql::Builder::new_local(url).flags(db_flags);
+        if let Some(encryption_config) = encryption_config {
+            builder = builder.encryption_config(encryption_config);
+        }
+
+        let db = builder
             .build()
             .await
-            .map_err(|e| PhpException::default(format!("Database build failed: {}", e)))?;
+            .map_err(|e| connection_error(&e, "Database build failed", "local"))?;
 
         db.connect()
-            .map_err(|e| PhpException::default(format!("Connection failed: {}", e)))
+            .map_err(|e| connection_error(&e, "Connection failed", "local"))
     })
 }
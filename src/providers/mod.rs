@@ -1,5 +1,19 @@
+use ext_php_rs::exception::PhpException;
+
+use crate::errors::{ErrorKind, LibSQLError};
+
 pub mod local;
 pub mod offline_write;
 pub mod remote;
 pub mod remote_replica;
 pub mod sqld_offline_write;
+
+/// Maps a failure to open a database to a `ConnectionException` unless a more specific kind,
+/// such as rejected credentials, was recognised.
+fn connection_error(error: &libsql::Error, context: &str, mode: &str) -> PhpException {
+    LibSQLError::from_libsql(error)
+        .or_kind(ErrorKind::Connection)
+        .context(context)
+        .with_mode(mode)
+        .into()
+}
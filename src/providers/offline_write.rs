@@ -1,19 +1,21 @@
-use crate::utils::runtime::runtime;
+use ext_php_rs::exception::PhpException;
+
+use crate::{providers::connection_error, utils::runtime::runtime};
 
 pub fn create_offline_write_connection(
     db_path: String,
     auth_token: String,
     sync_url: String,
-) -> (libsql::Database, libsql::Connection) {
-    let (db, conn) = runtime().block_on(async {
+) -> Result<(libsql::Database, libsql::Connection), PhpException> {
+    runtime().block_on(async {
         let db = libsql::Builder::new_synced_database(db_path, sync_url, auth_token)
             .build()
             .await
-            .unwrap();
-
-        let conn = db.connect().unwrap();
-        (db, conn)
-    });
+            .map_err(|e| connection_error(&e, "Database build failed", "remote_replica"))?;
 
-    (db, conn)
+        let conn = db
+            .connect()
+            .map_err(|e| connection_error(&e, "Connection failed", "remote_replica"))?;
+        Ok((db, conn))
+    })
 }
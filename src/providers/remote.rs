@@ -1,4 +1,6 @@
-use crate::utils::runtime::runtime;
+use ext_php_rs::exception::PhpException;
+
+use crate::{providers::connection_error, utils::runtime::runtime};
 
 /// Creates a connection to a remote database.
 ///
@@ -10,15 +12,17 @@ use crate::utils::runtime::runtime;
 /// # Returns
 ///
 /// Returns a `libsql::Connection` representing the connection to the remote database.
-pub fn create_remote_connection(url: String, auth_token: String) -> libsql::Connection {
-    let conn = runtime().block_on(async {
+pub fn create_remote_connection(
+    url: String,
+    auth_token: String,
+) -> Result<libsql::Connection, PhpException> {
+    runtime().block_on(async {
         let db = libsql::Builder::new_remote(url, auth_token)
             .build()
             .await
-            .unwrap();
-        let conn = db.connect().unwrap();
-        conn
-    });
+            .map_err(|e| connection_error(&e, "Database build failed", "remote"))?;
 
-    conn
+        db.connect()
+            .map_err(|e| connection_error(&e, "Connection failed", "remote"))
+    })
 }
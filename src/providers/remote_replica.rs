@@ -1,4 +1,6 @@
-use crate::utils::runtime::runtime;
+use ext_php_rs::exception::PhpException;
+
+use crate::{providers::connection_error, utils::runtime::runtime};
 
 /// Creates a new remote replica connection to a libSQL database.
 ///
@@ -21,9 +23,9 @@ use crate::utils::runtime::runtime;
 /// - A `libsql::Database` object representing the database.
 /// - A `libsql::Connection` object representing the connection.
 ///
-/// # Panics
+/// # Errors
 ///
-/// This function will panic if:
+/// Returns a `ConnectionException` if:
 /// - The database fails to build.
 /// - The connection to the database cannot be established.
 ///
@@ -37,7 +39,7 @@ use crate::utils::runtime::runtime;
 ///     std::time::Duration::from_secs(5),
 ///     true,
 ///     Some("encryption_key".to_string()),
-/// );
+/// )?;
 /// ```
 pub fn create_remote_replica_connection(
     url: String,
@@ -46,8 +48,8 @@ pub fn create_remote_replica_connection(
     sync_interval: std::time::Duration,
     read_your_writes: bool,
     encryption_key: Option<String>,
-) -> (libsql::Database, libsql::Connection) {
-    let (db, conn) = runtime().block_on(async {
+) -> Result<(libsql::Database, libsql::Connection), PhpException> {
+    runtime().block_on(async {
         let encryption_config = if let Some(key) = encryption_key {
             Some(libsql::EncryptionConfig::new(
                 libsql::Cipher::Aes256Cbc,
@@ -57,16 +59,21 @@ pub fn create_remote_replica_connection(
             None
         };
 
-        let db = libsql::Builder::new_remote_replica(url, sync_url, auth_token)
-            .encryption_config(encryption_config.unwrap())
+        let mut builder = libsql::Builder::new_remote_replica(url, sync_url, auth_token)
             .read_your_writes(read_your_writes)
-            .sync_interval(sync_interval)
+            .sync_interval(sync_interval);
+        if let Some(encryption_config) = encryption_config {
+            builder = builder.encryption_config(encryption_config);
+        }
+
+        let db = builder
             .build()
             .await
-            .unwrap();
-        let conn = db.connect().unwrap();
-        (db, conn)
-    });
+            .map_err(|e| connection_error(&e, "Database build failed", "remote_replica"))?;
 
-    (db, conn)
+        let conn = db
+            .connect()
+            .map_err(|e| connection_error(&e, "Connection failed", "remote_replica"))?;
+        Ok((db, conn))
+    })
 }
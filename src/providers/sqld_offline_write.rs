@@ -1,6 +1,14 @@
 use crate::{
+    errors::LibSQLError,
     providers,
-    utils::{log_error::log_error_to_tmp, query_params::QueryParameters, runtime::runtime},
+    utils::{
+        execute_many_result::ExecuteManyResult,
+        guard::LockExt,
+        interrupt,
+        log_error::log_error_to_tmp,
+        query_params::{ParameterSpec, QueryParameters},
+        runtime::runtime,
+    },
 };
 use ext_php_rs::prelude::PhpException;
 use libsql::Value;
@@ -9,6 +17,7 @@ use std::{
     sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
+#[derive(Clone)]
 pub struct OfflineWriteConnection {
     pub local_conn: libsql::Connection,
     pub remote_conn: libsql::Connection,
@@ -154,7 +163,8 @@ impl OfflineWriteConnection {
                 log_error_to_tmp(&format!("Failed to set schema_initialized: {}", err));
             }
         }
-        let remote_conn = providers::remote::create_remote_connection(sync_url.clone(), auth_token);
+        let remote_conn =
+            providers::remote::create_remote_connection(sync_url.clone(), auth_token)?;
         let initial_online_status = crate::utils::runtime::is_reachable(&sync_url);
         let connection = Self {
             local_conn,
@@ -188,15 +198,15 @@ impl OfflineWriteConnection {
         // Cache status for 5 seconds
         static LAST_CHECK: Mutex<Option<(Instant, bool)>> = Mutex::new(None);
 
-        let mut last_check = LAST_CHECK.lock().unwrap();
-        if let Some((time, status)) = *last_check {
+        if let Some((time, status)) = *LAST_CHECK.lock_or_recover() {
             if time.elapsed() < Duration::from_secs(5) {
                 return status;
             }
         }
 
+        // Not locked during the request, so other threads keep using the cached status
         let current_status = crate::utils::runtime::is_reachable(&self.remote_url);
-        *last_check = Some((Instant::now(), current_status));
+        *LAST_CHECK.lock_or_recover() = Some((Instant::now(), current_status));
         current_status
     }
 
@@ -385,7 +395,7 @@ impl OfflineWriteConnection {
     /// table in the order they were inserted, and they are added to the `pending_operations`
     /// field in the same order.
     fn load_pending_operations(&self) {
-        let mut ops = self.pending_operations.lock().unwrap();
+        let mut ops = self.pending_operations.lock_or_recover();
         ops.clear();
         let query_result = runtime().block_on(async {
             self.local_conn
@@ -520,7 +530,7 @@ impl OfflineWriteConnection {
     /// This function returns true if the remote connection is reachable, and false otherwise.
     pub fn check_connectivity(&self) -> bool {
         let is_reachable = crate::utils::runtime::is_reachable(&self.remote_url);
-        *self.is_online.lock().unwrap() = is_reachable;
+        *self.is_online.lock_or_recover() = is_reachable;
         is_reachable
     }
 
@@ -545,20 +555,99 @@ impl OfflineWriteConnection {
         sql: &str,
         parameters: Option<QueryParameters>,
     ) -> Result<u64, PhpException> {
-        let params = parameters.map(|p| p.to_params());
+        let (sql, parameters) = parameters.unwrap_or_default().expand_lists(sql)?;
+
+        let stmt = runtime()
+            .block_on(async { self.local_conn.prepare(&sql).await })
+            .map_err(|e| offline_error(&e, &sql))?;
+        let params = parameters.to_params_for(&ParameterSpec::from_statement(&stmt, &sql))?;
 
         let local_result = runtime()
-            .block_on(async {
-                self.local_conn
-                    .execute(sql, params.clone().unwrap_or(libsql::params::Params::None))
-                    .await
-            })
-            .map_err(|e| PhpException::from(format!("{:?}", e)))?;
+            .block_on(async { stmt.execute(params.clone()).await })
+            .map_err(|e| offline_error(&e, &sql))?;
 
-        self.queue_operation(sql, params, OperationType::Execute)
+        self.queue_operation(&sql, Some(params), OperationType::Execute)
             .map_err(|e| PhpException::from(format!("{:?}", e)))?;
 
-        Ok(local_result)
+        Ok(local_result as u64)
+    }
+
+    /// Executes a SQL statement once per parameter set on the local database, queueing the
+    /// executions to be synced with the remote database.
+    ///
+    /// Unless a transaction is already open, the executions run inside an implicit transaction
+    /// that is rolled back if any of them fails, in which case none of them is queued. The
+    /// query timeout of the connection applies to the executions as a whole.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn_id` - The ID of the connection.
+    /// * `sql` - The SQL statement to execute.
+    /// * `param_sets` - The parameter sets, one per execution.
+    ///
+    /// # Returns
+    ///
+    /// Returns the total number of rows changed and the last insert rowid of every execution.
+    pub fn execute_many(
+        &self,
+        conn_id: &str,
+        sql: &str,
+        param_sets: Vec<QueryParameters>,
+    ) -> Result<ExecuteManyResult, PhpException> {
+        let stmt = runtime()
+            .block_on(async { self.local_conn.prepare(sql).await })
+            .map_err(|e| offline_error(&e, sql))?;
+        let spec = ParameterSpec::from_statement(&stmt, sql);
+        let params = param_sets
+            .iter()
+            .map(|params| params.to_params_for(&spec))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let implicit_transaction = self.local_conn.is_autocommit();
+        if implicit_transaction {
+            runtime()
+                .block_on(async { self.local_conn.execute("BEGIN", ()).await })
+                .map_err(|e| offline_error(&e, sql))?;
+        }
+
+        let mut result = ExecuteManyResult::default();
+        let mut executed = Vec::new();
+        let outcome = interrupt::run(conn_id, &self.local_conn, None, async {
+            for params in params {
+                let changed = stmt.execute(params.clone()).await;
+                stmt.reset();
+
+                result.rows_changed += changed.map_err(|e| offline_error(&e, sql))? as u64;
+                result
+                    .last_insert_ids
+                    .push(self.local_conn.last_insert_rowid());
+                executed.push(params);
+            }
+            Ok(())
+        });
+
+        if implicit_transaction {
+            let end = if outcome.is_ok() {
+                "COMMIT"
+            } else {
+                "ROLLBACK"
+            };
+            let ended = runtime().block_on(async { self.local_conn.execute(end, ()).await });
+
+            match (&outcome, ended) {
+                (Ok(()), Err(e)) => return Err(offline_error(&e, sql)),
+                // Nothing was kept locally, so nothing is synced either
+                (Err(_), _) => executed.clear(),
+                _ => {}
+            }
+        }
+
+        for params in executed {
+            self.queue_operation(sql, Some(params), OperationType::Execute)
+                .map_err(|e| PhpException::from(format!("{:?}", e)))?;
+        }
+
+        outcome.map(|()| result)
     }
 
     /// Executes a SQL batch statement on the remote database if the connection is available.
@@ -579,7 +668,7 @@ impl OfflineWriteConnection {
             .block_on(async { self.local_conn.execute_batch(sql).await })
             .map_err(|e| {
                 log_error_to_tmp(&format!("Execute batch failed: {}", e));
-                PhpException::from(format!("{:?}", e))
+                offline_error(&e, sql)
             })?;
         self.queue_operation(sql, None, OperationType::ExecuteBatch)
             .map_err(|e| PhpException::from(format!("{:?}", e)))?;
@@ -601,7 +690,7 @@ impl OfflineWriteConnection {
             timestamp: std::time::SystemTime::now(),
         };
         pending_op.id = Some(self.save_pending_operation(&pending_op));
-        self.pending_operations.lock().unwrap().push(pending_op);
+        self.pending_operations.lock_or_recover().push(pending_op);
 
         // Attempt async sync if online
         if self.is_online() {
@@ -617,7 +706,7 @@ impl OfflineWriteConnection {
     /// # Arguments
     ///
     /// * `sql` - The SQL query to execute
-    /// * `parameters` - Optional query parameters
+    /// * `params` - The query parameters
     /// * `force_remote` - Whether to force using the remote connection when online
     ///
     /// # Returns
@@ -626,12 +715,9 @@ impl OfflineWriteConnection {
     pub fn query(
         &self,
         sql: &str,
-        parameters: Option<QueryParameters>,
+        params: libsql::params::Params,
         force_remote: Option<bool>,
-    ) -> Result<libsql::Rows, libsql::Error> {
-        let params = parameters
-            .map(|p| p.to_params())
-            .unwrap_or(libsql::params::Params::None);
+    ) -> Result<libsql::Rows, PhpException> {
         let force_remote = force_remote.unwrap_or(false);
 
         if force_remote && self.is_online() {
@@ -641,6 +727,31 @@ impl OfflineWriteConnection {
             // Default to local database
             runtime().block_on(async { self.local_conn.query(sql, params).await })
         }
+        .map_err(|e| offline_error(&e, sql))
+    }
+
+    /// Prepares a SQL statement on the same database `query` would read from.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The SQL statement to prepare
+    /// * `force_remote` - Whether to force using the remote connection when online
+    ///
+    /// # Returns
+    ///
+    /// The prepared statement
+    pub fn prepare(
+        &self,
+        sql: &str,
+        force_remote: Option<bool>,
+    ) -> Result<libsql::Statement, libsql::Error> {
+        let force_remote = force_remote.unwrap_or(false);
+
+        if force_remote && self.is_online() {
+            runtime().block_on(async { self.remote_conn.prepare(sql).await })
+        } else {
+            runtime().block_on(async { self.local_conn.prepare(sql).await })
+        }
     }
 
     /// Syncs any pending operations (i.e. operations that were executed on the local database
@@ -658,10 +769,11 @@ impl OfflineWriteConnection {
             log_error_to_tmp("Cannot sync: no internet connection");
             return Err("Cannot sync: no internet connection".to_string());
         }
-        let mut pending_ops = self.pending_operations.lock().unwrap();
+        // The queue is not locked while the operations are sent, so writes can be queued meanwhile
+        let pending_ops = std::mem::take(&mut *self.pending_operations.lock_or_recover());
         let mut synced_count = 0;
         let mut failed_ops = Vec::new();
-        for op in pending_ops.drain(..) {
+        for op in pending_ops {
             let sync_result = match op.operation_type {
                 OperationType::Execute => runtime().block_on(async {
                     self.remote_conn
@@ -686,7 +798,9 @@ impl OfflineWriteConnection {
                 }
             }
         }
-        pending_ops.extend(failed_ops);
+        let mut pending_ops = self.pending_operations.lock_or_recover();
+        failed_ops.append(&mut pending_ops);
+        *pending_ops = failed_ops;
         Ok(synced_count)
     }
 
@@ -700,7 +814,7 @@ impl OfflineWriteConnection {
         let _ = self.initial_sync_if_needed();
         match self.sync_pending_operations() {
             Ok(count) => {
-                let remaining = self.pending_operations.lock().unwrap().len();
+                let remaining = self.pending_operations.lock_or_recover().len();
                 Ok(format!(
                     "Synced {} operations, {} remaining",
                     count, remaining
@@ -720,7 +834,7 @@ impl OfflineWriteConnection {
     ///
     /// The number of pending operations as a `usize`.
     pub fn get_pending_operations_count(&self) -> usize {
-        self.pending_operations.lock().unwrap().len()
+        self.pending_operations.lock_or_recover().len()
     }
 
     /// Retrieves the number of changes made by the last executed statement.
@@ -814,3 +928,10 @@ pub fn create_sqld_offline_write_connection(
 ) -> Result<OfflineWriteConnection, PhpException> {
     OfflineWriteConnection::new(db_path, auth_token, sync_url, flags, encryption_key)
 }
+
+fn offline_error(error: &libsql::Error, sql: &str) -> PhpException {
+    LibSQLError::from_libsql(error)
+        .with_sql(sql)
+        .with_mode("offline_write")
+        .into()
+}
@@ -3,16 +3,22 @@
 use ext_php_rs::convert::IntoZval;
 
 use ext_php_rs::prelude::*;
-use ext_php_rs::types::Zval;
+use ext_php_rs::types::{ZendHashTable, Zval};
 use ext_php_rs::{php_class, php_impl};
-use std::collections::HashMap;
+use std::time::Duration;
 
 use crate::{
+    errors::LibSQLError,
     generator::LibSQLIterator,
-    hooks,
     utils::{
-        query_params::QueryParameters,
-        runtime::{convert_libsql_value_to_zval, runtime},
+        column_info::ColumnInfo,
+        fork::ProcessLocal,
+        guard::catch_panic,
+        interrupt,
+        query_params::{ParameterSpec, QueryParameters},
+        runtime::{remove_duplicates, runtime},
+        statement_cache::with_cached_statement,
+        type_map::{convert_value, PhpType, TypeMap},
     },
     CONNECTION_REGISTRY, LIBSQL_ALL, LIBSQL_ASSOC, LIBSQL_LAZY, LIBSQL_NUM,
     OFFLINE_CONNECTION_REGISTRY,
@@ -41,16 +47,87 @@ impl IntoZval for FetchResult {
     }
 }
 
+/// Rows materialized from a single execution of the query.
+struct FetchedRows {
+    /// Column names, in result order.
+    columns: Vec<String>,
+    /// PHP type of every column, in result order.
+    types: Vec<PhpType>,
+    /// Row values, in result order.
+    rows: Vec<Vec<libsql::Value>>,
+    rows_affected: u64,
+    last_insert_rowid: i64,
+}
+
+impl FetchedRows {
+    /// Converts every row into a PHP array laid out according to the fetch mode.
+    fn rows_to_zvals(&self, mode: i32) -> Result<Vec<Zval>, PhpException> {
+        let mut result = Vec::with_capacity(self.rows.len());
+
+        for row in self.rows.iter() {
+            let mut arr = ZendHashTable::new();
+
+            for (idx, value) in row.iter().enumerate() {
+                let column = self.columns[idx].as_str();
+                let ty = self.types.get(idx).copied().unwrap_or(PhpType::Auto);
+                let zval = convert_value(value.clone(), ty, column)?;
+
+                match mode {
+                    LIBSQL_ASSOC => arr.insert(column, zval)?,
+                    LIBSQL_NUM => arr.push(zval)?,
+                    _ => {
+                        arr.insert(column, zval.shallow_clone())?;
+                        arr.push(zval)?;
+                    }
+                }
+            }
+
+            result.push(arr.into_zval(false)?);
+        }
+
+        Ok(result)
+    }
+
+    /// Converts the rows into the `LIBSQL_ALL` result set layout.
+    fn into_result_set(self) -> Result<Zval, PhpException> {
+        let mut rows = ZendHashTable::new();
+        for row_zval in self.rows_to_zvals(LIBSQL_ASSOC)? {
+            rows.push(row_zval)?;
+        }
+
+        let mut columns = self.columns;
+        remove_duplicates(&mut columns);
+
+        let mut array = ZendHashTable::new();
+        array.insert("columns", columns)?;
+        array.insert("rows", rows)?;
+        array.insert("rows_affected", self.rows_affected)?;
+        array.insert("last_insert_rowid", self.last_insert_rowid)?;
+
+        Ok(array.into_zval(false)?)
+    }
+}
+
+/// Rows read from a prepared statement when the result was created.
+struct BufferedRows {
+    /// Column metadata of the statement, in result order.
+    column_info: Vec<ColumnInfo>,
+    fetched: FetchedRows,
+}
+
 #[php_class]
 pub struct LibSQLResult {
     pub conn_string: String,
-    pub conn: Option<libsql::Connection>,
+    pub conn: Option<ProcessLocal<libsql::Connection>>,
     pub sql: String,
     pub parameters: libsql::params::Params,
     pub query_params: Option<QueryParameters>,
     pub force_remote: Option<bool>,
     pub is_offline_mode: bool,
     pub sqld_offline_mode: bool,
+    pub type_map: Option<TypeMap>,
+    /// The rows of a result created from a prepared statement, read when it ran.
+    buffered: Option<BufferedRows>,
 }
 
 #[php_impl]
@@ -58,541 +135,465 @@ impl LibSQLResult {
     pub fn __construct(
         conn_id: String,
         sql: &str,
-        parameters: Option<QueryParameters>,
+        parameters: Option<&Zval>,
     ) -> Result<Self, PhpException> {
-        let conn_registry = CONNECTION_REGISTRY.lock().unwrap();
-        let conn = conn_registry
-            .get(&conn_id.clone())
-            .ok_or_else(|| PhpException::from("Connection not found"))?;
-        let params = if let Some(ref p) = parameters {
-            let params = p.to_params();
-            params
-        } else {
-            libsql::params::Params::None
-        };
-
-        Ok(Self {
-            conn_string: conn_id,
-            conn: Some(conn.clone()),
-            sql: sql.to_string(),
-            parameters: params,
-            query_params: parameters,
-            force_remote: None,
-            is_offline_mode: false,
-            sqld_offline_mode: false,
+        catch_panic(|| {
+            let (sql, parameters) = QueryParameters::from_argument(parameters, &conn_id)?
+                .unwrap_or_default()
+                .expand_lists(sql)?;
+
+            let conn = CONNECTION_REGISTRY
+                .get(&conn_id.clone())
+                .ok_or_else(|| PhpException::from("Connection not found"))?;
+            let stmt = runtime()
+                .block_on(async { conn.prepare(&sql).await })
+                .map_err(|e| query_error(&e, &sql, &conn_id))?;
+            let params = parameters.to_params_for(&ParameterSpec::from_statement(&stmt, &sql))?;
+
+            Ok(Self {
+                conn_string: conn_id,
+                conn: Some(ProcessLocal::new(conn.clone())),
+                sql,
+                parameters: params,
+                query_params: Some(parameters),
+                force_remote: None,
+                is_offline_mode: false,
+                sqld_offline_mode: false,
+                type_map: None,
+                buffered: None,
+            })
         })
     }
 
-    /// Constructor for offline write mode
-    pub fn __construct_offline(
-        conn_id: String,
-        sql: &str,
-        parameters: Option<QueryParameters>,
-        force_remote: Option<bool>,
-    ) -> Result<Self, PhpException> {
-        let params = if let Some(ref p) = parameters {
-            let params = p.to_params();
-            params
-        } else {
-            libsql::params::Params::None
-        };
+    /// Fetches every row of the result set.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The fetch mode (`LIBSQL_ASSOC`, `LIBSQL_NUM`, `LIBSQL_BOTH`, `LIBSQL_ALL` or `LIBSQL_LAZY`).
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the rows as an array (or iterator for `LIBSQL_LAZY`), or a `PhpException` if an error occurs.
+    pub fn fetch_array(&self, mode: Option<i32>) -> Result<FetchResult, PhpException> {
+        catch_panic(|| {
+            let mode = mode.unwrap_or(3);
+            let fetched = self.fetch_rows(None)?;
 
-        Ok(Self {
-            conn_string: conn_id,
-            conn: None, // We don't store the connection directly for offline mode
-            sql: sql.to_string(),
-            parameters: params,
-            query_params: parameters,
-            force_remote: Some(force_remote.unwrap_or(false)),
-            is_offline_mode: true,
-            sqld_offline_mode: true,
+            if mode == LIBSQL_ALL {
+                return Ok(FetchResult::Zval(fetched.into_result_set()?));
+            }
+
+            let mut arr = ZendHashTable::new();
+            for row_zval in fetched.rows_to_zvals(mode)? {
+                arr.push(row_zval)?;
+            }
+
+            let data = arr.into_zval(false)?;
+            if mode == LIBSQL_LAZY {
+                Ok(FetchResult::Iterator(LibSQLIterator::__construct(&data)))
+            } else {
+                Ok(FetchResult::Zval(data))
+            }
         })
     }
 
-    pub fn fetch_array(&self, mode: Option<i32>) -> Result<FetchResult, PhpException> {
-        let mode = mode.unwrap_or(3);
+    /// Fetches the first row of the result set.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The fetch mode (`LIBSQL_ASSOC`, `LIBSQL_NUM`, `LIBSQL_BOTH`, `LIBSQL_ALL` or `LIBSQL_LAZY`).
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the row as an array (or iterator for `LIBSQL_LAZY`), or a `PhpException` if an error occurs.
+    pub fn fetch_single(&self, mode: Option<i32>) -> Result<FetchResult, PhpException> {
+        catch_panic(|| {
+            let mode = mode.unwrap_or(3);
+            let fetched = self.fetch_rows(Some(1))?;
+
+            if mode == LIBSQL_ALL {
+                let first_row = fetched
+                    .rows_to_zvals(LIBSQL_ASSOC)?
+                    .into_iter()
+                    .next()
+                    .unwrap_or_else(Zval::new);
+
+                return Ok(FetchResult::Zval(first_row));
+            }
 
-        if self.is_offline_mode {
-            return self.fetch_array_offline(mode);
-        }
+            let row = match fetched.rows_to_zvals(mode)?.into_iter().next() {
+                Some(row_zval) => row_zval,
+                None => ZendHashTable::new().into_zval(false)?,
+            };
 
-        if mode != LIBSQL_ALL {
-            let conn = self
-                .conn
-                .as_ref()
-                .ok_or_else(|| PhpException::from("Connection not available"))?;
+            if mode == LIBSQL_LAZY {
+                Ok(FetchResult::Iterator(LibSQLIterator::__construct(&row)))
+            } else {
+                Ok(FetchResult::Zval(row))
+            }
+        })
+    }
 
-            let query_result = runtime().block_on(async {
-                let mut rows = conn
-                    .query(self.sql.as_str(), self.parameters.clone())
-                    .await
-                    .map_err(|e| PhpException::from(e.to_string()))?;
-                let mut results: Vec<HashMap<String, libsql::Value>> = Vec::new();
-
-                while let Ok(Some(row)) = rows.next().await {
-                    let mut result = HashMap::new();
-
-                    if mode == LIBSQL_ASSOC {
-                        for idx in 0..rows.column_count() {
-                            let column_name = row.column_name(idx as i32).unwrap();
-                            let value = row.get_value(idx).unwrap();
-
-                            result.insert(column_name.to_string(), value);
-                        }
-                        results.push(result);
-                    } else if mode == LIBSQL_NUM {
-                        for idx in 0..rows.column_count() {
-                            let value = row.get_value(idx).unwrap();
-
-                            result.insert(idx.to_string(), value);
-                        }
-                        results.push(result);
-                    } else {
-                        for idx in 0..rows.column_count() {
-                            let column_name = row.column_name(idx as i32).unwrap();
-                            let value = row.get_value(idx).unwrap();
-
-                            result.insert(column_name.to_string(), value.clone());
-                            result.insert(idx.to_string(), value);
-                        }
-                        results.push(result);
-                    }
-                }
+    /// Retrieves the metadata of every column returned by the query.
+    ///
+    /// The metadata is derived from the prepared statement, so it is available even
+    /// when the query does not return any rows.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing one array per column with its `name`, `decl_type`,
+    /// `table_name`, `origin_name` and `database_name`, or a `PhpException` if an error occurs.
+    pub fn columns(&self) -> Result<Vec<ColumnInfo>, PhpException> {
+        catch_panic(|| self.column_info())
+    }
 
-                Ok(results)
-            });
-
-            match query_result {
-                Ok(results) => {
-                    let mut arr = ext_php_rs::types::ZendHashTable::new();
-
-                    for result in results {
-                        let mut sub_arr = ext_php_rs::types::ZendHashTable::new();
-                        for (key, value) in result {
-                            let zval_value = convert_libsql_value_to_zval(value);
-                            match key.parse::<i64>() {
-                                Ok(_) => sub_arr.push(zval_value)?,
-                                Err(_) => sub_arr.insert(key.as_str(), zval_value)?,
-                            }
-                        }
-                        arr.push(sub_arr)?;
-                    }
+    /// Retrieves the name of the column at the given index.
+    pub fn column_name(&self, column_index: i32) -> Result<String, PhpException> {
+        catch_panic(|| Ok(self.column_at(column_index)?.name))
+    }
 
-                    let zval_arr = if mode == LIBSQL_LAZY {
-                        let data = arr.into_zval(false).unwrap();
-                        FetchResult::Iterator(LibSQLIterator::__construct(&data))
-                    } else {
-                        FetchResult::Zval(arr.into_zval(false).unwrap())
-                    };
-                    Ok(zval_arr)
-                }
-                Err(e) => Err(e),
-            }
-        } else {
-            Ok(FetchResult::Zval(hooks::use_query::query(
-                self.conn_string.clone(),
-                self.sql.as_str(),
-                self.query_params.clone(),
-            )?))
-        }
+    /// Retrieves the declared type of the column at the given index.
+    ///
+    /// Returns `null` for columns without a declared type, such as expressions.
+    pub fn column_type(&self, column_index: i32) -> Result<Option<String>, PhpException> {
+        catch_panic(|| Ok(self.column_at(column_index)?.decl_type))
     }
 
-    /// Fetch array method for offline mode
-    fn fetch_array_offline(&self, mode: i32) -> Result<FetchResult, PhpException> {
-        let offline_registry = OFFLINE_CONNECTION_REGISTRY.lock().unwrap();
-        let offline_conn = offline_registry
-            .get(&self.conn_string)
-            .ok_or_else(|| PhpException::from("Offline connection not found"))?;
-
-        let query_result = match offline_conn.query(
-            self.sql.as_str(),
-            self.query_params.clone(),
-            self.force_remote.clone(),
-        ) {
-            Ok(mut rows) => {
-                let mut results: Vec<HashMap<String, libsql::Value>> = Vec::new();
-
-                runtime().block_on(async {
-                    while let Ok(Some(row)) = rows.next().await {
-                        let mut result = HashMap::new();
-
-                        if mode == LIBSQL_ASSOC {
-                            for idx in 0..rows.column_count() {
-                                let column_name = row.column_name(idx as i32).unwrap();
-                                let value = row.get_value(idx).unwrap();
-                                result.insert(column_name.to_string(), value);
-                            }
-                        } else if mode == LIBSQL_NUM {
-                            for idx in 0..rows.column_count() {
-                                let value = row.get_value(idx).unwrap();
-                                result.insert(idx.to_string(), value);
-                            }
-                        } else {
-                            for idx in 0..rows.column_count() {
-                                let column_name = row.column_name(idx as i32).unwrap();
-                                let value = row.get_value(idx).unwrap();
-                                result.insert(column_name.to_string(), value.clone());
-                                result.insert(idx.to_string(), value);
-                            }
-                        }
-                        results.push(result);
-                    }
-                });
+    /// Retrieves the number of columns returned by the query.
+    pub fn num_columns(&self) -> Result<i32, PhpException> {
+        catch_panic(|| Ok(self.column_info()?.len() as i32))
+    }
 
-                Ok(results)
+    pub fn reset(&self) -> Result<(), PhpException> {
+        catch_panic(|| {
+            if self.is_offline_mode {
+                let offline_conn = OFFLINE_CONNECTION_REGISTRY
+                    .get(&self.conn_string)
+                    .ok_or_else(|| PhpException::from("Offline connection not found"))?;
+
+                runtime().block_on(async { offline_conn.reset().await });
+                Ok(())
+            } else {
+                let conn = self.connection()?;
+
+                runtime().block_on(async { conn.reset().await });
+                Ok(())
             }
-            Err(e) => Err(PhpException::from(e.to_string())),
-        };
-
-        match query_result {
-            Ok(results) => {
-                let mut arr = ext_php_rs::types::ZendHashTable::new();
-
-                for result in results {
-                    let mut sub_arr = ext_php_rs::types::ZendHashTable::new();
-                    for (key, value) in result {
-                        let zval_value = convert_libsql_value_to_zval(value);
-                        match key.parse::<i64>() {
-                            Ok(_) => sub_arr.push(zval_value)?,
-                            Err(_) => sub_arr.insert(key.as_str(), zval_value)?,
-                        }
-                    }
-                    arr.push(sub_arr)?;
-                }
+        })
+    }
+}
 
-                let zval_arr = if mode == LIBSQL_LAZY {
-                    let data = arr.into_zval(false).unwrap();
-                    FetchResult::Iterator(LibSQLIterator::__construct(&data))
-                } else {
-                    FetchResult::Zval(arr.into_zval(false).unwrap())
-                };
-                Ok(zval_arr)
-            }
-            Err(e) => Err(e),
-        }
+impl LibSQLResult {
+    /// Returns the connection of the result, failing in the child of a fork, since it belongs
+    /// to the parent.
+    fn connection(&self) -> Result<&libsql::Connection, PhpException> {
+        let conn = self
+            .conn
+            .as_ref()
+            .ok_or_else(|| PhpException::from("Connection not available"))?;
+
+        conn.get()
+            .ok_or_else(|| PhpException::from("Connection not found"))
     }
 
-    pub fn fetch_single(&self, mode: Option<i32>) -> Result<FetchResult, PhpException> {
-        let mode = mode.unwrap_or(3);
+    /// Constructor for offline write mode
+    pub fn __construct_offline(
+        conn_id: String,
+        sql: &str,
+        parameters: Option<QueryParameters>,
+        force_remote: Option<bool>,
+    ) -> Result<Self, PhpException> {
+        catch_panic(|| {
+            let (sql, parameters) = parameters.unwrap_or_default().expand_lists(sql)?;
+
+            let params = {
+                let offline_conn = OFFLINE_CONNECTION_REGISTRY
+                    .get(&conn_id)
+                    .ok_or_else(|| PhpException::from("Offline connection not found"))?;
+                let stmt = offline_conn
+                    .prepare(&sql, force_remote)
+                    .map_err(|e| query_error(&e, &sql, &conn_id))?;
+
+                parameters.to_params_for(&ParameterSpec::from_statement(&stmt, &sql))?
+            };
+
+            Ok(Self {
+                conn_string: conn_id,
+                conn: None, // We don't store the connection directly for offline mode
+                sql,
+                parameters: params,
+                query_params: Some(parameters),
+                force_remote: Some(force_remote.unwrap_or(false)),
+                is_offline_mode: true,
+                sqld_offline_mode: true,
+                type_map: None,
+                buffered: None,
+            })
+        })
+    }
 
-        if self.is_offline_mode {
-            return self.fetch_single_offline(mode);
-        }
+    /// Runs a prepared statement and buffers its rows, resetting the statement so it can be reused.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn_id` - The ID of the connection the statement was prepared on.
+    /// * `conn` - The connection the statement was prepared on.
+    /// * `sql` - The SQL of the statement.
+    /// * `stmt` - The prepared statement.
+    /// * `params` - The validated parameters to run the statement with.
+    /// * `timeout` - The timeout of the query, or `None` for the timeout of the connection.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the result, or a `PhpException` if the query fails.
+    pub fn from_statement(
+        conn_id: String,
+        conn: libsql::Connection,
+        sql: &str,
+        stmt: &mut libsql::Statement,
+        params: libsql::params::Params,
+        timeout: Option<Duration>,
+    ) -> Result<Self, PhpException> {
+        let column_info = ColumnInfo::from_statement(stmt);
+        let collected = interrupt::run(&conn_id, &conn, timeout, async {
+            let rows = stmt
+                .query(params)
+                .await
+                .map_err(|e| query_error(&e, sql, &conn_id))?;
+            read_rows(rows, None).await
+        });
+        stmt.reset();
+        let (columns, rows) = collected?;
+
+        let fetched = FetchedRows {
+            columns,
+            types: Vec::new(),
+            rows,
+            rows_affected: conn.changes(),
+            last_insert_rowid: conn.last_insert_rowid(),
+        };
 
-        if mode != LIBSQL_ALL {
-            let conn = self
-                .conn
-                .as_ref()
-                .ok_or_else(|| PhpException::from("Connection not available"))?;
+        Ok(Self {
+            conn_string: conn_id,
+            conn: Some(ProcessLocal::new(conn)),
+            sql: sql.to_string(),
+            parameters: libsql::params::Params::None,
+            query_params: None,
+            force_remote: None,
+            is_offline_mode: false,
+            sqld_offline_mode: false,
+            type_map: None,
+            buffered: Some(BufferedRows {
+                column_info,
+                fetched,
+            }),
+        })
+    }
 
-            let query_result = runtime().block_on(async {
-                let mut rows = conn
-                    .query(self.sql.as_str(), self.parameters.clone())
-                    .await
-                    .map_err(|e| PhpException::from(e.to_string()))?;
-                let mut result = HashMap::new();
-
-                if let Ok(Some(row)) = rows.next().await {
-                    match mode {
-                        LIBSQL_ASSOC => {
-                            for idx in 0..rows.column_count() {
-                                let column_name = row.column_name(idx as i32).unwrap();
-                                result.insert(column_name.to_string(), row.get_value(idx).unwrap());
-                            }
-                        }
-                        LIBSQL_NUM => {
-                            for idx in 0..rows.column_count() {
-                                result.insert(idx.to_string(), row.get_value(idx).unwrap());
-                            }
-                        }
-                        _ => {
-                            for idx in 0..rows.column_count() {
-                                let column_name = row.column_name(idx as i32).unwrap();
-                                let value = row.get_value(idx).unwrap();
-                                result.insert(column_name.to_string(), value.clone());
-                                result.insert(idx.to_string(), value);
-                            }
-                        }
-                    }
-                }
+    /// Runs a query through the connection's prepared statement cache and buffers its rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn_id` - The ID of the connection.
+    /// * `sql` - The SQL query to run.
+    /// * `parameters` - The parameters of the query.
+    /// * `timeout` - The timeout of the query, or `None` for the timeout of the connection.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the result, or a `PhpException` if the query fails.
+    pub fn from_cached_statement(
+        conn_id: String,
+        sql: &str,
+        parameters: Option<QueryParameters>,
+        timeout: Option<Duration>,
+    ) -> Result<Self, PhpException> {
+        let conn = CONNECTION_REGISTRY
+            .get(&conn_id)
+            .ok_or_else(|| PhpException::from("Connection not found"))?;
 
-                Ok(result)
-            });
-
-            match query_result {
-                Ok(result) => {
-                    let mut sub_arr = ext_php_rs::types::ZendHashTable::new();
-                    for (key, value) in result {
-                        let zval_value = convert_libsql_value_to_zval(value);
-                        match key.parse::<usize>() {
-                            Ok(_) => sub_arr.push(zval_value)?,
-                            Err(_) => sub_arr.insert(key.as_str(), zval_value)?,
-                        }
-                    }
+        LibSQLResult::from_connection(conn_id, conn, sql, parameters, timeout)
+    }
 
-                    let fetch_result = if mode == LIBSQL_LAZY {
-                        let data = sub_arr.into_zval(false)?;
-                        FetchResult::Iterator(LibSQLIterator::__construct(&data))
-                    } else {
-                        FetchResult::Zval(sub_arr.into_zval(false)?)
-                    };
-                    Ok(fetch_result)
-                }
-                Err(e) => Err(e),
-            }
-        } else {
-            let result_set = hooks::use_query::query(
-                self.conn_string.clone(),
-                self.sql.as_str(),
-                self.query_params.clone(),
-            )?;
+    /// Runs a query on a connection handle, such as the one of a transaction, through the
+    /// statement cache of its connection, and buffers its rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn_id` - The ID of the connection whose statement cache is used.
+    /// * `conn` - The connection handle to run the query on.
+    /// * `sql` - The SQL query to run.
+    /// * `parameters` - The parameters of the query.
+    /// * `timeout` - The timeout of the query, or `None` for the timeout of the connection.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the result, or a `PhpException` if the query fails.
+    pub fn from_connection(
+        conn_id: String,
+        conn: libsql::Connection,
+        sql: &str,
+        parameters: Option<QueryParameters>,
+        timeout: Option<Duration>,
+    ) -> Result<Self, PhpException> {
+        let (sql, parameters) = parameters.unwrap_or_default().expand_lists(sql)?;
 
-            let arr = result_set
-                .array()
-                .ok_or_else(|| PhpException::from("Result set is not an array"))?;
-
-            let rows = arr
-                .get("rows")
-                .and_then(|zv| zv.array())
-                .ok_or_else(|| PhpException::from("Missing rows in result set"))?;
-
-            let first_row = rows
-                .get_index(0)
-                .map(|zv| {
-                    let z = Zval::new();
-                    unsafe {
-                        std::ptr::copy_nonoverlapping(zv.value.counted, z.value.counted, 1);
-                    }
-                    z
-                })
-                .unwrap_or_else(Zval::new);
+        with_cached_statement(&conn_id, &conn, &sql, |stmt| {
+            let params = parameters.to_params_for(&ParameterSpec::from_statement(stmt, &sql))?;
 
-            Ok(FetchResult::Zval(first_row))
-        }
+            LibSQLResult::from_statement(conn_id.clone(), conn.clone(), &sql, stmt, params, timeout)
+        })
     }
 
-    /// Fetch single method for offline mode
-    fn fetch_single_offline(&self, mode: i32) -> Result<FetchResult, PhpException> {
-        let offline_registry = OFFLINE_CONNECTION_REGISTRY.lock().unwrap();
-        let offline_conn = offline_registry
-            .get(&self.conn_string)
-            .ok_or_else(|| PhpException::from("Offline connection not found"))?;
-
-        let query_result = match offline_conn.query(
-            self.sql.as_str(),
-            self.query_params.clone(),
-            self.force_remote.clone(),
-        ) {
-            Ok(mut rows) => {
-                let mut result = HashMap::new();
-
-                runtime().block_on(async {
-                    if let Ok(Some(row)) = rows.next().await {
-                        match mode {
-                            LIBSQL_ASSOC => {
-                                for idx in 0..rows.column_count() {
-                                    let column_name = row.column_name(idx as i32).unwrap();
-                                    result.insert(
-                                        column_name.to_string(),
-                                        row.get_value(idx).unwrap(),
-                                    );
-                                }
-                            }
-                            LIBSQL_NUM => {
-                                for idx in 0..rows.column_count() {
-                                    result.insert(idx.to_string(), row.get_value(idx).unwrap());
-                                }
-                            }
-                            _ => {
-                                for idx in 0..rows.column_count() {
-                                    let column_name = row.column_name(idx as i32).unwrap();
-                                    let value = row.get_value(idx).unwrap();
-                                    result.insert(column_name.to_string(), value.clone());
-                                    result.insert(idx.to_string(), value);
-                                }
-                            }
-                        }
-                    }
-                });
-
-                Ok(result)
-            }
-            Err(e) => Err(PhpException::from(e.to_string())),
+    /// Executes the query and materializes its rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum number of rows to read, or `None` to read every row.
+    fn fetch_rows(&self, limit: Option<usize>) -> Result<FetchedRows, PhpException> {
+        // Resolve the column types before locking the offline registry, since
+        // `column_info` needs the same lock.
+        let types = match &self.type_map {
+            Some(type_map) => Some(type_map.resolve(&self.column_info()?)),
+            None => None,
         };
 
-        match query_result {
-            Ok(result) => {
-                let mut sub_arr = ext_php_rs::types::ZendHashTable::new();
-                for (key, value) in result {
-                    let zval_value = convert_libsql_value_to_zval(value);
-                    match key.parse::<usize>() {
-                        Ok(_) => sub_arr.push(zval_value)?,
-                        Err(_) => sub_arr.insert(key.as_str(), zval_value)?,
-                    }
-                }
-
-                let fetch_result = if mode == LIBSQL_LAZY {
-                    let data = sub_arr.into_zval(false)?;
-                    FetchResult::Iterator(LibSQLIterator::__construct(&data))
-                } else {
-                    FetchResult::Zval(sub_arr.into_zval(false)?)
-                };
-                Ok(fetch_result)
+        let mut fetched = if let Some(buffered) = &self.buffered {
+            let buffered = &buffered.fetched;
+
+            FetchedRows {
+                columns: buffered.columns.clone(),
+                types: Vec::new(),
+                rows: match limit {
+                    Some(limit) => buffered.rows.iter().take(limit).cloned().collect(),
+                    None => buffered.rows.clone(),
+                },
+                rows_affected: buffered.rows_affected,
+                last_insert_rowid: buffered.last_insert_rowid,
             }
-            Err(e) => Err(e),
-        }
-    }
-
-    pub fn column_name(&self, column_index: i32) -> Result<String, PhpException> {
-        if self.is_offline_mode {
-            let offline_registry = OFFLINE_CONNECTION_REGISTRY.lock().unwrap();
-            let offline_conn = offline_registry
+        } else if self.is_offline_mode {
+            let offline_conn = OFFLINE_CONNECTION_REGISTRY
                 .get(&self.conn_string)
                 .ok_or_else(|| PhpException::from("Offline connection not found"))?;
 
-            match offline_conn.query(
+            let rows = offline_conn.query(
                 self.sql.as_str(),
-                self.query_params.clone(),
+                self.parameters.clone(),
                 self.force_remote.clone(),
-            ) {
-                Ok(mut rows) => runtime().block_on(async {
-                    if let Ok(Some(row)) = rows.next().await {
-                        let column_name = row.column_name(column_index).ok_or_else(|| {
-                            PhpException::from(format!(
-                                "Column index {} out of bounds",
-                                column_index
-                            ))
-                        })?;
-                        Ok(column_name.to_string())
-                    } else {
-                        Err(PhpException::from("No rows returned from query"))
-                    }
-                }),
-                Err(e) => Err(PhpException::from(e.to_string())),
+            )?;
+            let (columns, rows) = collect_rows(rows, limit)?;
+
+            FetchedRows {
+                columns,
+                types: Vec::new(),
+                rows,
+                rows_affected: offline_conn.changes(),
+                last_insert_rowid: offline_conn.last_insert_rowid(),
             }
         } else {
-            let conn = self
-                .conn
-                .as_ref()
-                .ok_or_else(|| PhpException::from("Connection not available"))?;
+            let conn = self.connection()?;
 
-            runtime().block_on(async {
-                let mut rows = conn
+            let (columns, rows) = interrupt::run(&self.conn_string, conn, None, async {
+                let rows = conn
                     .query(self.sql.as_str(), self.parameters.clone())
                     .await
-                    .map_err(|e| PhpException::from(e.to_string()))?;
-
-                if let Ok(Some(row)) = rows.next().await {
-                    let column_name = row.column_name(column_index).ok_or_else(|| {
-                        PhpException::from(format!("Column index {} out of bounds", column_index))
-                    })?;
-                    Ok(column_name.to_string())
-                } else {
-                    Err(PhpException::from("No rows returned from query"))
-                }
-            })
-        }
+                    .map_err(|e| query_error(&e, &self.sql, &self.conn_string))?;
+                read_rows(rows, limit).await
+            })?;
+
+            FetchedRows {
+                columns,
+                types: Vec::new(),
+                rows,
+                rows_affected: conn.changes(),
+                last_insert_rowid: conn.last_insert_rowid(),
+            }
+        };
+
+        fetched.types = types.unwrap_or_else(|| vec![PhpType::Auto; fetched.columns.len()]);
+        Ok(fetched)
     }
 
-    pub fn column_type(&self, column_index: i32) -> Result<String, PhpException> {
-        if self.is_offline_mode {
-            let offline_registry = OFFLINE_CONNECTION_REGISTRY.lock().unwrap();
-            let offline_conn = offline_registry
+    /// Prepares the query on its connection and collects the column metadata.
+    fn column_info(&self) -> Result<Vec<ColumnInfo>, PhpException> {
+        if let Some(buffered) = &self.buffered {
+            Ok(buffered.column_info.clone())
+        } else if self.is_offline_mode {
+            let offline_conn = OFFLINE_CONNECTION_REGISTRY
                 .get(&self.conn_string)
                 .ok_or_else(|| PhpException::from("Offline connection not found"))?;
 
-            match offline_conn.query(self.sql.as_str(), self.query_params.clone(), self.force_remote.clone()) {
-                Ok(mut rows) => {
-                    runtime().block_on(async {
-                        if let Ok(Some(row)) = rows.next().await {
-                            let column_type = row.column_type(column_index).or_else(|_| {
-                                Err(PhpException::from(format!("Column index {} out of bounds", column_index)))
-                            })?;
-                            Ok(format!("{:?}", column_type))
-                        } else {
-                            Err(PhpException::from("No rows returned from query"))
-                        }
-                    })
-                }
-                Err(e) => Err(PhpException::from(e.to_string())),
-            }
+            let stmt = offline_conn
+                .prepare(self.sql.as_str(), self.force_remote)
+                .map_err(|e| query_error(&e, &self.sql, &self.conn_string))?;
+
+            Ok(ColumnInfo::from_statement(&stmt))
         } else {
-            let conn = self.conn.as_ref()
-                .ok_or_else(|| PhpException::from("Connection not available"))?;
+            let conn = self.connection()?;
 
-            runtime().block_on(async {
-                let mut rows = conn
-                    .query(self.sql.as_str(), self.parameters.clone())
-                    .await
-                    .map_err(|e| PhpException::from(e.to_string()))?;
-
-                if let Ok(Some(row)) = rows.next().await {
-                    let column_type = row.column_type(column_index).or_else(|_| {
-                        Err(PhpException::from(format!("Column index {} out of bounds", column_index)))
-                    })?;
-                    Ok(format!("{:?}", column_type))
-                } else {
-                    Err(PhpException::from("No rows returned from query"))
-                }
-            })
+            let stmt = runtime()
+                .block_on(async { conn.prepare(self.sql.as_str()).await })
+                .map_err(|e| query_error(&e, &self.sql, &self.conn_string))?;
+
+            Ok(ColumnInfo::from_statement(&stmt))
         }
     }
 
-    pub fn num_columns(&self) -> Result<i32, PhpException> {
-        if self.is_offline_mode {
-            let offline_registry = OFFLINE_CONNECTION_REGISTRY.lock().unwrap();
-            let offline_conn = offline_registry
-                .get(&self.conn_string)
-                .ok_or_else(|| PhpException::from("Offline connection not found"))?;
-
-            match offline_conn.query(self.sql.as_str(), self.query_params.clone(), self.force_remote.clone()) {
-                Ok(mut rows) => {
-                    runtime().block_on(async {
-                        if let Ok(Some(row)) = rows.next().await {
-                            Ok(row.column_count() as i32)
-                        } else {
-                            Err(PhpException::from("No rows returned from query"))
-                        }
-                    })
-                }
-                Err(e) => Err(PhpException::from(e.to_string())),
-            }
-        } else {
-            let conn = self.conn.as_ref()
-                .ok_or_else(|| PhpException::from("Connection not available"))?;
+    /// Retrieves the metadata of the column at the given index.
+    fn column_at(&self, column_index: i32) -> Result<ColumnInfo, PhpException> {
+        let columns = self.column_info()?;
 
-            runtime().block_on(async {
-                let mut rows = conn
-                    .query(self.sql.as_str(), self.parameters.clone())
-                    .await
-                    .map_err(|e| PhpException::from(e.to_string()))?;
-
-                if let Ok(Some(row)) = rows.next().await {
-                    Ok(row.column_count() as i32)
-                } else {
-                    Err(PhpException::from("No rows returned from query"))
-                }
+        usize::try_from(column_index)
+            .ok()
+            .and_then(|idx| columns.get(idx).cloned())
+            .ok_or_else(|| {
+                PhpException::from(format!("Column index {} out of bounds", column_index))
             })
-        }
     }
+}
 
-    pub fn reset(&self) -> Result<(), PhpException> {
-        if self.is_offline_mode {
-            let offline_registry = OFFLINE_CONNECTION_REGISTRY.lock().unwrap();
-            let offline_conn = offline_registry
-                .get(&self.conn_string)
-                .ok_or_else(|| PhpException::from("Offline connection not found"))?;
+/// Reads the column names and up to `limit` rows from a `libsql::Rows`.
+fn collect_rows(
+    rows: libsql::Rows,
+    limit: Option<usize>,
+) -> Result<(Vec<String>, Vec<Vec<libsql::Value>>), PhpException> {
+    runtime().block_on(read_rows(rows, limit))
+}
 
-            runtime().block_on(async { offline_conn.reset().await });
-            Ok(())
-        } else {
-            let conn = self
-                .conn
-                .as_ref()
-                .ok_or_else(|| PhpException::from("Connection not available"))?;
+/// Reads the column names and up to `limit` rows from a `libsql::Rows`, without blocking.
+async fn read_rows(
+    mut rows: libsql::Rows,
+    limit: Option<usize>,
+) -> Result<(Vec<String>, Vec<Vec<libsql::Value>>), PhpException> {
+    let column_count = rows.column_count();
+    let columns = (0..column_count)
+        .map(|idx| rows.column_name(idx).unwrap_or_default().to_string())
+        .collect::<Vec<_>>();
+
+    let mut values = Vec::new();
+    while limit.map_or(true, |limit| values.len() < limit) {
+        let row = match rows
+            .next()
+            .await
+            .map_err(|e| LibSQLError::from_libsql(&e).context("Row fetch failed"))?
+        {
+            Some(row) => row,
+            None => break,
+        };
 
-            runtime().block_on(async { conn.reset().await });
-            Ok(())
-        }
+        let row_values = (0..column_count)
+            .map(|idx| row.get_value(idx))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| PhpException::from(format!("Value retrieval failed: {}", e)))?;
+        values.push(row_values);
     }
+
+    Ok((columns, values))
+}
+
+fn query_error(error: &libsql::Error, sql: &str, conn_id: &str) -> PhpException {
+    LibSQLError::from_libsql(error)
+        .with_sql(sql)
+        .with_connection(conn_id)
+        .into()
 }
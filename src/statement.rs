@@ -1,17 +1,30 @@
-use ext_php_rs::convert::IntoZval;
 #[allow(non_snake_case, deprecated, unused_attributes)]
 #[cfg_attr(windows, feature(abi_vectorcall))]
-use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
-};
+use std::sync::{Arc, Mutex};
 
-use crate::{result::LibSQLResult, utils::query_params::QueryValue};
+use crate::result::LibSQLResult;
 use ext_php_rs::prelude::*;
+use ext_php_rs::types::Zval;
+use ext_php_rs::{args::Arg, flags::DataType, zend::ExecutorGlobals};
 use ext_php_rs::{php_class, php_impl};
 
 use crate::{
-    utils::{query_params::QueryParameters, runtime::runtime},
+    errors::LibSQLError,
+    hooks::{
+        self,
+        request_state::{self, Handle},
+    },
+    transaction::transaction_connection,
+    utils::{
+        column_info::ColumnInfo,
+        execute_many_result::ExecuteManyResult,
+        guard::{catch_panic, LockExt},
+        interrupt, leak_debug,
+        query_params::{Binding, Bindings, DateTimeBinding, ParameterSpec, QueryParameters},
+        runtime::runtime,
+        statement_cache::invalidate_on_schema_change,
+        type_map::TypeMap,
+    },
     CONNECTION_REGISTRY, STATEMENT_REGISTRY,
 };
 
@@ -23,7 +36,12 @@ pub struct LibSQLStatement {
     /// The ID of the statement.
     pub stmt_id: String,
     pub stmt: String,
-    pub params: Arc<Mutex<HashMap<String, String>>>,
+    /// The values and references bound to the statement parameters.
+    pub bindings: Arc<Mutex<Bindings>>,
+    /// The SQL-to-PHP type map applied to query results, if type mapping is enabled.
+    pub type_map: Option<TypeMap>,
+    /// The ID of the transaction the statement was prepared in, if any.
+    pub trx_id: Option<String>,
 }
 
 #[php_impl]
@@ -39,28 +57,71 @@ impl LibSQLStatement {
     ///
     /// A `Result` containing the constructed `LibSQLStatement` object or a `PhpException` if an error occurs.
     pub fn __construct(conn_id: String, sql: &str) -> Result<Self, PhpException> {
-        let conn_registry = CONNECTION_REGISTRY.lock().unwrap();
-
-        let conn = conn_registry
-            .get(&conn_id)
-            .ok_or_else(|| PhpException::from("Connection not found"))?;
+        catch_panic(|| {
+            let conn = CONNECTION_REGISTRY
+                .get(&conn_id)
+                .ok_or_else(|| PhpException::from("Connection not found"))?;
 
-        let stmt = runtime().block_on(async { conn.prepare(sql).await.unwrap() });
+            Self::prepare_on(&conn, conn_id, None, sql)
+        })
+    }
 
-        let stmt_id = uuid::Uuid::new_v4().to_string();
-        STATEMENT_REGISTRY
-            .lock()
-            .unwrap()
-            .insert(stmt_id.clone(), stmt);
+    /// Binds a value to a parameter, keeping its type.
+    ///
+    /// # Arguments
+    ///
+    /// * `parameter` - The 1-based parameter index, or the parameter name with or without its prefix.
+    /// * `value` - The value to bind.
+    /// * `param_type` - Optional `LibSQL::PARAM_*` type to coerce the value to.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `()` if the binding was successful or a `PhpException` if an error occurs.
+    pub fn bind_value(
+        &self,
+        parameter: &Zval,
+        value: &Zval,
+        param_type: Option<i32>,
+    ) -> Result<(), PhpException> {
+        catch_panic(|| {
+            let dates = DateTimeBinding::of_connection(&self.conn_id);
+            let binding = Binding::value(value, param_type, &dates)?;
+            self.bindings.lock_or_recover().bind(parameter, binding)
+        })
+    }
 
-        Ok(Self {
-            conn_id,
-            stmt_id,
-            stmt: sql.to_string(),
-            params: Arc::new(Mutex::new(HashMap::new())),
+    /// Binds a variable by reference; its value is read each time the statement runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `parameter` - The 1-based parameter index, or the parameter name with or without its prefix.
+    /// * `variable` - The variable to bind.
+    /// * `param_type` - Optional `LibSQL::PARAM_*` type to coerce the variable to.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `()` if the binding was successful or a `PhpException` if an error occurs.
+    pub fn bind_param(
+        &self,
+        parameter: &Zval,
+        variable: &mut Zval,
+        param_type: Option<i32>,
+    ) -> Result<(), PhpException> {
+        catch_panic(|| {
+            let reference = variable_reference()
+                .ok_or_else(|| PhpException::from("The variable must be passed by reference"))?;
+            debug_assert!(std::ptr::eq(reference.dereference(), &*variable));
+
+            let binding = Binding::reference(reference, param_type)?;
+            self.bindings.lock_or_recover().bind(parameter, binding)
         })
     }
 
+    /// Removes all values bound to the statement.
+    pub fn clear_bindings(&self) {
+        self.bindings.lock_or_recover().clear();
+    }
+
     /// Binds named parameters to the statement.
     ///
     /// # Arguments
@@ -70,26 +131,26 @@ impl LibSQLStatement {
     /// # Returns
     ///
     /// A `Result` containing a `()` if the binding was successful or a `PhpException` if an error occurs.
-    pub fn bind_named(&self, parameters: QueryParameters) -> Result<(), PhpException> {
-        let mut params = self.params.lock().expect("Failed to lock params mutex");
+    pub fn bind_named(&self, parameters: &Zval) -> Result<(), PhpException> {
+        catch_panic(|| {
+            let parameters = QueryParameters::from_argument(Some(parameters), &self.conn_id)?
+                .unwrap_or_default();
+
+            if let Some(error) = parameters.error {
+                return Err(PhpException::default(error));
+            }
 
-        if let Some(named_params) = parameters.get_named() {
-            for (key, value) in named_params {
-                let clean_key = key
-                    .trim_start_matches(|c| c == '@' || c == '$' || c == '?' || c == ':')
-                    .to_string();
+            let mut bindings = self.bindings.lock_or_recover();
 
-                params.insert(clean_key, value.to_string());
+            for (key, value) in parameters.named.unwrap_or_default() {
+                bindings.bind_name(&key, Binding::Value(value));
             }
-        }
 
-        Ok(())
+            Ok(())
+        })
     }
 
-    /// Binds positional parameters to the statement.
-    ///
-    /// The placeholder style is automatically detected and can be one of `$`, `?`, or `@`.
-    /// The index of the parameter (1-based) is used to construct the placeholder.
+    /// Binds positional parameters to the statement, in order, starting at index 1.
     ///
     /// # Arguments
     ///
@@ -98,35 +159,28 @@ impl LibSQLStatement {
     /// # Returns
     ///
     /// A `Result` containing `()` if the binding was successful, or a `PhpException` if an error occurs.
-    fn bind_positional(&self, parameters: QueryParameters) -> Result<(), PhpException> {
-        let mut params = self.params.lock().expect("Failed to lock params mutex");
-
-        // Determine the placeholder style ($, ?, or @)
-        let uses_dollar = self.stmt.contains("$1"); // Check for a typical `$1` pattern
-        let uses_question_mark = self.stmt.contains("?"); // Check for `?` pattern
-        let uses_at_symbol = self.stmt.contains("@1"); // Check for `@1` pattern
-
-        if let Some(positional_params) = parameters.get_positional() {
-            for (index, value) in positional_params.iter().enumerate() {
-                let key = if uses_dollar {
-                    // Use `$`-based placeholders (1-based index)
-                    format!("${}", index + 1)
-                } else if uses_question_mark {
-                    // Use `?`-based placeholders (positional, no index in the placeholder itself)
-                    format!("?{}", index + 1)
-                } else if uses_at_symbol {
-                    // Use `@`-based placeholders (1-based index)
-                    format!("@{}", index + 1)
-                } else {
-                    // Default to `$`-based if no clear placeholder type is found
-                    format!("${}", index + 1)
-                };
-
-                params.insert(key, value.to_string());
+    pub fn bind_positional(&self, parameters: &Zval) -> Result<(), PhpException> {
+        catch_panic(|| {
+            let parameters = QueryParameters::from_argument(Some(parameters), &self.conn_id)?
+                .unwrap_or_default();
+
+            if let Some(error) = parameters.error {
+                return Err(PhpException::default(error));
+            }
+
+            let mut bindings = self.bindings.lock_or_recover();
+
+            for (index, value) in parameters
+                .positional
+                .unwrap_or_default()
+                .into_iter()
+                .enumerate()
+            {
+                bindings.positional.insert(index + 1, Binding::Value(value));
             }
-        }
 
-        Ok(())
+            Ok(())
+        })
     }
 
     /// Finalizes the statement.
@@ -135,18 +189,24 @@ impl LibSQLStatement {
     ///
     /// A `Result` indicating success or a `PhpException` if an error occurs.
     pub fn finalize(&self) -> Result<(), PhpException> {
-        let mut stmt_registry = STATEMENT_REGISTRY.lock().unwrap();
-
-        let mut stmt = stmt_registry
-            .remove(&self.stmt_id)
-            .ok_or_else(|| PhpException::from("Statement not found"))?;
-
-        stmt.finalize();
-        Ok(())
+        catch_panic(|| {
+            let stmt = STATEMENT_REGISTRY
+                .remove(&self.stmt_id)
+                .ok_or_else(|| PhpException::from("Statement not found"))?;
+            request_state::untrack(Handle::Statement, &self.stmt_id);
+
+            stmt.lock_or_recover().finalize();
+            Ok(())
+        })
     }
 
     /// Executes the statement with the given parameters.
     ///
+    /// Parameters passed here take precedence over bound values; without them, the values bound
+    /// through `bindValue`, `bindParam`, `bindNamed` and `bindPositional` are used. Bindings are
+    /// kept after execution so the statement can be run again. A list bound inside `IN (...)`
+    /// runs on a statement prepared for its length, see `QueryParameters::expand_lists`.
+    ///
     /// # Arguments
     ///
     /// * `parameters` - Query parameters for the statement.
@@ -154,91 +214,66 @@ impl LibSQLStatement {
     /// # Returns
     ///
     /// A `Result` containing the number of affected rows or a `PhpException` if an error occurs.
-    pub fn execute(&self, parameters: Option<QueryParameters>) -> Result<usize, PhpException> {
-        let mut stmt_registry = STATEMENT_REGISTRY.lock().unwrap();
-
-        let stmt = stmt_registry
-            .get_mut(&self.stmt_id)
-            .ok_or_else(|| PhpException::from("Statement not found"))?;
-
-        // Default parameters from `params` if none are provided
-        let params = match parameters {
-            Some(p) => p,
-            None => {
-                let params = self.params.lock().unwrap();
-                let uses_named_params = params.keys().any(|key| {
-                    !key.starts_with('$')
-                        && !key.starts_with('?')
-                        && !key.starts_with('@')
-                        && !key.chars().all(|c| c.is_ascii_digit())
-                });
-
-                if uses_named_params {
-                    QueryParameters {
-                        named: Some(
-                            params
-                                .iter()
-                                .map(|(key, value)| {
-                                    let prefixed_key = if key.starts_with(':')
-                                        || key.starts_with('@')
-                                        || key.starts_with('$')
-                                    {
-                                        key.clone()
-                                    } else {
-                                        format!(":{}", key)
-                                    };
-                                    (prefixed_key, QueryValue::Text(value.clone()))
-                                })
-                                .collect(),
-                        ),
-                        positional: None,
-                    }
-                } else {
-                    // Use positional parameters
-                    QueryParameters {
-                        positional: Some(
-                            params
-                                .iter()
-                                .map(|(_, value)| QueryValue::Text(value.clone()))
-                                .collect(),
-                        ),
-                        named: None,
-                    }
-                }
+    pub fn execute(&self, parameters: Option<&Zval>) -> Result<usize, PhpException> {
+        catch_panic(|| {
+            let (sql, params) = self
+                .resolve_parameters(parameters)?
+                .expand_lists(&self.stmt)?;
+
+            if sql != self.stmt {
+                let (_, mut stmt) = self.prepare_expanded(&sql)?;
+                return self.execute_statement(&mut stmt, &sql, params);
             }
-        };
 
-        // Here we can decide which parameter type is being passed and bind them accordingly
-        if let Some(params) = params.get_named() {
-            let query_params = QueryParameters {
-                named: Some(params.clone()),
-                positional: None,
-            };
-            self.bind_named(query_params)?;
-        }
+            // A statement prepared in a transaction cannot run once the transaction has ended
+            if self.trx_id.is_some() {
+                self.connection()?;
+            }
 
-        if let Some(params) = params.get_positional() {
-            let query_params = QueryParameters {
-                positional: Some(params.clone()),
-                named: None,
-            };
-            self.bind_positional(query_params)?;
-        }
+            let stmt = self.statement()?;
+            let mut stmt = stmt.lock_or_recover();
 
-        // Execute query asynchronously
-        let result = runtime().block_on(async { stmt.execute(params.to_params()).await });
+            self.execute_statement(&mut stmt, &sql, params)
+        })
+    }
 
-        // Clear the params after execution
-        self.params.lock().unwrap().clear();
+    /// Executes the statement once per parameter set.
+    ///
+    /// Unless a transaction is already open, the executions run inside an implicit transaction
+    /// that is rolled back if any of them fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `param_sets` - An array or `Traversable` (e.g. a generator) of parameter arrays.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `['rowsChanged' => int, 'lastInsertIds' => int[]]` or a `PhpException` if an error occurs.
+    pub fn execute_many(&self, param_sets: &Zval) -> Result<ExecuteManyResult, PhpException> {
+        catch_panic(|| {
+            let param_sets =
+                hooks::use_execute_many::collect_parameter_sets(param_sets, &self.conn_id)?;
 
-        match result {
-            Ok(u_result) => Ok(u_result),
-            Err(e) => Err(PhpException::from(e.to_string())),
-        }
+            let conn = self.connection()?;
+
+            let stmt = self.statement()?;
+            let mut stmt = stmt.lock_or_recover();
+
+            hooks::use_execute_many::execute_many(
+                &self.conn_id,
+                &conn,
+                Some(&mut stmt),
+                &self.stmt,
+                param_sets,
+            )
+        })
     }
 
     /// Executes a query with the given parameters and returns the result as a PHP value.
     ///
+    /// Parameters take precedence over bound values, as with `execute`. The query runs on the
+    /// prepared statement, whose rows are read into the result before it is reset for reuse.
+    ///
     /// # Arguments
     ///
     /// * `parameters` - Query parameters for the statement.
@@ -246,62 +281,60 @@ impl LibSQLStatement {
     /// # Returns
     ///
     /// A `Result` containing the query result as a PHP value or a `PhpException` if an error occurs.
-    pub fn query(&self, parameters: Option<QueryParameters>) -> Result<LibSQLResult, PhpException> {
-        let params = match parameters {
-            Some(p) => p,
-            None => QueryParameters {
-                named: Some(
-                    self.params
-                        .lock()
-                        .expect("Failed to lock params mutex")
-                        .iter()
-                        .map(|(key, value)| (key.clone(), QueryValue::Text(value.clone())))
-                        .collect::<HashMap<String, QueryValue>>(),
-                ),
-                positional: None,
-            },
-        };
-
-        if let Some(params) = params.get_named() {
-            let query_params = QueryParameters {
-                named: Some(params.clone()),
-                positional: None,
+    pub fn query(&self, parameters: Option<&Zval>) -> Result<LibSQLResult, PhpException> {
+        catch_panic(|| {
+            let (sql, params) = self
+                .resolve_parameters(parameters)?
+                .expand_lists(&self.stmt)?;
+
+            let mut result = if sql != self.stmt {
+                let (conn, mut stmt) = self.prepare_expanded(&sql)?;
+                let params = params.to_params_for(&ParameterSpec::from_statement(&stmt, &sql))?;
+
+                LibSQLResult::from_statement(
+                    self.conn_id.clone(),
+                    conn,
+                    &sql,
+                    &mut stmt,
+                    params,
+                    None,
+                )?
+            } else {
+                let conn = self.connection()?;
+
+                let stmt = self.statement()?;
+                let mut stmt = stmt.lock_or_recover();
+
+                let params = params.to_params_for(&ParameterSpec::from_statement(&stmt, &sql))?;
+
+                LibSQLResult::from_statement(
+                    self.conn_id.clone(),
+                    conn,
+                    &sql,
+                    &mut stmt,
+                    params,
+                    None,
+                )?
             };
-            self.bind_named(query_params)?;
-        }
+            result.type_map = self.type_map.clone();
 
-        if let Some(params) = params.get_positional() {
-            let query_params = QueryParameters {
-                positional: Some(params.clone()),
-                named: None,
-            };
-            self.bind_positional(query_params)?;
-        }
-
-        let result =
-            LibSQLResult::__construct(self.conn_id.clone(), self.stmt.as_str(), Some(params))?;
-
-        // Clear the params after execution
-        self.params.lock().unwrap().clear();
-
-        Ok(result)
+            Ok(result)
+        })
     }
 
-    /// Resets the statement.
+    /// Resets the statement and removes all values bound to it.
     ///
     /// # Returns
     ///
     /// A `Result` indicating success or a `PhpException` if an error occurs.
     pub fn reset(&self) -> Result<(), PhpException> {
-        let mut stmt_registry = STATEMENT_REGISTRY.lock().unwrap();
+        catch_panic(|| {
+            let stmt = self.statement()?;
+            stmt.lock_or_recover().reset();
 
-        let stmt = stmt_registry
-            .get_mut(&self.stmt_id)
-            .ok_or_else(|| PhpException::from("Statement not found"))?;
-
-        stmt.reset();
-        self.params.lock().unwrap().clear();
-        Ok(())
+            self.bindings.lock_or_recover().clear();
+            Ok(())
+        })
     }
 
     /// Gets the number of parameters in the statement.
@@ -310,14 +343,13 @@ impl LibSQLStatement {
     ///
     /// A `Result` containing the number of parameters or a `PhpException` if an error occurs.
     pub fn parameter_count(&self) -> Result<usize, PhpException> {
-        let mut stmt_registry = STATEMENT_REGISTRY.lock().unwrap();
-
-        let stmt = stmt_registry
-            .get_mut(&self.stmt_id)
-            .ok_or_else(|| PhpException::from("Statement not found"))?;
+        catch_panic(|| {
+            let stmt = self.statement()?;
+            let stmt = stmt.lock_or_recover();
 
-        let result = stmt.parameter_count();
-        Ok(result)
+            let result = stmt.parameter_count();
+            Ok(result)
+        })
     }
 
     /// Gets the name of the parameter at the specified index.
@@ -331,19 +363,18 @@ impl LibSQLStatement {
     /// A `Result` containing the parameter name or `None` if the parameter is not found,
     /// or a `PhpException` if an error occurs.
     pub fn parameter_name(&self, idx: i32) -> Result<Option<String>, PhpException> {
-        let stmt_registry = STATEMENT_REGISTRY.lock().unwrap();
+        catch_panic(|| {
+            let stmt = self.statement()?;
+            let stmt = stmt.lock_or_recover();
 
-        let stmt = stmt_registry
-            .get(&self.stmt_id)
-            .ok_or_else(|| PhpException::from("Statement not found"))?;
+            let result = stmt.parameter_name(idx).map(|name| {
+                // Strip leading @, $, : or ? from parameter names
+                name.trim_start_matches(|c| c == '@' || c == '$' || c == '?' || c == ':')
+                    .to_string()
+            });
 
-        let result = stmt.parameter_name(idx).map(|name| {
-            // Strip leading @, $, : or ? from parameter names
-            name.trim_start_matches(|c| c == '@' || c == '$' || c == '?' || c == ':')
-                .to_string()
-        });
-
-        Ok(result)
+            Ok(result)
+        })
     }
 
     /// Retrieves information about the columns returned by the statement.
@@ -351,38 +382,155 @@ impl LibSQLStatement {
     /// # Returns
     ///
     /// A `Result` containing an array of column information or a `PhpException` if an error occurs.
-    pub fn columns(&self) -> Result<Vec<ext_php_rs::types::Zval>, PhpException> {
-        let stmt_registry = STATEMENT_REGISTRY.lock().unwrap();
+    pub fn columns(&self) -> Result<Vec<ColumnInfo>, PhpException> {
+        catch_panic(|| {
+            let stmt = self.statement()?;
+            let stmt = stmt.lock_or_recover();
 
-        let stmt = stmt_registry
-            .get(&self.stmt_id)
-            .ok_or_else(|| PhpException::from("Statement not found"))?;
+            Ok(ColumnInfo::from_statement(&stmt))
+        })
+    }
+}
 
-        let columns = stmt.columns();
-        let mut zval_columns = Vec::new();
+impl LibSQLStatement {
+    /// Prepares a statement on a connection handle.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - The connection handle to prepare the statement on.
+    /// * `conn_id` - The ID of the database connection.
+    /// * `trx_id` - The ID of the transaction whose connection `conn` is, if any.
+    /// * `sql` - The SQL query string.
+    pub(crate) fn prepare_on(
+        conn: &libsql::Connection,
+        conn_id: String,
+        trx_id: Option<String>,
+        sql: &str,
+    ) -> Result<Self, PhpException> {
+        let stmt = runtime()
+            .block_on(async { conn.prepare(sql).await })
+            .map_err(|e| statement_error(&e, sql, &conn_id))?;
 
-        for column in columns {
-            let mut zval_column = ext_php_rs::types::Zval::new();
-            let mut column_info = std::collections::HashMap::new();
+        let stmt_id = uuid::Uuid::new_v4().to_string();
+        STATEMENT_REGISTRY.insert(stmt_id.clone(), Arc::new(Mutex::new(stmt)));
+        request_state::track(Handle::Statement, &stmt_id);
 
-            column_info.insert("name".to_string(), column.name().into_zval(false)?);
-            if let Some(origin_name) = column.origin_name() {
-                column_info.insert("origin_name".to_string(), origin_name.into_zval(false)?);
-            }
-            if let Some(table_name) = column.table_name() {
-                column_info.insert("table_name".to_string(), table_name.into_zval(false)?);
-            }
-            if let Some(database_name) = column.database_name() {
-                column_info.insert("database_name".to_string(), database_name.into_zval(false)?);
-            }
-            if let Some(decl_type) = column.decl_type() {
-                column_info.insert("decl_type".to_string(), decl_type.into_zval(false)?);
-            }
+        Ok(Self {
+            conn_id,
+            stmt_id,
+            stmt: sql.to_string(),
+            bindings: Arc::new(Mutex::new(Bindings::default())),
+            type_map: None,
+            trx_id,
+        })
+    }
 
-            zval_column.set_array(column_info)?;
-            zval_columns.push(zval_column);
+    /// Returns the prepared statement, locked only by the calls that run it.
+    fn statement(&self) -> Result<Arc<Mutex<libsql::Statement>>, PhpException> {
+        STATEMENT_REGISTRY
+            .get(&self.stmt_id)
+            .ok_or_else(|| PhpException::from("Statement not found"))
+    }
+
+    /// Returns the connection the statement runs on: its transaction's, if it was prepared in one.
+    fn connection(&self) -> Result<libsql::Connection, PhpException> {
+        match &self.trx_id {
+            Some(trx_id) => Ok(transaction_connection(trx_id)?),
+            None => CONNECTION_REGISTRY
+                .get(&self.conn_id)
+                .ok_or_else(|| PhpException::from("Connection not found")),
         }
+    }
+
+    /// Returns the parameters given to `execute`/`query`, or the bound values if none were given.
+    fn resolve_parameters(
+        &self,
+        parameters: Option<&Zval>,
+    ) -> Result<QueryParameters, PhpException> {
+        let dates = DateTimeBinding::of_connection(&self.conn_id);
+
+        match QueryParameters::from_argument(parameters, &self.conn_id)? {
+            Some(params) if !params.is_empty() => Ok(params),
+            _ => Ok(self.bindings.lock_or_recover().to_query_parameters(&dates)),
+        }
+    }
 
-        Ok(zval_columns)
+    /// Prepares the statement with expanded list parameters on its connection.
+    fn prepare_expanded(
+        &self,
+        sql: &str,
+    ) -> Result<(libsql::Connection, libsql::Statement), PhpException> {
+        let conn = self.connection()?;
+
+        let stmt = runtime()
+            .block_on(async { conn.prepare(sql).await })
+            .map_err(|e| statement_error(&e, sql, &self.conn_id))?;
+
+        Ok((conn, stmt))
+    }
+
+    /// Validates the parameters and executes the statement, resetting it afterwards.
+    fn execute_statement(
+        &self,
+        stmt: &mut libsql::Statement,
+        sql: &str,
+        params: QueryParameters,
+    ) -> Result<usize, PhpException> {
+        let params = params.to_params_for(&ParameterSpec::from_statement(stmt, sql))?;
+
+        let conn = self.connection()?;
+        let result = interrupt::run(&self.conn_id, &conn, None, async {
+            stmt.execute(params)
+                .await
+                .map_err(|e| statement_error(&e, sql, &self.conn_id))
+        });
+        stmt.reset();
+        invalidate_on_schema_change(&self.conn_id, sql);
+
+        result
     }
 }
+
+/// Frees the prepared statement when its PHP object is destroyed without `finalize()`.
+impl Drop for LibSQLStatement {
+    fn drop(&mut self) {
+        let leaked = STATEMENT_REGISTRY.remove(&self.stmt_id).is_some();
+        request_state::untrack(Handle::Statement, &self.stmt_id);
+
+        if leaked && leak_debug::is_leak_debugging() {
+            leak_debug::warn(&format!(
+                "LibSQLStatement destroyed without finalize(): {}",
+                self.stmt
+            ));
+        }
+    }
+}
+
+fn statement_error(error: &libsql::Error, sql: &str, conn_id: &str) -> PhpException {
+    LibSQLError::from_libsql(error)
+        .with_sql(sql)
+        .with_connection(conn_id)
+        .into()
+}
+
+/// Reads the `$variable` argument of the running `bindParam()` call as the PHP reference it was
+/// passed as.
+///
+/// ext-php-rs hands by-reference arguments over dereferenced, so the arguments of the call are
+/// parsed again to reach the reference itself.
+fn variable_reference() -> Option<Zval> {
+    let ex = unsafe { ExecutorGlobals::get().current_execute_data.as_mut() }?;
+
+    let mut parameter = Arg::new("parameter", DataType::Mixed);
+    let mut variable = Arg::new("variable", DataType::Mixed).as_ref();
+    let mut param_type = Arg::new("type", DataType::Long);
+    ex.parser()
+        .arg(&mut parameter)
+        .arg(&mut variable)
+        .not_required()
+        .arg(&mut param_type)
+        .parse()
+        .ok()?;
+
+    variable.zval().map(|zval| zval.shallow_clone())
+}
@@ -1,68 +1,185 @@
 #[allow(non_snake_case, deprecated, unused_attributes)]
 #[cfg_attr(windows, feature(abi_vectorcall))]
 extern crate ext_php_rs;
-use ext_php_rs::prelude::*;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use ext_php_rs::{prelude::*, types::Zval};
 
 use crate::{
-    hooks,
+    errors::{ErrorKind, LibSQLError},
+    hooks::{
+        self,
+        request_state::{self, Handle},
+    },
+    result::LibSQLResult,
     statement::LibSQLStatement,
-    utils::{query_params::QueryParameters, runtime::runtime},
+    utils::{
+        guard::catch_panic, interrupt, leak_debug, log_error::log_error_to_tmp,
+        query_params::QueryParameters, runtime::runtime, sql_lexer::quote_identifier,
+        type_map::TypeMap, watchdog,
+    },
     CONNECTION_REGISTRY, TRANSACTION_REGISTRY,
 };
 
+/// A transaction registered in `TRANSACTION_REGISTRY`.
+pub struct TransactionHandle {
+    /// The ID of the database connection the transaction runs on.
+    pub conn_id: String,
+
+    /// The libSQL transaction; statements of the transaction run on the connection it derefs to.
+    pub trx: libsql::Transaction,
+
+    /// Set when the transaction was rolled back because its timeout elapsed.
+    pub timed_out: Arc<AtomicBool>,
+
+    /// When the transaction times out, if it was begun with a timeout.
+    pub deadline: Option<Instant>,
+
+    /// Interrupts the statement running when the transaction times out; dropped with the handle,
+    /// so it cannot interrupt the connection once the transaction ended.
+    pub interrupt_at: Option<watchdog::Deadline>,
+}
+
 /// Represents a LibSQLTransaction object for managing transactions.
 #[php_class]
+#[derive(Clone)]
 pub struct LibSQLTransaction {
     /// The behavior of the transaction.
     pub trx_behavior: String,
 
-    /// The ID of the transaction.
+    /// The ID of the transaction; a nested transaction shares the ID of its top-level transaction.
     pub trx_id: String,
 
     /// The ID of the database connection associated with the transaction.
     pub conn_id: String,
+
+    /// The savepoint backing a nested transaction, or `None` for a top-level transaction.
+    pub savepoint: Option<String>,
+
+    /// Whether the transaction has not been committed or rolled back yet, shared by its clones.
+    pub active: Arc<AtomicBool>,
+
+    /// Set when the top-level transaction was rolled back because its timeout elapsed.
+    pub timed_out: Arc<AtomicBool>,
+
+    /// The SQL-to-PHP type map applied to query results, if type mapping is enabled.
+    pub type_map: Option<TypeMap>,
 }
 
 #[php_impl]
 impl LibSQLTransaction {
     /// Constructs a new `LibSQLTransaction` object.
     ///
+    /// If a transaction is already active on the connection, the new transaction is nested
+    /// inside it and backed by a savepoint.
+    ///
     /// # Arguments
     ///
     /// * `conn_id` - The ID of the database connection.
-    /// * `trx_mode` - The mode of the transaction.
+    /// * `trx_mode` - The behavior of the transaction: `DEFERRED`, `IMMEDIATE`, `EXCLUSIVE` or `READONLY`.
+    /// * `timeout` - The time in milliseconds after which the transaction is rolled back if it
+    ///   has not ended; ignored for a nested transaction.
     ///
     /// # Returns
     ///
     /// A `Result` containing the constructed `LibSQLTransaction` object or a `PhpException` if an error occurs.
-    pub fn __construct(conn_id: String, trx_mode: String) -> Result<Self, PhpException> {
-        let conn_registry = CONNECTION_REGISTRY.lock().unwrap();
+    pub fn __construct(
+        conn_id: String,
+        trx_mode: String,
+        timeout: Option<i64>,
+    ) -> Result<Self, PhpException> {
+        catch_panic(|| {
+            let timeout = timeout
+                .filter(|timeout| *timeout > 0)
+                .map(|timeout| Duration::from_millis(timeout as u64));
 
-        let conn = conn_registry
-            .get(&conn_id)
-            .ok_or_else(|| PhpException::from("Connection not found"))?;
+            Ok(Self::begin(conn_id, trx_mode, timeout)?)
+        })
+    }
 
-        let trx_behavior = match trx_mode.as_str() {
-            "WRITE" => libsql::TransactionBehavior::Immediate,
-            "READ" => libsql::TransactionBehavior::ReadOnly,
-            _ => libsql::TransactionBehavior::Deferred,
-        };
+    /// Starts a transaction nested inside this one, backed by a savepoint.
+    ///
+    /// Committing the nested transaction releases its savepoint; rolling it back undoes only
+    /// the changes made since it started.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the nested `LibSQLTransaction` or a `PhpException` if this
+    /// transaction is no longer active.
+    pub fn transaction(&self) -> Result<LibSQLTransaction, PhpException> {
+        catch_panic(|| {
+            self.connection()?;
 
-        let trx = runtime().block_on(async {
-            let transaction = conn.transaction_with_behavior(trx_behavior).await;
-            transaction.unwrap()
-        });
+            let mut nested = Self::nested(
+                self.conn_id.clone(),
+                self.trx_id.clone(),
+                self.trx_behavior.clone(),
+                self.timed_out.clone(),
+            )?;
+            nested.type_map = self.type_map.clone();
 
-        let trx_id = uuid::Uuid::new_v4().to_string();
-        TRANSACTION_REGISTRY
-            .lock()
-            .unwrap()
-            .insert(trx_id.clone(), trx);
+            Ok(nested)
+        })
+    }
 
-        Ok(Self {
-            trx_behavior: trx_mode,
-            trx_id,
-            conn_id,
+    /// Creates a savepoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the savepoint.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or a `PhpException` if an error occurs.
+    pub fn savepoint(&self, name: &str) -> Result<(), PhpException> {
+        catch_panic(|| {
+            Ok(self.execute_control(
+                &format!("SAVEPOINT {}", quote_identifier(name)),
+                "Failed to create savepoint",
+            )?)
+        })
+    }
+
+    /// Releases a savepoint, keeping the changes made since it was created.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the savepoint.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or a `PhpException` if an error occurs.
+    pub fn release_savepoint(&self, name: &str) -> Result<(), PhpException> {
+        catch_panic(|| {
+            Ok(self.execute_control(
+                &format!("RELEASE SAVEPOINT {}", quote_identifier(name)),
+                "Failed to release savepoint",
+            )?)
+        })
+    }
+
+    /// Rolls back the changes made since a savepoint was created; the savepoint stays active.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the savepoint.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or a `PhpException` if an error occurs.
+    pub fn rollback_to(&self, name: &str) -> Result<(), PhpException> {
+        catch_panic(|| {
+            Ok(self.execute_control(
+                &format!("ROLLBACK TO SAVEPOINT {}", quote_identifier(name)),
+                "Failed to roll back to savepoint",
+            )?)
         })
     }
 
@@ -72,7 +189,7 @@ impl LibSQLTransaction {
     ///
     /// A `Result` containing the number of rows changed or a `PhpException` if an error occurs.
     pub fn changes(&self) -> Result<u64, PhpException> {
-        hooks::changes::get_changes(self.conn_id.to_string())
+        catch_panic(|| Ok(self.connection()?.changes()))
     }
 
     /// Checks if the connection is in autocommit mode.
@@ -81,7 +198,30 @@ impl LibSQLTransaction {
     ///
     /// A `Result` containing a boolean indicating if autocommit is enabled or a `PhpException` if an error occurs.
     pub fn is_autocommit(&self) -> Result<bool, PhpException> {
-        hooks::is_autocommit::get_is_autocommit(self.conn_id.to_string())
+        catch_panic(|| Ok(self.connection()?.is_autocommit()))
+    }
+
+    /// Checks whether the transaction can still be used.
+    ///
+    /// A transaction stops being active once it, or the transaction it is nested in, has been
+    /// committed or rolled back, or has timed out.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the transaction has not ended.
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+            && !expire_if_due(&self.trx_id)
+            && TRANSACTION_REGISTRY.contains(&self.trx_id)
+    }
+
+    /// Gets the behavior the transaction was started with.
+    ///
+    /// # Returns
+    ///
+    /// The behavior, such as `"DEFERRED"`, `"WRITE"` or `"READ"`.
+    pub fn behavior(&self) -> String {
+        self.trx_behavior.clone()
     }
 
     /// Executes a SQL statement within the transaction.
@@ -94,15 +234,18 @@ impl LibSQLTransaction {
     /// # Returns
     ///
     /// A `Result` containing the number of affected rows or a `PhpException` if an error occurs.
-    pub fn execute(
-        &self,
-        stmt: &str,
-        parameters: Option<QueryParameters>,
-    ) -> Result<u64, PhpException> {
-        hooks::use_exec::exec(self.conn_id.to_string(), stmt, parameters)
+    pub fn execute(&self, stmt: &str, parameters: Option<&Zval>) -> Result<u64, PhpException> {
+        catch_panic(|| {
+            let parameters = QueryParameters::from_argument(parameters, &self.conn_id)?;
+
+            hooks::use_exec::exec_on(&self.connection()?, &self.conn_id, stmt, parameters, None)
+                .map_err(|e| self.timed_out_or(e))
+        })
     }
 
-    /// Prepares a SQL statement for execution.
+    /// Prepares a SQL statement for execution within the transaction.
+    ///
+    /// The statement can only be executed while the transaction is active.
     ///
     /// # Arguments
     ///
@@ -112,7 +255,17 @@ impl LibSQLTransaction {
     ///
     /// A `Result` containing a `LibSQLStatement` instance or a `PhpException` if an error occurs.
     pub fn prepare(&self, sql: &str) -> Result<LibSQLStatement, PhpException> {
-        LibSQLStatement::__construct(self.conn_id.clone(), sql)
+        catch_panic(|| {
+            let mut statement = LibSQLStatement::prepare_on(
+                &self.connection()?,
+                self.conn_id.clone(),
+                Some(self.trx_id.clone()),
+                sql,
+            )?;
+            statement.type_map = self.type_map.clone();
+
+            Ok(statement)
+        })
     }
 
     /// Executes a query within the transaction.
@@ -121,16 +274,32 @@ impl LibSQLTransaction {
     ///
     /// * `stmt` - The SQL statement to execute.
     /// * `parameters` - Query parameters for the statement.
+    /// * `types` - Per-query PHP types keyed by column name, applied on top of the connection type map.
     ///
     /// # Returns
     ///
-    /// A `Result` containing the query result as a PHP value or a `PhpException` if an error occurs.
+    /// A `Result` containing the `LibSQLResult` of the query or a `PhpException` if an error occurs.
     pub fn query(
         &self,
         stmt: &str,
-        parameters: QueryParameters,
-    ) -> Result<ext_php_rs::types::Zval, PhpException> {
-        hooks::use_query::query(self.conn_id.to_string(), stmt, Some(parameters))
+        parameters: Option<&Zval>,
+        types: Option<HashMap<String, String>>,
+    ) -> Result<LibSQLResult, PhpException> {
+        catch_panic(|| {
+            let parameters = QueryParameters::from_argument(parameters, &self.conn_id)?;
+
+            let mut result = LibSQLResult::from_connection(
+                self.conn_id.clone(),
+                self.connection()?,
+                stmt,
+                parameters,
+                None,
+            )
+            .map_err(|e| self.timed_out_or(e))?;
+            result.type_map = TypeMap::for_query(self.type_map.as_ref(), types)?;
+
+            Ok(result)
+        })
     }
 
     /// Commits the transaction.
@@ -139,18 +308,7 @@ impl LibSQLTransaction {
     ///
     /// A `Result` indicating success or a `PhpException` if an error occurs.
     pub fn commit(&self) -> Result<(), PhpException> {
-        let mut trx_registry = TRANSACTION_REGISTRY.lock().unwrap();
-
-        let trx = trx_registry
-            .remove(&self.trx_id)
-            .ok_or_else(|| PhpException::from("Transaction not found"))?;
-
-        let commit_result = runtime().block_on(async { trx.commit().await });
-
-        match commit_result {
-            Ok(_) => Ok(()),
-            Err(_) => Err(PhpException::from("Failed to commit transaction")),
-        }
+        catch_panic(|| Ok(self.finish(true)?))
     }
 
     /// Rolls back the transaction.
@@ -159,17 +317,313 @@ impl LibSQLTransaction {
     ///
     /// A `Result` indicating success or a `PhpException` if an error occurs.
     pub fn rollback(&self) -> Result<(), PhpException> {
-        let mut trx_registry = TRANSACTION_REGISTRY.lock().unwrap();
+        catch_panic(|| Ok(self.finish(false)?))
+    }
+}
+
+impl LibSQLTransaction {
+    /// Begins a transaction, nested inside the active one if the connection is in a transaction.
+    ///
+    /// A top-level transaction with a `timeout` has the statement running when the timeout
+    /// elapses interrupted, and is rolled back by the next call on the transaction or its
+    /// connection, which fails with a `TimeoutException`.
+    pub(crate) fn begin(
+        conn_id: String,
+        trx_mode: String,
+        timeout: Option<Duration>,
+    ) -> Result<Self, LibSQLError> {
+        let trx_behavior = parse_behavior(&trx_mode)?;
 
-        let trx = trx_registry
+        let root = TRANSACTION_REGISTRY
+            .find_map(|trx_id, handle| {
+                (handle.conn_id == conn_id).then(|| (trx_id.to_string(), handle.timed_out.clone()))
+            })
+            // A transaction that timed out is rolled back rather than nested into
+            .filter(|(root_id, _)| !expire_if_due(root_id));
+
+        if let Some((root_id, timed_out)) = root {
+            return Self::nested(conn_id, root_id, trx_mode, timed_out);
+        }
+
+        let conn = CONNECTION_REGISTRY
+            .get(&conn_id)
+            .ok_or_else(|| LibSQLError::new(ErrorKind::Generic, "Connection not found"))?;
+
+        let trx = runtime()
+            .block_on(async { conn.transaction_with_behavior(trx_behavior).await })
+            .map_err(|e| {
+                LibSQLError::from_libsql(&e)
+                    .context("Failed to begin transaction")
+                    .with_connection(&conn_id)
+            })?;
+
+        let trx_id = uuid::Uuid::new_v4().to_string();
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let interrupt_at = deadline.map(|deadline| {
+            watchdog::schedule(deadline, interrupt::interrupter_of(&conn_id, &trx))
+        });
+        TRANSACTION_REGISTRY.insert(
+            trx_id.clone(),
+            TransactionHandle {
+                conn_id: conn_id.clone(),
+                trx,
+                timed_out: timed_out.clone(),
+                deadline,
+                interrupt_at,
+            },
+        );
+        request_state::track(Handle::Transaction, &trx_id);
+
+        Ok(Self {
+            trx_behavior: trx_mode.to_uppercase(),
+            trx_id,
+            conn_id,
+            savepoint: None,
+            active: Arc::new(AtomicBool::new(true)),
+            timed_out,
+            type_map: None,
+        })
+    }
+
+    /// Commits or rolls back the transaction; a nested transaction releases its savepoint.
+    pub(crate) fn finish(&self, commit: bool) -> Result<(), LibSQLError> {
+        let context = if commit {
+            "Failed to commit transaction"
+        } else {
+            "Failed to rollback transaction"
+        };
+
+        if let Some(savepoint) = &self.savepoint {
+            let savepoint = quote_identifier(savepoint);
+            if !commit {
+                self.execute_control(&format!("ROLLBACK TO SAVEPOINT {}", savepoint), context)?;
+            }
+            self.execute_control(&format!("RELEASE SAVEPOINT {}", savepoint), context)?;
+            self.active.store(false, Ordering::SeqCst);
+            return Ok(());
+        }
+
+        if !self.active.swap(false, Ordering::SeqCst) || expire_if_due(&self.trx_id) {
+            return Err(self.ended_error());
+        }
+
+        let handle = TRANSACTION_REGISTRY
             .remove(&self.trx_id)
-            .ok_or_else(|| PhpException::from("Transaction not found"))?;
+            .ok_or_else(|| self.ended_error())?;
+        request_state::untrack(Handle::Transaction, &self.trx_id);
+
+        runtime()
+            .block_on(async {
+                if commit {
+                    handle.trx.commit().await
+                } else {
+                    handle.trx.rollback().await
+                }
+            })
+            .map_err(|e| {
+                LibSQLError::from_libsql(&e)
+                    .context(context)
+                    .with_connection(&self.conn_id)
+            })
+    }
 
-        let rollback_result = runtime().block_on(async { trx.rollback().await });
+    /// Starts a savepoint-backed transaction inside the registered transaction `root_id`.
+    fn nested(
+        conn_id: String,
+        root_id: String,
+        trx_mode: String,
+        timed_out: Arc<AtomicBool>,
+    ) -> Result<Self, LibSQLError> {
+        let savepoint = format!("libsql_trx_{}", uuid::Uuid::new_v4().simple());
 
-        match rollback_result {
-            Ok(_) => Ok(()),
-            Err(_) => Err(PhpException::from("Failed to rollback transaction")),
+        let trx = Self {
+            trx_behavior: trx_mode.to_uppercase(),
+            trx_id: root_id,
+            conn_id,
+            savepoint: Some(savepoint.clone()),
+            active: Arc::new(AtomicBool::new(true)),
+            timed_out,
+            type_map: None,
+        };
+        if let Err(e) = trx.execute_control(
+            &format!("SAVEPOINT {}", quote_identifier(&savepoint)),
+            "Failed to begin nested transaction",
+        ) {
+            // There is no savepoint to roll back when it is dropped
+            trx.active.store(false, Ordering::SeqCst);
+            return Err(e);
         }
+
+        Ok(trx)
     }
+
+    /// Returns the connection of the transaction, failing once the transaction has ended.
+    fn connection(&self) -> Result<libsql::Connection, LibSQLError> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(self.ended_error());
+        }
+
+        transaction_connection(&self.trx_id).map_err(|_| self.ended_error())
+    }
+
+    /// Replaces the error of a statement the watchdog interrupted at the deadline of the
+    /// transaction, which is rolled back, with the timeout of the transaction.
+    fn timed_out_or(&self, error: PhpException) -> PhpException {
+        if expire_if_due(&self.trx_id) {
+            self.ended_error().into()
+        } else {
+            error
+        }
+    }
+
+    /// Describes why the transaction can no longer be used.
+    fn ended_error(&self) -> LibSQLError {
+        if self.timed_out.load(Ordering::SeqCst) {
+            timed_out_error().with_connection(&self.conn_id)
+        } else {
+            inactive_error()
+        }
+    }
+
+    /// Runs a transaction control statement, such as `SAVEPOINT`, on the transaction.
+    fn execute_control(&self, sql: &str, context: &str) -> Result<(), LibSQLError> {
+        let conn = self.connection()?;
+
+        runtime()
+            .block_on(async { conn.execute(sql, ()).await })
+            .map(|_| ())
+            .map_err(|e| {
+                LibSQLError::from_libsql(&e)
+                    .context(context)
+                    .with_sql(sql)
+                    .with_connection(&self.conn_id)
+            })
+    }
+}
+
+/// Rolls back a transaction whose PHP object is destroyed while it is still active, so an
+/// abandoned transaction does not hold its locks until the end of the process.
+impl Drop for LibSQLTransaction {
+    fn drop(&mut self) {
+        // Clones share the transaction; only the last one releases it
+        if Arc::strong_count(&self.active) > 1 || !self.is_active() {
+            return;
+        }
+
+        match self.finish(false) {
+            Ok(()) => leak_debug::warn(
+                "LibSQLTransaction destroyed without commit() or rollback(), it was rolled back",
+            ),
+            Err(e) => leak_debug::warn(&format!(
+                "LibSQLTransaction destroyed without commit() or rollback(), rollback failed: {}",
+                e
+            )),
+        }
+    }
+}
+
+/// Returns the connection of a registered transaction.
+///
+/// Statements run on this connection belong to the transaction; for a remote database it is
+/// bound to the stream the transaction was started on.
+///
+/// # Arguments
+///
+/// * `trx_id` - The ID of the transaction.
+///
+/// # Returns
+///
+/// The connection, or an error if the transaction has been committed or rolled back, or has
+/// timed out.
+pub(crate) fn transaction_connection(trx_id: &str) -> Result<libsql::Connection, LibSQLError> {
+    if expire_if_due(trx_id) {
+        return Err(timed_out_error());
+    }
+
+    TRANSACTION_REGISTRY
+        .with(trx_id, |handle| (*handle.trx).clone())
+        .ok_or_else(inactive_error)
+}
+
+/// Maps a transaction behavior name to the libSQL behavior; `WRITE` and `READ` are accepted
+/// as aliases of `IMMEDIATE` and `READONLY`.
+fn parse_behavior(behavior: &str) -> Result<libsql::TransactionBehavior, LibSQLError> {
+    match behavior.to_uppercase().as_str() {
+        "DEFERRED" => Ok(libsql::TransactionBehavior::Deferred),
+        "IMMEDIATE" | "WRITE" => Ok(libsql::TransactionBehavior::Immediate),
+        "EXCLUSIVE" => Ok(libsql::TransactionBehavior::Exclusive),
+        "READONLY" | "READ" => Ok(libsql::TransactionBehavior::ReadOnly),
+        _ => Err(LibSQLError::new(
+            ErrorKind::Generic,
+            format!(
+                "Unknown transaction behavior '{}', expected DEFERRED, IMMEDIATE, EXCLUSIVE or READONLY",
+                behavior
+            ),
+        )),
+    }
+}
+
+/// Rolls back a transaction whose timeout elapsed, unless it has already ended.
+///
+/// The watchdog only interrupts the statement running at the deadline; the deadline is checked
+/// whenever the transaction or its connection is used, so the rollback runs on the thread that
+/// owns the transaction rather than racing its statements.
+///
+/// # Returns
+///
+/// `true` if the transaction was rolled back.
+fn expire_if_due(trx_id: &str) -> bool {
+    let due = TRANSACTION_REGISTRY
+        .with(trx_id, |handle| {
+            handle
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        })
+        .unwrap_or(false);
+    if !due {
+        return false;
+    }
+
+    let handle = TRANSACTION_REGISTRY.remove_with(trx_id, |handle| {
+        handle.timed_out.store(true, Ordering::SeqCst)
+    });
+    let Some(handle) = handle else {
+        return false;
+    };
+    request_state::untrack(Handle::Transaction, trx_id);
+
+    if let Err(e) = runtime().block_on(async { handle.trx.rollback().await }) {
+        log_error_to_tmp(&format!("Failed to roll back timed out transaction: {}", e));
+    }
+    true
+}
+
+/// Rolls back the transaction open on a connection if its timeout elapsed, before a statement
+/// runs on the connection itself.
+///
+/// # Returns
+///
+/// A `TimeoutException` error if the transaction was rolled back, since the statement was
+/// issued while it was open.
+pub(crate) fn expire_on_connection(conn_id: &str) -> Result<(), LibSQLError> {
+    let root = TRANSACTION_REGISTRY.find_map(|trx_id, handle| {
+        (handle.conn_id == conn_id && handle.deadline.is_some()).then(|| trx_id.to_string())
+    });
+
+    match root {
+        Some(trx_id) if expire_if_due(&trx_id) => Err(timed_out_error().with_connection(conn_id)),
+        _ => Ok(()),
+    }
+}
+
+fn timed_out_error() -> LibSQLError {
+    LibSQLError::new(
+        ErrorKind::Timeout,
+        "Transaction timed out and was rolled back",
+    )
+}
+
+fn inactive_error() -> LibSQLError {
+    LibSQLError::new(ErrorKind::Generic, "Transaction is no longer active")
 }
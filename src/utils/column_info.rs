@@ -0,0 +1,71 @@
+use ext_php_rs::convert::IntoZval;
+use ext_php_rs::types::{ZendHashTable, Zval};
+
+/// Represents the metadata of a single column produced by a prepared statement.
+#[derive(Debug, Clone)]
+pub struct ColumnInfo {
+    /// The name of the column as it appears in the result set.
+    pub name: String,
+    /// The declared type of the column, if the column maps to a table column.
+    pub decl_type: Option<String>,
+    /// The table the column originates from, if any.
+    pub table_name: Option<String>,
+    /// The name of the originating table column, if any.
+    pub origin_name: Option<String>,
+    /// The database the originating table belongs to, if any.
+    pub database_name: Option<String>,
+}
+
+impl ColumnInfo {
+    /// Collects the column metadata of a prepared statement.
+    ///
+    /// The metadata is derived from the statement itself, so it is available even when
+    /// executing the statement would not return any rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `stmt` - The prepared statement to inspect.
+    ///
+    /// # Returns
+    ///
+    /// A vector with one `ColumnInfo` per result column, in result order.
+    pub fn from_statement(stmt: &libsql::Statement) -> Vec<ColumnInfo> {
+        stmt.columns()
+            .iter()
+            .map(|column| ColumnInfo {
+                name: column.name().to_string(),
+                decl_type: column.decl_type().map(|s| s.to_string()),
+                table_name: column.table_name().map(|s| s.to_string()),
+                origin_name: column.origin_name().map(|s| s.to_string()),
+                database_name: column.database_name().map(|s| s.to_string()),
+            })
+            .collect()
+    }
+}
+
+impl IntoZval for ColumnInfo {
+    const TYPE: ext_php_rs::flags::DataType = ext_php_rs::flags::DataType::Array;
+    const NULLABLE: bool = false;
+
+    /// Sets the ColumnInfo into a Zval as an associative array.
+    ///
+    /// Missing metadata (e.g. for expression columns) is exposed as `null`.
+    fn set_zval(self, zv: &mut Zval, _: bool) -> ext_php_rs::error::Result<()> {
+        let mut array = ZendHashTable::new();
+
+        array.insert("name", self.name)?;
+        array.insert("decl_type", self.decl_type)?;
+        array.insert("table_name", self.table_name)?;
+        array.insert("origin_name", self.origin_name)?;
+        array.insert("database_name", self.database_name)?;
+
+        *zv = array.into_zval(false)?;
+        Ok(())
+    }
+
+    fn into_zval(self, persistent: bool) -> ext_php_rs::error::Result<Zval> {
+        let mut zval = Zval::new();
+        self.set_zval(&mut zval, persistent)?;
+        Ok(zval)
+    }
+}
@@ -1,6 +1,8 @@
 #[derive(Debug, Clone)]
 pub enum ConfigValue {
     String(String),
+    Long(i64),
+    Bool(bool),
     Array(std::collections::HashMap<String, ConfigValue>),
 }
 
@@ -13,11 +15,24 @@ impl ConfigValue {
     }
 
     pub fn to_long(&self) -> Option<u64> {
-        None
+        match self {
+            ConfigValue::Long(l) => u64::try_from(*l).ok(),
+            ConfigValue::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
     }
 
     pub fn to_bool(&self) -> Option<bool> {
-        None
+        match self {
+            ConfigValue::Bool(b) => Some(*b),
+            ConfigValue::Long(l) => Some(*l != 0),
+            ConfigValue::String(s) => match s.trim().to_lowercase().as_str() {
+                "1" | "true" | "on" | "yes" => Some(true),
+                "0" | "false" | "off" | "no" | "" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
     }
 }
 
@@ -33,6 +48,8 @@ impl<'a> ext_php_rs::convert::FromZval<'a> for ConfigValue {
                 let key_str = key.to_string();
                 let value = match val {
                     v if v.is_string() => ConfigValue::String(v.string()?),
+                    v if v.is_long() => ConfigValue::Long(v.long()?),
+                    v if v.is_bool() => ConfigValue::Bool(v.bool()?),
                     v if v.is_array() => ConfigValue::from_zval(v)?,
                     _ => continue,
                 };
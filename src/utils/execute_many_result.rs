@@ -0,0 +1,33 @@
+use ext_php_rs::convert::IntoZval;
+use ext_php_rs::types::{ZendHashTable, Zval};
+
+/// Represents the outcome of running one statement over many parameter sets.
+#[derive(Debug, Clone, Default)]
+pub struct ExecuteManyResult {
+    /// Total number of rows changed by every execution.
+    pub rows_changed: u64,
+    /// The last insert rowid after each execution, in parameter set order.
+    pub last_insert_ids: Vec<i64>,
+}
+
+impl IntoZval for ExecuteManyResult {
+    const TYPE: ext_php_rs::flags::DataType = ext_php_rs::flags::DataType::Array;
+    const NULLABLE: bool = false;
+
+    /// Sets the ExecuteManyResult into a Zval as `['rowsChanged' => int, 'lastInsertIds' => int[]]`.
+    fn set_zval(self, zv: &mut Zval, _: bool) -> ext_php_rs::error::Result<()> {
+        let mut array = ZendHashTable::new();
+
+        array.insert("rowsChanged", self.rows_changed)?;
+        array.insert("lastInsertIds", self.last_insert_ids)?;
+
+        *zv = array.into_zval(false)?;
+        Ok(())
+    }
+
+    fn into_zval(self, persistent: bool) -> ext_php_rs::error::Result<Zval> {
+        let mut zval = Zval::new();
+        self.set_zval(&mut zval, persistent)?;
+        Ok(zval)
+    }
+}
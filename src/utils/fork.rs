@@ -0,0 +1,100 @@
+use std::{
+    mem::ManuallyDrop,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use crate::{
+    utils::registry::Registry, CONNECTION_LIFECYCLE_REGISTRY, CONNECTION_MODE_REGISTRY,
+    CONNECTION_REGISTRY, DATETIME_BINDING_REGISTRY, INTERRUPT_REGISTRY, NATIVE_HANDLE_REGISTRY,
+    OFFLINE_CONNECTION_REGISTRY, STATEMENT_CACHE_REGISTRY, STATEMENT_REGISTRY,
+    TRANSACTION_REGISTRY,
+};
+
+/// The pid of the process the registered handles were opened in.
+static HANDLES_PID: AtomicU32 = AtomicU32::new(0);
+
+/// Forgets the handles inherited from the parent process, if this process is a fork.
+///
+/// Called when PHP enters the extension, before any registry is locked, so that a child of
+/// `pcntl_fork` or of the FPM master never uses its parent's connections.
+pub fn release_after_fork() {
+    let pid = std::process::id();
+    let previous = HANDLES_PID.load(Ordering::Acquire);
+
+    if previous == pid {
+        return;
+    }
+
+    if HANDLES_PID
+        .compare_exchange(previous, pid, Ordering::AcqRel, Ordering::Acquire)
+        .is_ok()
+        && previous != 0
+    {
+        forget_inherited_handles();
+    }
+}
+
+/// Drops the registered handles of the parent process.
+///
+/// Remote connections are bound to the parent's runtime, and a SQLite connection must not be
+/// used on both sides of a fork. Their destructors are not run, since they could close or
+/// unlock what the parent still uses.
+fn forget_inherited_handles() {
+    fn forget<T>(registry: &Registry<T>) {
+        for (_, handle) in registry.drain() {
+            std::mem::forget(handle);
+        }
+    }
+
+    forget(&TRANSACTION_REGISTRY);
+    forget(&STATEMENT_REGISTRY);
+    forget(&STATEMENT_CACHE_REGISTRY);
+    forget(&CONNECTION_REGISTRY);
+    forget(&OFFLINE_CONNECTION_REGISTRY);
+    forget(&CONNECTION_LIFECYCLE_REGISTRY);
+    forget(&NATIVE_HANDLE_REGISTRY);
+    CONNECTION_MODE_REGISTRY.clear();
+    INTERRUPT_REGISTRY.clear();
+    DATETIME_BINDING_REGISTRY.clear();
+}
+
+/// A handle kept outside the registries, such as by a PHP object, that is only used and dropped
+/// in the process that opened it.
+///
+/// An object created before a fork still holds its parent's handle in the child; like the
+/// registered handles, it is forgotten there rather than used or dropped.
+pub struct ProcessLocal<T> {
+    value: ManuallyDrop<T>,
+    pid: u32,
+}
+
+impl<T> ProcessLocal<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value: ManuallyDrop::new(value),
+            pid: std::process::id(),
+        }
+    }
+
+    /// Returns the handle, unless it was opened by the parent of this process.
+    pub fn get(&self) -> Option<&T> {
+        (self.pid == std::process::id()).then_some(&*self.value)
+    }
+}
+
+impl<T: Clone> Clone for ProcessLocal<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            pid: self.pid,
+        }
+    }
+}
+
+impl<T> Drop for ProcessLocal<T> {
+    fn drop(&mut self) {
+        if self.pid == std::process::id() {
+            unsafe { ManuallyDrop::drop(&mut self.value) };
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use ext_php_rs::exception::PhpException;
+
+use crate::utils::{fork::release_after_fork, log_error::log_error_to_tmp};
+
+/// Runs the body of a method called from PHP, converting a panic into a PHP exception.
+///
+/// A panic must not unwind into the PHP engine, where it would abort the whole process. The
+/// handles inherited from a parent process are released first, see `release_after_fork`.
+///
+/// # Arguments
+///
+/// * `f` - The body of the method.
+///
+/// # Returns
+///
+/// The result of `f`, or a `PhpException` describing the panic.
+pub fn catch_panic<T>(f: impl FnOnce() -> Result<T, PhpException>) -> Result<T, PhpException> {
+    release_after_fork();
+
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let reason = payload
+            .downcast_ref::<&str>()
+            .map(|reason| reason.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+
+        let err_msg = format!("Internal error: {}", reason);
+        log_error_to_tmp(&err_msg);
+        Err(PhpException::default(err_msg))
+    })
+}
+
+/// Locks a mutex, recovering it if a panic poisoned it while it was held.
+///
+/// The registries only hold handles, which stay consistent when an operation panics, so
+/// a poisoned registry is still safe to use.
+pub trait LockExt<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> LockExt<T> for Mutex<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
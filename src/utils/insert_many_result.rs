@@ -0,0 +1,44 @@
+use ext_php_rs::convert::IntoZval;
+use ext_php_rs::types::{ZendHashTable, Zval};
+
+/// Represents the outcome of a bulk insert or upsert.
+#[derive(Debug, Clone, Default)]
+pub struct InsertManyResult {
+    /// Number of rows that were inserted.
+    pub inserted: u64,
+    /// Number of existing rows that were updated because of a conflict.
+    pub updated: u64,
+}
+
+impl InsertManyResult {
+    /// Adds the rows changed by a statement, of which `inserted` were inserted and the rest
+    /// updated; every row counts as inserted when `inserted` is `None`.
+    pub fn add_chunk(&mut self, changed: u64, inserted: Option<u64>) {
+        let inserted = inserted.unwrap_or(changed).min(changed);
+
+        self.inserted += inserted;
+        self.updated += changed - inserted;
+    }
+}
+
+impl IntoZval for InsertManyResult {
+    const TYPE: ext_php_rs::flags::DataType = ext_php_rs::flags::DataType::Array;
+    const NULLABLE: bool = false;
+
+    /// Sets the InsertManyResult into a Zval as `['inserted' => int, 'updated' => int]`.
+    fn set_zval(self, zv: &mut Zval, _: bool) -> ext_php_rs::error::Result<()> {
+        let mut array = ZendHashTable::new();
+
+        array.insert("inserted", self.inserted)?;
+        array.insert("updated", self.updated)?;
+
+        *zv = array.into_zval(false)?;
+        Ok(())
+    }
+
+    fn into_zval(self, persistent: bool) -> ext_php_rs::error::Result<Zval> {
+        let mut zval = Zval::new();
+        self.set_zval(&mut zval, persistent)?;
+        Ok(zval)
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use ext_php_rs::exception::PhpException;
+use tokio::sync::Notify;
+
+use crate::{
+    errors::{ErrorKind, LibSQLError},
+    utils::{runtime::runtime, watchdog},
+    CONNECTION_MODE_REGISTRY, INTERRUPT_REGISTRY,
+};
+
+/// Stops the queries running on a connection, when it is interrupted or their timeout elapses.
+#[derive(Default)]
+pub struct Interrupt {
+    /// The timeout of the queries run on the connection, unless a call sets its own.
+    timeout: Option<Duration>,
+
+    /// Wakes the queries waiting on a remote database.
+    notify: Notify,
+}
+
+/// Registers the query timeout of a connection.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection.
+/// * `timeout` - The timeout of its queries, or `None` to let them run until they finish.
+pub fn register(conn_id: &str, timeout: Option<Duration>) {
+    INTERRUPT_REGISTRY.insert(
+        conn_id.to_string(),
+        Arc::new(Interrupt {
+            timeout,
+            notify: Notify::new(),
+        }),
+    );
+}
+
+/// Interrupts the queries running on a connection.
+///
+/// SQLite stops a local query at its next step; a query waiting on a remote database is
+/// abandoned. Queries started afterwards run normally.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection.
+/// * `conn` - The connection.
+pub fn interrupt(conn_id: &str, conn: &libsql::Connection) {
+    interrupter(conn, INTERRUPT_REGISTRY.get(conn_id))();
+}
+
+/// Returns a function interrupting the queries running on a connection, to be called later,
+/// for instance by the watchdog.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection.
+/// * `conn` - The connection, which may be a transaction's.
+pub fn interrupter_of(conn_id: &str, conn: &libsql::Connection) -> impl FnOnce() + Send + 'static {
+    interrupter(conn, INTERRUPT_REGISTRY.get(conn_id))
+}
+
+fn interrupter(
+    conn: &libsql::Connection,
+    interrupt: Option<Arc<Interrupt>>,
+) -> impl FnOnce() + Send + 'static {
+    let conn = conn.clone();
+
+    move || {
+        // Only local connections support it, remote queries and writes an embedded replica
+        // forwarded to its primary are woken below instead
+        let _ = conn.interrupt();
+
+        if let Some(interrupt) = interrupt {
+            interrupt.notify.notify_waiters();
+        }
+    }
+}
+
+/// Runs a query, stopping it when the connection is interrupted or the query timeout elapses.
+///
+/// A remote query is cancelled when its timeout elapses. A local query blocks the runtime while
+/// it runs, so its timeout is watched by the watchdog thread, which interrupts it. A remote
+/// query that is stopped leaves its stream in an unknown state, so the stream is discarded and
+/// the next query opens a new one.
+///
+/// The query must not block on the runtime itself.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection the query runs on.
+/// * `conn` - The connection the query runs on, which may be a transaction's.
+/// * `timeout` - The timeout of this query, or `None` for the timeout of the connection.
+/// * `query` - The query.
+///
+/// # Returns
+///
+/// The result of the query, or a `TimeoutException` if it was stopped.
+pub fn run<T>(
+    conn_id: &str,
+    conn: &libsql::Connection,
+    timeout: Option<Duration>,
+    query: impl Future<Output = Result<T, PhpException>>,
+) -> Result<T, PhpException> {
+    let interrupt = INTERRUPT_REGISTRY.get(conn_id).unwrap_or_default();
+    let timeout = timeout.or(interrupt.timeout);
+    let remote = CONNECTION_MODE_REGISTRY.get(conn_id).as_deref() == Some("remote");
+    let timed_out = Arc::new(AtomicBool::new(false));
+
+    // Created before the deadline is scheduled, so a notification sent before the query is
+    // first polled still wakes it
+    let notified = interrupt.notify.notified();
+
+    let deadline = timeout.filter(|_| !remote).map(|timeout| {
+        let stop = interrupter(conn, Some(interrupt.clone()));
+        let timed_out = timed_out.clone();
+
+        watchdog::schedule(Instant::now() + timeout, move || {
+            timed_out.store(true, Ordering::SeqCst);
+            stop();
+        })
+    });
+
+    let outcome = runtime().block_on(async {
+        let query = async {
+            match timeout.filter(|_| remote) {
+                Some(timeout) => tokio::time::timeout(timeout, query)
+                    .await
+                    .map_err(|_| timed_out.store(true, Ordering::SeqCst))
+                    .ok(),
+                None => Some(query.await),
+            }
+        };
+
+        tokio::select! {
+            outcome = query => outcome,
+            _ = notified => None,
+        }
+    });
+
+    // Once dropped, the deadline cannot interrupt a query started after this one
+    drop(deadline);
+
+    if outcome.is_none() && remote {
+        runtime().block_on(async { conn.reset().await });
+    }
+
+    let stopped = || -> PhpException {
+        let error = match timeout {
+            Some(timeout) if timed_out.load(Ordering::SeqCst) => LibSQLError::new(
+                ErrorKind::Timeout,
+                format!("Query timed out after {} ms", timeout.as_millis()),
+            ),
+            _ => LibSQLError::new(ErrorKind::Timeout, "Query was interrupted"),
+        };
+        error.with_connection(conn_id).into()
+    };
+
+    match outcome {
+        None => Err(stopped()),
+        // A local query interrupted by the timeout fails with SQLITE_INTERRUPT
+        Some(Err(_)) if timed_out.load(Ordering::SeqCst) => Err(stopped()),
+        Some(result) => result,
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use ext_php_rs::{error::php_error, flags::ErrorType};
+
+use crate::{
+    utils::log_error::log_error_to_tmp, CONNECTION_REGISTRY, OFFLINE_CONNECTION_REGISTRY,
+    STATEMENT_REGISTRY, TRANSACTION_REGISTRY,
+};
+
+static LEAK_DEBUGGING: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables the reporting of handles that were never released explicitly.
+///
+/// # Arguments
+///
+/// * `enabled` - Whether to report leaked handles.
+pub fn set_leak_debugging(enabled: bool) {
+    LEAK_DEBUGGING.store(enabled, Ordering::SeqCst);
+}
+
+/// Returns `true` if leaked handles are reported.
+pub fn is_leak_debugging() -> bool {
+    LEAK_DEBUGGING.load(Ordering::SeqCst)
+}
+
+/// Raises a PHP warning, also written to the error log.
+///
+/// # Arguments
+///
+/// * `message` - The warning message.
+pub fn warn(message: &str) {
+    log_error_to_tmp(message);
+    // The message is used as a printf format string
+    php_error(&ErrorType::Warning, &message.replace('%', "%%"));
+}
+
+/// Counts the handles held in the registries.
+///
+/// # Returns
+///
+/// The number of open `connections`, `transactions` and `statements`.
+pub fn open_handles() -> [(&'static str, usize); 3] {
+    let connections = CONNECTION_REGISTRY.len() + OFFLINE_CONNECTION_REGISTRY.len();
+
+    [
+        ("connections", connections),
+        ("transactions", TRANSACTION_REGISTRY.len()),
+        ("statements", STATEMENT_REGISTRY.len()),
+    ]
+}
+
+/// Logs the handles that are still open, if leak debugging is enabled.
+pub fn report_open_handles() {
+    if !is_leak_debugging() {
+        return;
+    }
+
+    for (kind, count) in open_handles() {
+        if count > 0 {
+            log_error_to_tmp(&format!("Leaked handles: {} {} still open", count, kind));
+        }
+    }
+}
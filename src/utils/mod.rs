@@ -1,5 +1,18 @@
+pub mod column_info;
 pub mod config_value;
+pub mod execute_many_result;
+pub mod fork;
+pub mod guard;
+pub mod insert_many_result;
+pub mod interrupt;
+pub mod leak_debug;
 pub mod log_error;
+pub mod native_handle;
 pub mod query_params;
+pub mod registry;
 pub mod result_set;
 pub mod runtime;
+pub mod sql_lexer;
+pub mod statement_cache;
+pub mod type_map;
+pub mod watchdog;
@@ -0,0 +1,420 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ffi::{c_char, c_int, c_void, CStr},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+    rc::Rc,
+    sync::Once,
+};
+
+use ext_php_rs::{convert::IntoZvalDyn, types::ZendCallable};
+use libsql::ffi;
+
+use crate::{
+    errors::{ErrorKind, LibSQLError},
+    utils::runtime::runtime,
+    NATIVE_HANDLE_REGISTRY,
+};
+
+/// The highest `SQLITE_LIMIT_*` category, `SQLITE_LIMIT_WORKER_THREADS`.
+const MAX_LIMIT: i32 = ffi::SQLITE_LIMIT_WORKER_THREADS;
+
+/// The SQL function defined on the handles opened during a capture, which returns the handle the
+/// statement calling it runs on.
+const PROBE: &CStr = c"libsql_php_handle";
+
+static REGISTER_OPEN_HOOK: Once = Once::new();
+
+thread_local! {
+    // Only connections opened on this thread while a capture is running are recorded
+    static CAPTURING: Cell<bool> = const { Cell::new(false) };
+    static OPENED: RefCell<Vec<*mut ffi::sqlite3>> = const { RefCell::new(Vec::new()) };
+
+    // The callables belong to the request that set them, so they are only called for queries
+    // run on its thread
+    static CALLBACKS: RefCell<HashMap<String, Callbacks>> = RefCell::default();
+}
+
+/// The raw SQLite handle of a local or embedded replica connection.
+///
+/// libsql exposes neither progress handlers nor limits, and only supports authorizers on local
+/// connections, so they are set on the handle directly. The handle is only valid while its
+/// connection is open, and must be released before it closes.
+pub struct NativeHandle {
+    db: *mut ffi::sqlite3,
+
+    /// Passed to the callbacks, boxed so its address is stable.
+    context: Box<CallbackContext>,
+}
+
+/// Identifies the connection whose callback SQLite calls.
+struct CallbackContext {
+    conn_id: String,
+}
+
+/// The PHP callables set on a connection.
+#[derive(Default)]
+struct Callbacks {
+    progress: Option<Rc<ZendCallable<'static>>>,
+    authorizer: Option<Rc<ZendCallable<'static>>>,
+}
+
+impl NativeHandle {
+    fn context(&self) -> *mut c_void {
+        &*self.context as *const CallbackContext as *mut c_void
+    }
+
+    /// Removes every callback from the connection.
+    fn unhook(&self) {
+        unsafe {
+            ffi::sqlite3_progress_handler(self.db, 0, None, ptr::null_mut());
+            ffi::sqlite3_set_authorizer(self.db, None, ptr::null_mut());
+        }
+    }
+}
+
+// The handle is only used through SQLite functions, which are serialized by SQLite itself.
+unsafe impl Send for NativeHandle {}
+
+/// Records the handles of the connections opened on this thread until it is finished.
+///
+/// libsql does not expose the handle of a connection, and opening a database may open several
+/// handles, for instance for replication. Each recorded handle is given a SQL function returning
+/// the handle it runs on, so the connection itself tells which handle it uses.
+pub struct Capture(());
+
+impl Capture {
+    /// Starts recording the connections opened on this thread.
+    pub fn start() -> Self {
+        REGISTER_OPEN_HOOK.call_once(|| unsafe {
+            ffi::sqlite3_auto_extension(Some(on_open));
+        });
+
+        OPENED.take();
+        CAPTURING.set(true);
+        Capture(())
+    }
+
+    /// Stops recording, returning the handle `conn` runs its statements on.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn_id` - The ID the connection is registered with.
+    /// * `conn` - The local or embedded replica connection opened since `start`.
+    ///
+    /// # Returns
+    ///
+    /// The handle, or an error if `conn` does not run on a handle opened since `start`.
+    pub fn finish(
+        self,
+        conn_id: &str,
+        conn: &libsql::Connection,
+    ) -> Result<NativeHandle, LibSQLError> {
+        let opened = OPENED.take();
+        let not_found = |reason: &str| {
+            LibSQLError::new(
+                ErrorKind::Generic,
+                format!(
+                    "Failed to find the SQLite handle of the connection: {}",
+                    reason
+                ),
+            )
+            .with_connection(conn_id)
+        };
+
+        let sql = format!("SELECT {}()", PROBE.to_string_lossy());
+        let address = runtime()
+            .block_on(async {
+                let row = conn.query(&sql, ()).await?.next().await?;
+                row.map(|row| row.get::<i64>(0)).transpose()
+            })
+            .map_err(|e| not_found(&e.to_string()))?
+            .ok_or_else(|| not_found("the probe returned no row"))?;
+
+        // The address is only trusted once it matches a handle opened for the connection
+        let db = opened
+            .into_iter()
+            .find(|&db| db as i64 == address)
+            .ok_or_else(|| not_found("the probe ran on a handle the connection did not open"))?;
+
+        unsafe { define_probe(db, None) };
+
+        Ok(NativeHandle {
+            db,
+            context: Box::new(CallbackContext {
+                conn_id: conn_id.to_string(),
+            }),
+        })
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        CAPTURING.set(false);
+        OPENED.take();
+    }
+}
+
+/// Runs whenever SQLite opens a connection, as an automatic extension.
+unsafe extern "C" fn on_open(
+    db: *mut ffi::sqlite3,
+    _err: *mut *const c_char,
+    _api: *const ffi::sqlite3_api_routines,
+) -> c_int {
+    // The probe is defined while the handle is known to be open; a handle libsql closes again
+    // before the capture finishes is never used afterwards
+    if CAPTURING.get() && define_probe(db, Some(probe)) == ffi::SQLITE_OK {
+        OPENED.with_borrow_mut(|opened| opened.push(db));
+    }
+
+    ffi::SQLITE_OK
+}
+
+/// Defines the probe function on `db`, or removes it when `function` is `None`.
+unsafe fn define_probe(
+    db: *mut ffi::sqlite3,
+    function: Option<
+        unsafe extern "C" fn(*mut ffi::sqlite3_context, c_int, *mut *mut ffi::sqlite3_value),
+    >,
+) -> c_int {
+    ffi::sqlite3_create_function_v2(
+        db,
+        PROBE.as_ptr(),
+        0,
+        ffi::SQLITE_UTF8,
+        ptr::null_mut(),
+        function,
+        None,
+        None,
+        None,
+    )
+}
+
+/// The body of the probe function, which returns the address of the handle it runs on.
+unsafe extern "C" fn probe(
+    ctx: *mut ffi::sqlite3_context,
+    _argc: c_int,
+    _argv: *mut *mut ffi::sqlite3_value,
+) {
+    ffi::sqlite3_result_int64(ctx, ffi::sqlite3_context_db_handle(ctx) as i64);
+}
+
+/// Sets or removes the progress handler of a connection.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection.
+/// * `ops` - The number of virtual machine instructions between two calls of the handler.
+/// * `callable` - The handler, or `None` to remove it. The query is aborted when it returns `true`.
+pub fn set_progress_handler(
+    conn_id: &str,
+    ops: i32,
+    callable: Option<ZendCallable<'static>>,
+) -> Result<(), LibSQLError> {
+    let callable = callable.filter(|_| ops > 0);
+
+    NATIVE_HANDLE_REGISTRY
+        .with(conn_id, |handle| unsafe {
+            match &callable {
+                Some(_) => ffi::sqlite3_progress_handler(
+                    handle.db,
+                    ops,
+                    Some(on_progress),
+                    handle.context(),
+                ),
+                None => ffi::sqlite3_progress_handler(handle.db, 0, None, ptr::null_mut()),
+            }
+        })
+        .ok_or_else(|| unavailable("Progress handlers", conn_id))?;
+
+    CALLBACKS.with(|callbacks| {
+        callbacks
+            .borrow_mut()
+            .entry(conn_id.to_string())
+            .or_default()
+            .progress = callable.map(Rc::new);
+    });
+
+    Ok(())
+}
+
+/// Calls the progress handler of the connection identified by `ctx`.
+unsafe extern "C" fn on_progress(ctx: *mut c_void) -> c_int {
+    // Only queries run on the thread that set the handler call it
+    let Some(callable) = callback(ctx, |callbacks| callbacks.progress.clone()) else {
+        return 0;
+    };
+
+    // A handler that throws aborts the query as well
+    catch_unwind(AssertUnwindSafe(|| match callable.try_call(vec![]) {
+        Ok(result) => c_int::from(result.bool().unwrap_or(false)),
+        Err(_) => 1,
+    }))
+    .unwrap_or(1)
+}
+
+/// Sets or removes the authorizer of a connection.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection.
+/// * `callable` - The authorizer, or `None` to remove it. It receives the action code, the two
+///   names describing the action, the database name and the innermost trigger or view, and
+///   returns `SQLITE_OK`, `SQLITE_DENY` or `SQLITE_IGNORE`.
+pub fn set_authorizer(
+    conn_id: &str,
+    callable: Option<ZendCallable<'static>>,
+) -> Result<(), LibSQLError> {
+    // SQLite expires the prepared statements, so cached ones are authorized again before they run
+    NATIVE_HANDLE_REGISTRY
+        .with(conn_id, |handle| unsafe {
+            match &callable {
+                Some(_) => {
+                    ffi::sqlite3_set_authorizer(handle.db, Some(on_authorize), handle.context())
+                }
+                None => ffi::sqlite3_set_authorizer(handle.db, None, ptr::null_mut()),
+            }
+        })
+        .ok_or_else(|| unavailable("Authorizers", conn_id))?;
+
+    CALLBACKS.with(|callbacks| {
+        callbacks
+            .borrow_mut()
+            .entry(conn_id.to_string())
+            .or_default()
+            .authorizer = callable.map(Rc::new);
+    });
+
+    Ok(())
+}
+
+/// Calls the authorizer of the connection identified by `ctx`.
+unsafe extern "C" fn on_authorize(
+    ctx: *mut c_void,
+    action: c_int,
+    arg1: *const c_char,
+    arg2: *const c_char,
+    database: *const c_char,
+    accessor: *const c_char,
+) -> c_int {
+    // The authorizer cannot be called on another thread, so nothing is authorized there; the
+    // extension itself only prepares statements on the thread that owns the connection
+    let Some(callable) = callback(ctx, |callbacks| callbacks.authorizer.clone()) else {
+        return ffi::SQLITE_DENY;
+    };
+
+    let text = |arg: *const c_char| {
+        (!arg.is_null()).then(|| CStr::from_ptr(arg).to_string_lossy().into_owned())
+    };
+    let action = i64::from(action);
+    let (arg1, arg2, database, accessor) = (text(arg1), text(arg2), text(database), text(accessor));
+    let args: Vec<&dyn IntoZvalDyn> = vec![&action, &arg1, &arg2, &database, &accessor];
+
+    // Anything but an explicit decision, including a thrown exception, denies the action
+    catch_unwind(AssertUnwindSafe(|| match callable.try_call(args) {
+        Ok(result) => match result
+            .long()
+            .and_then(|decision| c_int::try_from(decision).ok())
+        {
+            Some(decision @ (ffi::SQLITE_OK | ffi::SQLITE_IGNORE)) => decision,
+            _ => ffi::SQLITE_DENY,
+        },
+        Err(_) => ffi::SQLITE_DENY,
+    }))
+    .unwrap_or(ffi::SQLITE_DENY)
+}
+
+/// Looks up a callable of the connection identified by `ctx`, set on this thread.
+unsafe fn callback(
+    ctx: *mut c_void,
+    f: impl FnOnce(&Callbacks) -> Option<Rc<ZendCallable<'static>>>,
+) -> Option<Rc<ZendCallable<'static>>> {
+    let conn_id = &(*(ctx as *const CallbackContext)).conn_id;
+
+    CALLBACKS
+        .try_with(|callbacks| callbacks.borrow().get(conn_id).and_then(f))
+        .ok()
+        .flatten()
+}
+
+/// Reads or changes a runtime limit of a connection.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection.
+/// * `id` - The limit category, one of the `SQLITE_LIMIT_*` constants.
+/// * `value` - The new value of the limit, or `None` to leave it unchanged.
+///
+/// # Returns
+///
+/// The value of the limit before the call.
+pub fn limit(conn_id: &str, id: i64, value: Option<i64>) -> Result<i64, LibSQLError> {
+    let id = i32::try_from(id)
+        .ok()
+        .filter(|id| (0..=MAX_LIMIT).contains(id))
+        .ok_or_else(|| {
+            LibSQLError::new(
+                ErrorKind::Generic,
+                format!(
+                    "Unknown limit {}, expected one of the SQLITE_LIMIT_* constants",
+                    id
+                ),
+            )
+        })?;
+
+    let value = match value {
+        // SQLite caps the value at the limit's compile-time maximum
+        Some(value) if value >= 0 => value.min(i32::MAX as i64) as i32,
+        Some(value) => {
+            return Err(LibSQLError::new(
+                ErrorKind::Generic,
+                format!("Invalid limit value {}, expected at least 0", value),
+            ))
+        }
+        None => -1,
+    };
+
+    NATIVE_HANDLE_REGISTRY
+        .with(conn_id, |handle| unsafe {
+            ffi::sqlite3_limit(handle.db, id, value) as i64
+        })
+        .ok_or_else(|| unavailable("Limits", conn_id))
+}
+
+/// Releases the handle of a connection that is being closed, removing its callbacks.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection.
+pub fn release(conn_id: &str) {
+    if let Some(handle) = NATIVE_HANDLE_REGISTRY.remove(conn_id) {
+        handle.unhook();
+    }
+
+    let _ = CALLBACKS.try_with(|callbacks| callbacks.borrow_mut().remove(conn_id));
+}
+
+/// Removes the callbacks set on this thread, whose callables end with the request.
+///
+/// Connections kept open across requests stay registered, without their callbacks.
+pub fn release_callbacks() {
+    let Ok(callbacks) = CALLBACKS.try_with(|callbacks| callbacks.take()) else {
+        return;
+    };
+
+    for conn_id in callbacks.keys() {
+        NATIVE_HANDLE_REGISTRY.with(conn_id, |handle| handle.unhook());
+    }
+}
+
+fn unavailable(feature: &str, conn_id: &str) -> LibSQLError {
+    LibSQLError::new(
+        ErrorKind::Generic,
+        format!(
+            "{} are only available on local and embedded replica connections",
+            feature
+        ),
+    )
+    .with_connection(conn_id)
+}
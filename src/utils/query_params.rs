@@ -1,11 +1,62 @@
 use ext_php_rs::{
     convert::FromZval,
-    flags::DataType,
-    types::{ArrayKey, Zval},
+    exception::PhpException,
+    types::{ArrayKey, ZendCallable, Zval},
+    zend::ClassEntry,
 };
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 
+use crate::{
+    blob::LibSQLBlob,
+    utils::sql_lexer::{self, PlaceholderKind},
+    DATETIME_BINDING_REGISTRY, LIBSQL_PARAM_BLOB, LIBSQL_PARAM_BOOL, LIBSQL_PARAM_FLOAT,
+    LIBSQL_PARAM_INT, LIBSQL_PARAM_NULL, LIBSQL_PARAM_STR,
+};
+
+/// `JSON_THROW_ON_ERROR`, so `json_encode` reports unencodable values instead of returning `false`.
+const JSON_THROW_ON_ERROR: i64 = 4194304;
+
+/// The maximum number of host parameters in one statement (`SQLITE_MAX_VARIABLE_NUMBER`).
+pub const MAX_VARIABLES: usize = 32766;
+
+/// The `date()` format of ISO-8601 timestamps, e.g. `2024-05-01T10:00:00+00:00`.
+const DATETIME_ISO8601: &str = "Y-m-d\\TH:i:sP";
+
+/// How a connection binds `DateTimeInterface` parameters.
+#[derive(Debug, Clone, Default)]
+pub enum DateTimeBinding {
+    #[default]
+    Iso8601,
+    Unix,
+    Format(String),
+}
+
+impl DateTimeBinding {
+    /// Parses a binding.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - `"iso8601"` for ISO-8601 text, `"unix"` for unix timestamps, or any other
+    ///   `date()` format string.
+    pub fn parse(format: &str) -> Self {
+        match format.trim().to_lowercase().as_str() {
+            "iso8601" | "iso" => DateTimeBinding::Iso8601,
+            "unix" | "timestamp" => DateTimeBinding::Unix,
+            _ => DateTimeBinding::Format(format.to_string()),
+        }
+    }
+
+    /// Returns how a connection binds `DateTimeInterface` parameters.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn_id` - The ID of the connection.
+    pub fn of_connection(conn_id: &str) -> Self {
+        DATETIME_BINDING_REGISTRY.get(conn_id).unwrap_or_default()
+    }
+}
+
 /// Represents a value used in query parameters.
 #[derive(Debug, Clone)]
 pub enum QueryValue {
@@ -14,6 +65,9 @@ pub enum QueryValue {
     Text(String),
     Blob(Vec<u8>),
     Null,
+    /// A PHP list of scalar values and its JSON encoding. Inside `IN (...)` it is expanded to
+    /// one parameter per item; anywhere else it binds as the JSON text.
+    List(Vec<QueryValue>, String),
 }
 
 impl fmt::Display for QueryValue {
@@ -30,58 +84,529 @@ impl fmt::Display for QueryValue {
                     .collect::<String>();
                 write!(f, "X'{}'", blob_str)
             }
+            QueryValue::List(_, json) => write!(f, "{}", json),
         }
     }
 }
 
+impl QueryValue {
+    /// Converts a PHP value to a query value.
+    ///
+    /// * `bool` binds as `0`/`1`
+    /// * strings bind as TEXT and must be valid UTF-8; binary data binds as BLOB when wrapped
+    ///   in `LibSQLBlob` or bound with `LibSQL::PARAM_BLOB`
+    /// * arrays and `JsonSerializable` objects bind as JSON text, except that lists of scalars
+    ///   are expanded when bound to the only placeholder of an `IN (...)` list
+    /// * `DateTimeInterface` objects bind as given by `dates`
+    /// * `Stringable` objects bind as the result of `__toString`
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The PHP value to be converted.
+    /// * `dates` - How the connection binds `DateTimeInterface` objects.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the query value, or a message describing why the value cannot be bound.
+    pub fn from_php(value: &Zval, dates: &DateTimeBinding) -> Result<QueryValue, String> {
+        if value.is_null() {
+            return Ok(QueryValue::Null);
+        }
+
+        if let Some(b) = value.bool() {
+            return Ok(QueryValue::Integer(b as i64));
+        }
+
+        if let Some(i) = value.long() {
+            return Ok(QueryValue::Integer(i));
+        }
+
+        if let Some(f) = value.double() {
+            return Ok(QueryValue::Real(f));
+        }
+
+        if let Some(s) = value.zend_str() {
+            return std::str::from_utf8(s.as_bytes())
+                .map(|text| QueryValue::Text(text.to_string()))
+                .map_err(|_| {
+                    "String is not valid UTF-8: wrap binary data in LibSQLBlob or bind it with LibSQL::PARAM_BLOB"
+                        .to_string()
+                });
+        }
+
+        if let Some(array) = value.array() {
+            let json = encode_json(value)?;
+            let mut items = Vec::with_capacity(array.len());
+
+            for (idx, (key, item)) in array.iter().enumerate() {
+                if !matches!(key, ArrayKey::Long(index) if index == idx as i64) || item.is_array() {
+                    return Ok(QueryValue::Text(json));
+                }
+
+                match QueryValue::from_php(item, dates) {
+                    Ok(item) => items.push(item),
+                    Err(_) => return Ok(QueryValue::Text(json)),
+                }
+            }
+
+            return Ok(QueryValue::List(items, json));
+        }
+
+        if let Some(blob) = <&LibSQLBlob>::from_zval(value) {
+            return Ok(QueryValue::Blob(blob.data.clone()));
+        }
+
+        if let Some(object) = value.object() {
+            let class_name = object
+                .get_class_name()
+                .unwrap_or_else(|_| "object".to_string());
+            let instance_of = |name: &str| {
+                ClassEntry::try_find(name)
+                    .map(|ce| object.instance_of(ce))
+                    .unwrap_or(false)
+            };
+
+            if instance_of("DateTimeInterface") {
+                return bind_datetime(value, &class_name, dates);
+            }
+
+            if instance_of("JsonSerializable") {
+                return encode_json(value).map(QueryValue::Text);
+            }
+
+            if instance_of("Stringable") {
+                return value
+                    .try_call_method("__toString", vec![])
+                    .ok()
+                    .and_then(|s| s.string())
+                    .map(QueryValue::Text)
+                    .ok_or_else(|| format!("Failed to convert {} to string", class_name));
+            }
+
+            return Err(format!(
+                "Cannot bind object of class {}: expected DateTimeInterface, JsonSerializable, Stringable or LibSQLBlob",
+                class_name
+            ));
+        }
+
+        Err(format!(
+            "Cannot bind value of type {}",
+            value.get_type().to_string().to_lowercase()
+        ))
+    }
+
+    /// Converts a PHP value to a query value, optionally coerced to a `LibSQL::PARAM_*` type.
+    ///
+    /// Strings bound as `PARAM_BLOB` keep their bytes, so binary data need not be valid UTF-8.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The PHP value to be converted.
+    /// * `param_type` - The optional `LibSQL::PARAM_*` type to coerce the value to.
+    /// * `dates` - How the connection binds `DateTimeInterface` objects.
+    pub fn from_php_as(
+        value: &Zval,
+        param_type: Option<i32>,
+        dates: &DateTimeBinding,
+    ) -> Result<QueryValue, String> {
+        match (param_type, value.zend_str()) {
+            (Some(LIBSQL_PARAM_BLOB), Some(s)) => Ok(QueryValue::Blob(s.as_bytes().to_vec())),
+            (Some(param_type), _) => QueryValue::from_php(value, dates)?.coerce(param_type),
+            (None, _) => QueryValue::from_php(value, dates),
+        }
+    }
+
+    /// Converts the query value to a LibSQL value.
+    pub fn to_value(&self) -> libsql::Value {
+        match self {
+            QueryValue::Integer(i) => libsql::Value::Integer(*i),
+            QueryValue::Real(f) => libsql::Value::Real(*f),
+            QueryValue::Null => libsql::Value::Null,
+            QueryValue::Blob(b) => libsql::Value::Blob(b.to_vec()),
+            QueryValue::Text(t) => libsql::Value::Text(t.to_string()),
+            QueryValue::List(_, json) => libsql::Value::Text(json.to_string()),
+        }
+    }
+
+    /// Coerces the query value to one of the `LibSQL::PARAM_*` types.
+    ///
+    /// `NULL` stays `NULL` unless `PARAM_BOOL` is requested, matching PHP's casts.
+    ///
+    /// # Arguments
+    ///
+    /// * `param_type` - The `LibSQL::PARAM_*` type to coerce to.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the coerced value, or a message describing why it cannot be coerced.
+    pub fn coerce(self, param_type: i32) -> Result<QueryValue, String> {
+        let type_error =
+            |value: &QueryValue, target: &str| format!("Cannot bind {:?} as {}", value, target);
+
+        match (param_type, self) {
+            (LIBSQL_PARAM_NULL, _) => Ok(QueryValue::Null),
+            (LIBSQL_PARAM_BOOL, value) => {
+                let truthy = match &value {
+                    QueryValue::Null => false,
+                    QueryValue::Integer(i) => *i != 0,
+                    QueryValue::Real(f) => *f != 0.0,
+                    QueryValue::Text(t) => !t.is_empty() && t != "0",
+                    QueryValue::Blob(b) => !b.is_empty() && b.as_slice() != b"0",
+                    QueryValue::List(items, _) => !items.is_empty(),
+                };
+                Ok(QueryValue::Integer(truthy as i64))
+            }
+            (_, QueryValue::Null) => Ok(QueryValue::Null),
+            (LIBSQL_PARAM_INT, QueryValue::Integer(i)) => Ok(QueryValue::Integer(i)),
+            (LIBSQL_PARAM_INT, QueryValue::Real(f)) => Ok(QueryValue::Integer(f as i64)),
+            (LIBSQL_PARAM_INT, QueryValue::Text(t)) => match t.trim().parse::<i64>() {
+                Ok(i) => Ok(QueryValue::Integer(i)),
+                Err(_) => Err(type_error(&QueryValue::Text(t), "PARAM_INT")),
+            },
+            (LIBSQL_PARAM_FLOAT, QueryValue::Real(f)) => Ok(QueryValue::Real(f)),
+            (LIBSQL_PARAM_FLOAT, QueryValue::Integer(i)) => Ok(QueryValue::Real(i as f64)),
+            (LIBSQL_PARAM_FLOAT, QueryValue::Text(t)) => match t.trim().parse::<f64>() {
+                Ok(f) => Ok(QueryValue::Real(f)),
+                Err(_) => Err(type_error(&QueryValue::Text(t), "PARAM_FLOAT")),
+            },
+            (LIBSQL_PARAM_STR, QueryValue::Blob(b)) => {
+                Ok(QueryValue::Text(String::from_utf8_lossy(&b).into_owned()))
+            }
+            (LIBSQL_PARAM_STR, value) => Ok(QueryValue::Text(value.to_string())),
+            (LIBSQL_PARAM_BLOB, QueryValue::Blob(b)) => Ok(QueryValue::Blob(b)),
+            (LIBSQL_PARAM_BLOB, value) => Ok(QueryValue::Blob(value.to_string().into_bytes())),
+            (LIBSQL_PARAM_INT | LIBSQL_PARAM_FLOAT, value) => Err(type_error(
+                &value,
+                if param_type == LIBSQL_PARAM_INT {
+                    "PARAM_INT"
+                } else {
+                    "PARAM_FLOAT"
+                },
+            )),
+            (param_type, _) => Err(format!("Unknown parameter type {}", param_type)),
+        }
+    }
+}
+
+/// Binds a `DateTimeInterface` as formatted text or as a unix timestamp.
+fn bind_datetime(
+    value: &Zval,
+    class_name: &str,
+    dates: &DateTimeBinding,
+) -> Result<QueryValue, String> {
+    let converted = match dates {
+        DateTimeBinding::Unix => value
+            .try_call_method("getTimestamp", vec![])
+            .ok()
+            .and_then(|ts| ts.long())
+            .map(QueryValue::Integer),
+        DateTimeBinding::Iso8601 => format_datetime(value, DATETIME_ISO8601),
+        DateTimeBinding::Format(format) => format_datetime(value, format),
+    };
+
+    converted.ok_or_else(|| format!("Failed to format {} for binding", class_name))
+}
+
+/// Formats a `DateTimeInterface` through its `format` method.
+fn format_datetime(value: &Zval, format: &str) -> Option<QueryValue> {
+    value
+        .try_call_method("format", vec![&format])
+        .ok()
+        .and_then(|s| s.string())
+        .map(QueryValue::Text)
+}
+
+/// Encodes an array or `JsonSerializable` object as JSON text through `json_encode`.
+fn encode_json(value: &Zval) -> Result<String, String> {
+    let json_encode = ZendCallable::try_from_name("json_encode").map_err(|e| e.to_string())?;
+
+    json_encode
+        .try_call(vec![value, &JSON_THROW_ON_ERROR])
+        .ok()
+        .and_then(|json| json.string())
+        .ok_or_else(|| "Failed to encode parameter as JSON".to_string())
+}
+
 /// Represents query parameters for database queries.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct QueryParameters {
     pub positional: Option<Vec<QueryValue>>,
     pub named: Option<HashMap<String, QueryValue>>,
+    /// Describes the first PHP value that could not be converted, reported by `to_params`.
+    pub error: Option<String>,
+    /// Positions of positional values that were skipped, e.g. by `[0 => 1, 2 => 3]`; they hold
+    /// `NULL` and are only accepted for parameter indexes no placeholder uses.
+    pub unbound: Vec<usize>,
 }
 
 /// Converts QueryParameters to libsql parameters.
 impl QueryParameters {
-    pub fn to_params(&self) -> libsql::params::Params {
+    pub fn to_params(&self) -> Result<libsql::params::Params, PhpException> {
+        if let Some(error) = &self.error {
+            return Err(PhpException::default(error.clone()));
+        }
+
         let positional_params = self
             .positional
             .as_ref()
+            .map(|params| params.iter().map(QueryValue::to_value).collect::<Vec<_>>())
+            .unwrap_or_else(Vec::new);
+
+        let param_named = self
+            .named
+            .as_ref()
             .map(|params| {
                 params
                     .iter()
-                    .map(|s| match &s {
-                        QueryValue::Integer(i) => libsql::Value::Integer(*i),
-                        QueryValue::Real(f) => libsql::Value::Real(*f),
-                        QueryValue::Null => libsql::Value::Null,
-                        QueryValue::Blob(b) => libsql::Value::Blob(b.to_vec()),
-                        QueryValue::Text(t) => libsql::Value::Text(t.to_string()),
-                    })
+                    .map(|(k, v)| (k.clone(), v.to_value()))
                     .collect::<Vec<_>>()
             })
-            .unwrap_or_else(Vec::new);
+            .filter(|params| !params.is_empty());
 
-        let param_named = self.named.as_ref().map(|params| {
-            params
-                .iter()
-                .map(|(k, v)| match &v {
-                    QueryValue::Integer(i) => (k.clone(), libsql::Value::Integer(*i)),
-                    QueryValue::Real(f) => (k.clone(), libsql::Value::Real(*f)),
-                    QueryValue::Null => (k.clone(), libsql::Value::Null),
-                    QueryValue::Blob(b) => (k.clone(), libsql::Value::Blob(b.to_vec())),
-                    QueryValue::Text(t) => (k.clone(), libsql::Value::Text(t.to_string())),
-                })
-                .collect::<Vec<_>>()
-        });
+        match (positional_params.is_empty(), param_named) {
+            (false, None) => Ok(libsql::params::Params::Positional(positional_params)),
+            (true, Some(named)) => Ok(libsql::params::Params::Named(named)),
+            (true, None) => Ok(libsql::params::Params::None),
+            (false, Some(named)) => Err(PhpException::default(format!(
+                "Cannot mix positional and named parameters: got both index 0 and key '{}'",
+                named.first().map(|(k, _)| k.as_str()).unwrap_or_default()
+            ))),
+        }
+    }
+
+    /// Validates the parameters against the parameters a statement expects and converts them
+    /// to libsql parameters.
+    ///
+    /// Named parameters may be given with or without their prefix; they are bound under the
+    /// name used in the statement. Positional values bind to `?` and `?NNN` by parameter index
+    /// and to `$N`, `@N` and `:N` by their number; indexes that no placeholder uses bind `NULL`.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The parameters expected by the prepared statement.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the libsql parameters, or a `PhpException` naming the first missing,
+    /// unknown or mixed parameter.
+    pub fn to_params_for(
+        &self,
+        spec: &ParameterSpec,
+    ) -> Result<libsql::params::Params, PhpException> {
+        let params = self.to_params()?;
+
+        match params {
+            libsql::params::Params::None => match spec.first_required() {
+                None => Ok(libsql::params::Params::None),
+                Some((_, Some(name))) => Err(PhpException::default(format!(
+                    "Missing value for named parameter '{}'",
+                    name
+                ))),
+                Some((position, None)) => Err(PhpException::default(format!(
+                    "Missing value for parameter {}: statement expects {} parameter(s), got none",
+                    position + 1,
+                    spec.count()
+                ))),
+            },
+            libsql::params::Params::Positional(values) => {
+                let given = values.len() - self.unbound.len();
+
+                if let Some(missing) = spec
+                    .sources()
+                    .flatten()
+                    .filter(|source| *source >= values.len() || self.unbound.contains(source))
+                    .min()
+                {
+                    return Err(PhpException::default(format!(
+                        "Missing value for parameter {}: statement expects {} parameter(s), got {}",
+                        missing + 1,
+                        spec.count(),
+                        given
+                    )));
+                }
+
+                if values.len() > spec.count() {
+                    return Err(PhpException::default(format!(
+                        "Unexpected parameter {}: statement expects {} parameter(s), got {}",
+                        spec.count() + 1,
+                        spec.count(),
+                        given
+                    )));
+                }
+
+                Ok(libsql::params::Params::Positional(
+                    spec.sources()
+                        .map(|source| source.map_or(libsql::Value::Null, |s| values[s].clone()))
+                        .collect(),
+                ))
+            }
+            libsql::params::Params::Named(values) => {
+                if spec.uses_positional() {
+                    return Err(PhpException::default(format!(
+                        "Cannot bind named parameter '{}': statement uses positional parameters",
+                        values.first().map(|(k, _)| k.as_str()).unwrap_or_default()
+                    )));
+                }
+
+                let mut values: HashMap<String, libsql::Value> = values
+                    .into_iter()
+                    .map(|(key, value)| (strip_prefix(&key).to_string(), value))
+                    .collect();
+
+                let mut bound = Vec::with_capacity(spec.names.len());
+                for name in spec.names.iter().flatten() {
+                    let value = values.remove(strip_prefix(name)).ok_or_else(|| {
+                        PhpException::default(format!(
+                            "Missing value for named parameter '{}'",
+                            name
+                        ))
+                    })?;
+                    bound.push((name.clone(), value));
+                }
+
+                if let Some(key) = values.keys().next() {
+                    return Err(PhpException::default(format!(
+                        "Unknown named parameter '{}'",
+                        key
+                    )));
+                }
+
+                Ok(libsql::params::Params::Named(bound))
+            }
+        }
+    }
+
+    /// Expands list values bound to the only placeholder of an `IN (...)` list.
+    ///
+    /// `WHERE id IN (:ids)` with `[':ids' => [1, 2, 3]]` becomes `WHERE id IN (?, ?, ?)` with
+    /// three positional parameters; an empty list becomes `IN ()`, which matches no rows. When
+    /// any list is expanded, every placeholder of the statement is rewritten to `?`, so the
+    /// returned parameters are positional.
+    ///
+    /// The SQL and parameters are returned unchanged when there is nothing to expand, or when
+    /// they do not match up; validating them against the statement then reports the problem.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The SQL the parameters are bound to.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the SQL and parameters to run, or a `PhpException` if the expanded
+    /// statement would exceed SQLite's limit of host parameters.
+    pub fn expand_lists(self, sql: &str) -> Result<(String, QueryParameters), PhpException> {
+        match self.expanded(sql)? {
+            Some((rewritten, values)) => Ok((
+                rewritten,
+                QueryParameters {
+                    positional: Some(values),
+                    ..Default::default()
+                },
+            )),
+            None => Ok((sql.to_string(), self)),
+        }
+    }
+
+    /// Returns the rewritten SQL and its positional values, or `None` if nothing is expanded.
+    fn expanded(&self, sql: &str) -> Result<Option<(String, Vec<QueryValue>)>, PhpException> {
+        let positional = self.positional.as_deref().unwrap_or_default();
+        let named = self
+            .named
+            .as_ref()
+            .map(|named| {
+                named
+                    .iter()
+                    .map(|(key, value)| (strip_prefix(key), value))
+                    .collect::<HashMap<_, _>>()
+            })
+            .unwrap_or_default();
+
+        let has_list = positional
+            .iter()
+            .chain(named.values().copied())
+            .any(|value| matches!(value, QueryValue::List(..)));
+
+        if !has_list
+            || self.error.is_some()
+            || !self.unbound.is_empty()
+            || (!positional.is_empty() && !named.is_empty())
+        {
+            return Ok(None);
+        }
 
-        match (
-            positional_params.is_empty(),
-            param_named.as_ref().map(|p| p.is_empty()).unwrap_or(true),
-        ) {
-            (false, true) => libsql::params::Params::Positional(positional_params),
-            (true, false) => libsql::params::Params::Named(param_named.unwrap()),
-            _ => libsql::params::Params::None,
+        let placeholders = sql_lexer::placeholders(sql);
+        let indexes = sql_lexer::parameter_indexes(&placeholders);
+        let mut rewritten = String::with_capacity(sql.len());
+        let mut values = Vec::new();
+        let mut used_names = Vec::new();
+        let mut max_position = 0;
+        let mut last = 0;
+        let mut expanded = false;
+
+        for (placeholder, index) in placeholders.iter().zip(indexes) {
+            let value = match &placeholder.kind {
+                PlaceholderKind::Named(name) if !named.is_empty() => {
+                    used_names.push(strip_prefix(name));
+                    named.get(strip_prefix(name)).copied()
+                }
+                _ if !named.is_empty() => None,
+                kind => {
+                    // Positional values bind as `ParameterSpec` maps them.
+                    let position = match kind {
+                        PlaceholderKind::Named(name) => numbered_name(name).unwrap_or(index),
+                        _ => index,
+                    };
+                    max_position = max_position.max(position);
+                    position.checked_sub(1).and_then(|p| positional.get(p))
+                }
+            };
+
+            let Some(value) = value else {
+                return Ok(None);
+            };
+
+            rewritten.push_str(&sql[last..placeholder.start]);
+            match value {
+                QueryValue::List(items, _) if placeholder.in_list => {
+                    rewritten.push_str(&vec!["?"; items.len()].join(", "));
+                    values.extend(items.iter().cloned());
+                    expanded = true;
+                }
+                value => {
+                    rewritten.push('?');
+                    values.push(value.clone());
+                }
+            }
+            last = placeholder.end;
+        }
+        rewritten.push_str(&sql[last..]);
+
+        let all_used = if named.is_empty() {
+            max_position == positional.len()
+        } else {
+            named.keys().all(|key| used_names.contains(key))
+        };
+
+        if !expanded || !all_used {
+            return Ok(None);
+        }
+
+        if values.len() > MAX_VARIABLES {
+            return Err(PhpException::default(format!(
+                "List parameters expand to {} values, more than the limit of {} per statement",
+                values.len(),
+                MAX_VARIABLES
+            )));
         }
+
+        Ok(Some((rewritten, values)))
+    }
+
+    /// Returns `true` if no parameters were given.
+    pub fn is_empty(&self) -> bool {
+        self.error.is_none()
+            && self.positional.as_ref().map_or(true, Vec::is_empty)
+            && self.named.as_ref().map_or(true, HashMap::is_empty)
     }
 
     pub fn get_named(&self) -> Option<&HashMap<String, QueryValue>> {
@@ -93,83 +618,374 @@ impl QueryParameters {
     }
 }
 
-impl<'a> FromZval<'a> for QueryParameters {
-    const TYPE: DataType = DataType::Mixed;
-
-    fn from_zval(zval: &'a Zval) -> Option<Self> {
-        if let Some(array) = zval.array() {
-            let mut positional = Vec::new();
-            let mut named = HashMap::new();
-
-            for (key, value) in array.iter() {
-                match key {
-                    ArrayKey::Long(index) => {
-                        let query_value = if let Some(int_val) = value.long() {
-                            QueryValue::Integer(int_val)
-                        } else if let Some(float_val) = value.double() {
-                            QueryValue::Real(float_val)
-                        } else if let Some(text_val) = value.string() {
-                            QueryValue::Text(text_val.to_string())
-                        } else if let Some(text_val) = value.string() {
-                            QueryValue::Blob(text_val.as_bytes().to_vec())
-                        } else if value.is_null() {
-                            QueryValue::Null
-                        } else {
-                            continue;
-                        };
-
-                        if index >= positional.len() as i64 {
-                            positional.resize((index + 1) as usize, QueryValue::Null);
-                        }
-                        positional[index as usize] = query_value;
-                    }
-                    ArrayKey::String(key) => {
-                        let query_value = if let Some(int_val) = value.long() {
-                            QueryValue::Integer(int_val)
-                        } else if let Some(float_val) = value.double() {
-                            QueryValue::Real(float_val)
-                        } else if let Some(text_val) = value.string() {
-                            QueryValue::Text(text_val.to_string())
-                        } else if let Some(text_val) = value.string() {
-                            QueryValue::Blob(text_val.as_bytes().to_vec())
-                        } else if value.is_null() {
-                            QueryValue::Null
-                        } else {
-                            continue;
-                        };
-
-                        named.insert(key.to_string(), query_value);
-                    }
-                    ArrayKey::Str(key) => {
-                        let query_value = if let Some(int_val) = value.long() {
-                            QueryValue::Integer(int_val)
-                        } else if let Some(float_val) = value.double() {
-                            QueryValue::Real(float_val)
-                        } else if let Some(text_val) = value.string() {
-                            QueryValue::Text(text_val.to_string())
-                        } else if let Some(text_val) = value.string() {
-                            QueryValue::Blob(text_val.as_bytes().to_vec())
-                        } else if value.is_null() {
-                            QueryValue::Null
-                        } else {
-                            continue;
-                        };
-
-                        named.insert(key.to_string(), query_value);
-                    }
+/// A value bound to a statement parameter.
+#[derive(Debug)]
+pub enum Binding {
+    /// A value converted when it was bound, through `bindValue`.
+    Value(QueryValue),
+    /// A PHP reference read each time the statement runs, through `bindParam`.
+    Param(Zval, Option<i32>),
+}
+
+impl Binding {
+    /// Creates a binding from a PHP value, optionally coerced to a `LibSQL::PARAM_*` type.
+    pub fn value(
+        value: &Zval,
+        param_type: Option<i32>,
+        dates: &DateTimeBinding,
+    ) -> Result<Binding, PhpException> {
+        QueryValue::from_php_as(value, param_type, dates)
+            .map(Binding::Value)
+            .map_err(PhpException::default)
+    }
+
+    /// Creates a binding to a PHP variable passed by reference, read each time the statement runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `reference` - The PHP reference the variable was passed as.
+    /// * `param_type` - The optional `LibSQL::PARAM_*` type to coerce the variable to.
+    pub fn reference(reference: Zval, param_type: Option<i32>) -> Result<Binding, PhpException> {
+        if !reference.is_reference() {
+            return Err(PhpException::from(
+                "The variable must be passed by reference",
+            ));
+        }
+
+        Ok(Binding::Param(reference, param_type))
+    }
+
+    /// Resolves the bound value, reading the current value of bound references.
+    fn resolve(&self, dates: &DateTimeBinding) -> Result<QueryValue, String> {
+        match self {
+            Binding::Value(value) => Ok(value.clone()),
+            Binding::Param(reference, param_type) => {
+                QueryValue::from_php_as(reference.dereference(), *param_type, dates)
+            }
+        }
+    }
+}
+
+/// The values bound to a prepared statement, keyed by 1-based index or by name without prefix.
+#[derive(Debug, Default)]
+pub struct Bindings {
+    pub positional: BTreeMap<usize, Binding>,
+    pub named: HashMap<String, Binding>,
+}
+
+impl Bindings {
+    /// Binds a value to a parameter.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The 1-based parameter index, or the parameter name with or without its prefix.
+    /// * `binding` - The value or reference to bind.
+    pub fn bind(&mut self, key: &Zval, binding: Binding) -> Result<(), PhpException> {
+        if let Some(index) = key.long() {
+            if index < 1 {
+                return Err(PhpException::default(format!(
+                    "Invalid parameter index {}: positional parameters start at 1",
+                    index
+                )));
+            }
+            self.positional.insert(index as usize, binding);
+        } else if let Some(name) = key.string() {
+            self.bind_name(&name, binding);
+        } else {
+            return Err(PhpException::default(
+                "Parameter must be identified by a 1-based index or a name".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Binds a value to a named parameter, given with or without its prefix.
+    pub fn bind_name(&mut self, name: &str, binding: Binding) {
+        self.named.insert(strip_prefix(name).to_string(), binding);
+    }
+
+    /// Removes all bindings.
+    pub fn clear(&mut self) {
+        self.positional.clear();
+        self.named.clear();
+    }
+
+    /// Resolves the bindings to query parameters, keeping positional bindings in index order.
+    ///
+    /// # Arguments
+    ///
+    /// * `dates` - How the connection binds `DateTimeInterface` objects held by references.
+    pub fn to_query_parameters(&self, dates: &DateTimeBinding) -> QueryParameters {
+        let mut error = None;
+        let mut unbound = Vec::new();
+        let mut resolve = |key: String, binding: &Binding| {
+            binding.resolve(dates).unwrap_or_else(|e| {
+                error.get_or_insert_with(|| format!("Parameter {}: {}", key, e));
+                QueryValue::Null
+            })
+        };
+
+        let max_index = self.positional.keys().next_back().copied().unwrap_or(0);
+        let positional: Vec<QueryValue> = (1..=max_index)
+            .map(|index| match self.positional.get(&index) {
+                Some(binding) => resolve(index.to_string(), binding),
+                None => {
+                    unbound.push(index - 1);
+                    QueryValue::Null
                 }
+            })
+            .collect();
+        let named: HashMap<String, QueryValue> = self
+            .named
+            .iter()
+            .map(|(name, binding)| (name.clone(), resolve(name.clone(), binding)))
+            .collect();
+
+        QueryParameters {
+            positional: if positional.is_empty() {
+                None
+            } else {
+                Some(positional)
+            },
+            named: if named.is_empty() { None } else { Some(named) },
+            error,
+            unbound,
+        }
+    }
+}
+
+/// Describes the parameters a prepared statement expects.
+#[derive(Debug, Clone, Default)]
+pub struct ParameterSpec {
+    /// The name of each parameter in index order, or `None` for positional parameters.
+    pub names: Vec<Option<String>>,
+    /// Whether a placeholder uses each index; `?NNN` can leave indexes unused.
+    used: Vec<bool>,
+}
+
+impl ParameterSpec {
+    /// Reads the expected parameters of a prepared statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `stmt` - The prepared statement.
+    /// * `sql` - The SQL it was prepared from, to find the indexes its placeholders use.
+    pub fn from_statement(stmt: &libsql::Statement, sql: &str) -> Self {
+        // Remote statements only learn their parameters once values are bound, so read
+        // them from the SQL instead.
+        if stmt.parameter_count() == 0 {
+            return Self::from_sql(sql);
+        }
+
+        let names: Vec<Option<String>> = (1..=stmt.parameter_count())
+            .map(|idx| {
+                stmt.parameter_name(idx as i32)
+                    .filter(|name| !name.starts_with('?'))
+                    .map(str::to_string)
+            })
+            .collect();
+
+        let mut used = vec![false; names.len()];
+        for index in sql_lexer::parameter_indexes(&sql_lexer::placeholders(sql)) {
+            if let Some(slot) = index.checked_sub(1).and_then(|idx| used.get_mut(idx)) {
+                *slot = true;
             }
+        }
 
-            Some(QueryParameters {
-                positional: if positional.is_empty() {
-                    None
-                } else {
-                    Some(positional)
-                },
-                named: if named.is_empty() { None } else { Some(named) },
+        // Fall back to requiring every index if the SQL could not be followed.
+        if !used.contains(&true) {
+            used.fill(true);
+        }
+
+        Self { names, used }
+    }
+
+    /// Reads the expected parameters from the placeholders of an SQL statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The SQL text of a single statement.
+    pub fn from_sql(sql: &str) -> Self {
+        let placeholders = sql_lexer::placeholders(sql);
+        let indexes = sql_lexer::parameter_indexes(&placeholders);
+        let count = indexes.iter().copied().max().unwrap_or(0);
+
+        let mut names = vec![None; count];
+        let mut used = vec![false; count];
+        for (placeholder, index) in placeholders.iter().zip(indexes) {
+            let Some(idx) = index.checked_sub(1) else {
+                continue;
+            };
+            used[idx] = true;
+            if let sql_lexer::PlaceholderKind::Named(name) = &placeholder.kind {
+                names[idx] = Some(name.clone());
+            }
+        }
+
+        Self { names, used }
+    }
+
+    /// Returns the number of positional values the statement expects.
+    pub fn count(&self) -> usize {
+        self.sources()
+            .flatten()
+            .map(|source| source + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns, for every parameter index, the position of the positional value bound to it,
+    /// or `None` if no placeholder uses the index.
+    ///
+    /// `$N`, `@N` and `:N` take the N-th value wherever they appear, since SQLite numbers named
+    /// parameters in order of appearance; every other parameter takes the value at its index.
+    fn sources(&self) -> impl Iterator<Item = Option<usize>> + '_ {
+        self.names
+            .iter()
+            .zip(&self.used)
+            .enumerate()
+            .map(|(idx, (name, used))| {
+                used.then(|| {
+                    name.as_deref()
+                        .and_then(numbered_name)
+                        .map_or(idx, |number| number - 1)
+                })
             })
-        } else {
-            None
+    }
+
+    /// Returns the position of the first expected value, with its name unless it is positional
+    /// or numbered.
+    fn first_required(&self) -> Option<(usize, Option<&String>)> {
+        self.sources()
+            .zip(&self.names)
+            .filter_map(|(source, name)| {
+                source.map(|source| {
+                    let name = name.as_ref().filter(|name| numbered_name(name).is_none());
+                    (source, name)
+                })
+            })
+            .min_by_key(|(source, _)| *source)
+    }
+
+    /// Returns `true` if a placeholder of the statement is `?` or `?NNN`.
+    fn uses_positional(&self) -> bool {
+        self.names
+            .iter()
+            .zip(&self.used)
+            .any(|(name, used)| *used && name.is_none())
+    }
+}
+
+/// Returns N for the parameter names `$N`, `@N` and `:N`.
+fn numbered_name(name: &str) -> Option<usize> {
+    name.strip_prefix(['$', '@', ':'])
+        .and_then(|number| number.parse().ok())
+        .filter(|number| *number > 0)
+}
+
+/// Strips the `:`, `@`, `$` or `?` prefix from a parameter name.
+fn strip_prefix(name: &str) -> &str {
+    name.trim_start_matches(|c| c == '@' || c == '$' || c == '?' || c == ':')
+}
+
+impl QueryParameters {
+    /// Converts the parameters passed to a PHP method, binding dates as the connection does.
+    ///
+    /// # Arguments
+    ///
+    /// * `parameters` - The array of parameters, if any was passed.
+    /// * `conn_id` - The ID of the connection the parameters are bound on.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the parameters, `None` if none were passed, or a `PhpException` if
+    /// they are not an array.
+    pub fn from_argument(
+        parameters: Option<&Zval>,
+        conn_id: &str,
+    ) -> Result<Option<Self>, PhpException> {
+        match parameters {
+            Some(parameters) if !parameters.is_null() => {
+                QueryParameters::from_php(parameters, &DateTimeBinding::of_connection(conn_id))
+                    .map(Some)
+                    .ok_or_else(|| PhpException::default("Parameters must be an array".into()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Converts a PHP array of parameters, keyed by position or by name.
+    ///
+    /// Values that cannot be bound are recorded in `error` and reported by `to_params`.
+    ///
+    /// # Arguments
+    ///
+    /// * `zval` - The PHP array.
+    /// * `dates` - How the connection binds `DateTimeInterface` objects.
+    ///
+    /// # Returns
+    ///
+    /// The parameters, or `None` if the value is not an array.
+    pub fn from_php(zval: &Zval, dates: &DateTimeBinding) -> Option<Self> {
+        let array = zval.array()?;
+        let mut positional = Vec::new();
+        let mut named = HashMap::new();
+        let mut error = None;
+
+        for (key, value) in array.iter() {
+            let query_value = match QueryValue::from_php(value, dates) {
+                Ok(query_value) => query_value,
+                Err(e) => {
+                    error.get_or_insert_with(|| format!("Parameter {}: {}", key, e));
+                    QueryValue::Null
+                }
+            };
+
+            match key {
+                ArrayKey::Long(index) => {
+                    // An index no statement can have is rejected before the values are sized by it
+                    if index < 0 || index >= MAX_VARIABLES as i64 {
+                        error.get_or_insert_with(|| {
+                            format!("Invalid positional parameter index {}", index)
+                        });
+                        continue;
+                    }
+
+                    if index >= positional.len() as i64 {
+                        positional.resize((index + 1) as usize, None);
+                    }
+                    positional[index as usize] = Some(query_value);
+                }
+                ArrayKey::String(key) => {
+                    named.insert(key.to_string(), query_value);
+                }
+                ArrayKey::Str(key) => {
+                    named.insert(key.to_string(), query_value);
+                }
+            }
         }
+
+        let unbound = positional
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| value.is_none())
+            .map(|(position, _)| position)
+            .collect();
+        let positional: Vec<QueryValue> = positional
+            .into_iter()
+            .map(|value| value.unwrap_or(QueryValue::Null))
+            .collect();
+
+        Some(QueryParameters {
+            positional: if positional.is_empty() {
+                None
+            } else {
+                Some(positional)
+            },
+            named: if named.is_empty() { None } else { Some(named) },
+            error,
+            unbound,
+        })
     }
 }
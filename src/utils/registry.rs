@@ -0,0 +1,125 @@
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, RandomState},
+    sync::Mutex,
+};
+
+use crate::utils::guard::LockExt;
+
+/// The number of shards of a registry, a power of two.
+const SHARDS: usize = 16;
+
+/// A concurrent map of handles, keyed by their ID.
+///
+/// Handles are spread over shards by ID, so PHP threads working on different handles rarely
+/// wait on one another. A shard is only locked for a single map operation: handles are
+/// cloned or taken out of the registry before anything is awaited on them, so no registry
+/// lock is ever held across `block_on`.
+pub struct Registry<T> {
+    hasher: RandomState,
+    shards: Box<[Mutex<HashMap<String, T>>]>,
+}
+
+impl<T> Registry<T> {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self {
+            hasher: RandomState::new(),
+            shards: (0..SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+        }
+    }
+
+    fn shard(&self, id: &str) -> &Mutex<HashMap<String, T>> {
+        let hash = self.hasher.hash_one(id) as usize;
+        &self.shards[hash & (SHARDS - 1)]
+    }
+
+    /// Registers a handle, returning the one previously registered with the same ID.
+    pub fn insert(&self, id: String, value: T) -> Option<T> {
+        self.shard(&id).lock_or_recover().insert(id, value)
+    }
+
+    /// Takes a handle out of the registry.
+    pub fn remove(&self, id: &str) -> Option<T> {
+        self.shard(id).lock_or_recover().remove(id)
+    }
+
+    /// Takes a handle out of the registry, running `f` on it before the shard is unlocked.
+    ///
+    /// Lets a caller publish that it took the handle before anyone else can observe it missing.
+    pub fn remove_with(&self, id: &str, f: impl FnOnce(&T)) -> Option<T> {
+        let mut shard = self.shard(id).lock_or_recover();
+        let handle = shard.remove(id);
+        if let Some(handle) = &handle {
+            f(handle);
+        }
+        handle
+    }
+
+    /// Returns `true` if a handle is registered with this ID.
+    pub fn contains(&self, id: &str) -> bool {
+        self.shard(id).lock_or_recover().contains_key(id)
+    }
+
+    /// Runs `f` on a registered handle while its shard is locked.
+    ///
+    /// `f` must not block, since every handle of the shard waits for it.
+    ///
+    /// # Returns
+    ///
+    /// The result of `f`, or `None` if no handle is registered with this ID.
+    pub fn with<R>(&self, id: &str, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        self.shard(id).lock_or_recover().get_mut(id).map(f)
+    }
+
+    /// Returns the first result of `f` that is not `None`, over every registered handle.
+    pub fn find_map<R>(&self, mut f: impl FnMut(&str, &T) -> Option<R>) -> Option<R> {
+        self.shards.iter().find_map(|shard| {
+            shard
+                .lock_or_recover()
+                .iter()
+                .find_map(|(id, value)| f(id, value))
+        })
+    }
+
+    /// Returns the number of registered handles.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock_or_recover().len())
+            .sum()
+    }
+
+    /// Returns `true` if no handle is registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every handle.
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            shard.lock_or_recover().clear();
+        }
+    }
+
+    /// Takes every handle out of the registry.
+    pub fn drain(&self) -> Vec<(String, T)> {
+        self.shards
+            .iter()
+            .flat_map(|shard| shard.lock_or_recover().drain().collect::<Vec<_>>())
+            .collect()
+    }
+}
+
+impl<T: Clone> Registry<T> {
+    /// Returns a copy of a registered handle, to use once the registry is unlocked.
+    pub fn get(&self, id: &str) -> Option<T> {
+        self.shard(id).lock_or_recover().get(id).cloned()
+    }
+}
+
+impl<T> Default for Registry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -5,8 +5,16 @@ use ext_php_rs::{
     exception::PhpException,
     types::{ZendHashTable, Zval},
 };
-use once_cell::sync::OnceCell;
-use tokio::runtime::Runtime;
+use std::sync::{
+    atomic::{AtomicPtr, AtomicU32, Ordering},
+    Mutex,
+};
+use tokio::runtime::{Builder, Runtime};
+
+use crate::{
+    errors::{ErrorKind, LibSQLError},
+    utils::guard::LockExt,
+};
 
 pub fn cwd() -> Result<String, PhpException> {
     match std::env::current_dir() {
@@ -106,15 +114,96 @@ pub fn convert_vec_hashmap_to_php_array(
     ext_php_rs::convert::IntoZval::into_zval(ext_php_rs::boxed::ZBox::from(outer_array), false)
 }
 
-/// Retrieves the global runtime instance.
+/// The Tokio runtime flavor and size, configurable until the runtime is first used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuntimeConfig {
+    /// A multi-threaded runtime, with the given number of worker threads or one per CPU core.
+    MultiThread(Option<usize>),
+    /// A runtime driven by the PHP thread itself, without worker threads.
+    CurrentThread,
+}
+
+static RUNTIME_CONFIG: Mutex<RuntimeConfig> = Mutex::new(RuntimeConfig::MultiThread(None));
+
+/// The runtime of the current process and the pid it was built in.
+static RUNTIME: AtomicPtr<Runtime> = AtomicPtr::new(std::ptr::null_mut());
+static RUNTIME_PID: AtomicU32 = AtomicU32::new(0);
+
+/// Serializes building the runtime.
+static RUNTIME_INIT: Mutex<()> = Mutex::new(());
+
+/// Configures the runtime built on first use.
+///
+/// # Arguments
+///
+/// * `config` - The flavor and size of the runtime.
+///
+/// # Errors
+///
+/// Returns a `LibSQLError` if the runtime of this process is already running with a different configuration.
+pub fn configure_runtime(config: RuntimeConfig) -> Result<(), LibSQLError> {
+    let _init = RUNTIME_INIT.lock_or_recover();
+    let mut current = RUNTIME_CONFIG.lock_or_recover();
+
+    if *current != config && RUNTIME_PID.load(Ordering::Acquire) == std::process::id() {
+        return Err(LibSQLError::new(
+            ErrorKind::Generic,
+            "The runtime is already running, configure it before opening a connection",
+        ));
+    }
+
+    *current = config;
+    Ok(())
+}
+
+/// Retrieves the runtime of the current process.
+///
+/// The runtime is rebuilt after a fork, since the worker threads of the runtime inherited
+/// from the parent do not exist in the child.
 ///
 /// # Returns
 ///
-/// A reference to the global runtime instance.
+/// A reference to the runtime instance.
 pub fn runtime() -> &'static Runtime {
-    static RUNTIME: OnceCell<Runtime> = OnceCell::new();
+    let pid = std::process::id();
+
+    // The pid is stored after the runtime, so a matching pid guarantees a current runtime
+    if RUNTIME_PID.load(Ordering::Acquire) == pid {
+        return unsafe { &*RUNTIME.load(Ordering::Acquire) };
+    }
 
-    RUNTIME.get_or_try_init(Runtime::new).unwrap()
+    let _init = RUNTIME_INIT.lock_or_recover();
+    if RUNTIME_PID.load(Ordering::Acquire) == pid {
+        return unsafe { &*RUNTIME.load(Ordering::Acquire) };
+    }
+
+    // The inherited runtime is leaked rather than dropped, since dropping it would wait for
+    // worker threads that do not exist in this process.
+    let runtime: &'static Runtime = Box::leak(Box::new(build_runtime()));
+    RUNTIME.store(runtime as *const Runtime as *mut Runtime, Ordering::Release);
+    RUNTIME_PID.store(pid, Ordering::Release);
+
+    runtime
+}
+
+fn build_runtime() -> Runtime {
+    let config = *RUNTIME_CONFIG.lock_or_recover();
+
+    let mut builder = match config {
+        RuntimeConfig::MultiThread(worker_threads) => {
+            let mut builder = Builder::new_multi_thread();
+            if let Some(worker_threads) = worker_threads {
+                builder.worker_threads(worker_threads);
+            }
+            builder
+        }
+        RuntimeConfig::CurrentThread => Builder::new_current_thread(),
+    };
+
+    builder
+        .enable_all()
+        .build()
+        .expect("Failed to build the Tokio runtime")
 }
 
 /// Determines the mode based on the provided URL, authentication token, and sync URL.
@@ -0,0 +1,226 @@
+/// The kind of a parameter placeholder in an SQL statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaceholderKind {
+    /// `?`, numbered one above the highest index used before it.
+    Anonymous,
+    /// `?NNN`.
+    Numbered(usize),
+    /// `:name`, `@name` or `$name`, including the prefix.
+    Named(String),
+}
+
+/// A parameter placeholder found in an SQL statement.
+#[derive(Debug, Clone)]
+pub struct Placeholder {
+    /// Byte offset of the placeholder in the SQL text.
+    pub start: usize,
+    /// Byte offset just past the placeholder.
+    pub end: usize,
+    pub kind: PlaceholderKind,
+    /// Whether the placeholder is the only element of an `IN (...)` list.
+    pub in_list: bool,
+}
+
+/// A significant token, used to look at the neighbours of a placeholder.
+#[derive(Debug)]
+enum Token {
+    Word(String),
+    Punct(char),
+    Placeholder(usize),
+}
+
+/// Finds the parameter placeholders of an SQL statement.
+///
+/// String literals, quoted identifiers and comments are skipped, so placeholder-like text
+/// inside them is not reported.
+///
+/// # Arguments
+///
+/// * `sql` - The SQL text, which may contain several statements.
+///
+/// # Returns
+///
+/// The placeholders in the order they appear.
+pub fn placeholders(sql: &str) -> Vec<Placeholder> {
+    let (tokens, mut placeholders) = tokenize(sql);
+
+    for (idx, token) in tokens.iter().enumerate() {
+        let Token::Placeholder(placeholder) = token else {
+            continue;
+        };
+
+        let in_list = idx >= 2
+            && matches!(&tokens[idx - 2], Token::Word(word) if word.eq_ignore_ascii_case("IN"))
+            && matches!(tokens[idx - 1], Token::Punct('('))
+            && matches!(tokens.get(idx + 1), Some(Token::Punct(')')));
+
+        placeholders[*placeholder].in_list = in_list;
+    }
+
+    placeholders
+}
+
+/// Finds the first keyword of each statement in an SQL text.
+///
+/// Comments, string literals and quoted identifiers are skipped, so a leading comment or a `;`
+/// inside a literal does not hide a keyword.
+///
+/// # Arguments
+///
+/// * `sql` - The SQL text, which may contain several statements.
+///
+/// # Returns
+///
+/// The keywords in upper case, in the order their statements appear.
+pub fn leading_keywords(sql: &str) -> Vec<String> {
+    let (tokens, _) = tokenize(sql);
+    let mut keywords = Vec::new();
+    let mut at_start = true;
+
+    for token in tokens {
+        match token {
+            Token::Punct(';') => at_start = true,
+            Token::Word(word) if at_start => {
+                keywords.push(word.to_ascii_uppercase());
+                at_start = false;
+            }
+            _ => at_start = false,
+        }
+    }
+
+    keywords
+}
+
+/// Splits an SQL text into significant tokens, collecting its placeholders along the way.
+fn tokenize(sql: &str) -> (Vec<Token>, Vec<Placeholder>) {
+    let bytes = sql.as_bytes();
+    let mut placeholders = Vec::new();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        match c {
+            b'\'' | b'"' | b'`' => pos = skip_quoted(bytes, pos, c),
+            b'[' => pos = skip_quoted(bytes, pos, b']'),
+            b'-' if bytes.get(pos + 1) == Some(&b'-') => {
+                pos = sql[pos..]
+                    .find('\n')
+                    .map_or(bytes.len(), |end| pos + end + 1);
+            }
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                pos = sql[pos + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| pos + 2 + end + 2);
+            }
+            b'?' => {
+                let end = pos + 1 + count_while(&bytes[pos + 1..], |b| b.is_ascii_digit());
+                let kind = match sql[pos + 1..end].parse() {
+                    Ok(index) => PlaceholderKind::Numbered(index),
+                    Err(_) => PlaceholderKind::Anonymous,
+                };
+                tokens.push(Token::Placeholder(placeholders.len()));
+                placeholders.push(Placeholder {
+                    start: pos,
+                    end,
+                    kind,
+                    in_list: false,
+                });
+                pos = end;
+            }
+            b':' | b'@' | b'$' if bytes.get(pos + 1).is_some_and(|b| is_word_byte(*b)) => {
+                let end = pos + 1 + count_while(&bytes[pos + 1..], is_word_byte);
+                tokens.push(Token::Placeholder(placeholders.len()));
+                placeholders.push(Placeholder {
+                    start: pos,
+                    end,
+                    kind: PlaceholderKind::Named(sql[pos..end].to_string()),
+                    in_list: false,
+                });
+                pos = end;
+            }
+            _ if is_word_byte(c) => {
+                let end = pos + count_while(&bytes[pos..], is_word_byte);
+                tokens.push(Token::Word(sql[pos..end].to_string()));
+                pos = end;
+            }
+            _ if c.is_ascii_whitespace() => pos += 1,
+            _ => {
+                tokens.push(Token::Punct(c as char));
+                pos += 1;
+            }
+        }
+    }
+
+    (tokens, placeholders)
+}
+
+/// Returns the offset just past a quoted literal or identifier starting at `start`.
+///
+/// A doubled closing quote is an escaped quote; an unterminated literal runs to the end.
+fn skip_quoted(bytes: &[u8], start: usize, close: u8) -> usize {
+    let mut pos = start + 1;
+
+    while pos < bytes.len() {
+        if bytes[pos] == close {
+            if close != b']' && bytes.get(pos + 1) == Some(&close) {
+                pos += 2;
+                continue;
+            }
+            return pos + 1;
+        }
+        pos += 1;
+    }
+
+    bytes.len()
+}
+
+fn count_while(bytes: &[u8], predicate: impl Fn(u8) -> bool) -> usize {
+    bytes.iter().take_while(|b| predicate(**b)).count()
+}
+
+/// Returns `true` for bytes that may appear in identifiers, including UTF-8 continuation bytes.
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80
+}
+
+/// Assigns each placeholder the parameter index SQLite gives it.
+///
+/// `?NNN` uses index `NNN`; `?` and the first occurrence of each name use one above the highest
+/// index so far, and repeated names reuse their first index.
+///
+/// # Arguments
+///
+/// * `placeholders` - The placeholders of a statement, in order.
+///
+/// # Returns
+///
+/// The 1-based index of every placeholder, in the same order.
+pub fn parameter_indexes(placeholders: &[Placeholder]) -> Vec<usize> {
+    let mut names: Vec<(&str, usize)> = Vec::new();
+    let mut max_index = 0;
+
+    placeholders
+        .iter()
+        .map(|placeholder| {
+            let index = match &placeholder.kind {
+                PlaceholderKind::Anonymous => max_index + 1,
+                PlaceholderKind::Numbered(index) => *index,
+                PlaceholderKind::Named(name) => match names.iter().find(|(n, _)| n == name) {
+                    Some((_, index)) => *index,
+                    None => {
+                        names.push((name, max_index + 1));
+                        max_index + 1
+                    }
+                },
+            };
+            max_index = max_index.max(index);
+            index
+        })
+        .collect()
+}
+
+/// Quotes an identifier, such as a table, column or savepoint name, for use in SQL text.
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
@@ -0,0 +1,172 @@
+use std::collections::{HashMap, VecDeque};
+
+use ext_php_rs::exception::PhpException;
+
+use crate::{
+    errors::LibSQLError,
+    utils::{runtime::runtime, sql_lexer},
+    CONNECTION_MODE_REGISTRY, STATEMENT_CACHE_REGISTRY,
+};
+
+/// The number of prepared statements cached per connection unless configured otherwise.
+pub const DEFAULT_STATEMENT_CACHE_SIZE: usize = 100;
+
+/// A least-recently-used cache of prepared statements, keyed by SQL text.
+pub struct StatementCache {
+    capacity: usize,
+    statements: HashMap<String, libsql::Statement>,
+    /// SQL texts of the cached statements, from least to most recently used.
+    order: VecDeque<String>,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl StatementCache {
+    /// Creates an empty cache holding up to `capacity` statements; `0` disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            statements: HashMap::new(),
+            order: VecDeque::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Takes the statement for `sql` out of the cache, counting a hit or a miss.
+    ///
+    /// The statement is put back with `put` once it has run, so the cache is not locked
+    /// while it executes.
+    fn take(&mut self, sql: &str) -> Option<libsql::Statement> {
+        if self.capacity == 0 {
+            return None;
+        }
+
+        match self.statements.remove(sql) {
+            Some(stmt) => {
+                self.order.retain(|cached| cached != sql);
+                self.hits += 1;
+                Some(stmt)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Caches a statement as the most recently used, evicting the least recently used ones.
+    fn put(&mut self, sql: &str, stmt: libsql::Statement) {
+        if self.capacity == 0 || self.statements.contains_key(sql) {
+            return;
+        }
+
+        while self.statements.len() >= self.capacity {
+            match self.order.pop_front() {
+                Some(evicted) => {
+                    self.statements.remove(&evicted);
+                }
+                None => break,
+            }
+        }
+
+        self.order.push_back(sql.to_string());
+        self.statements.insert(sql.to_string(), stmt);
+    }
+
+    /// Removes every cached statement, keeping the counters.
+    pub fn clear(&mut self) {
+        self.statements.clear();
+        self.order.clear();
+    }
+
+    /// Returns the number of cached statements.
+    pub fn size(&self) -> usize {
+        self.statements.len()
+    }
+
+    /// Returns the maximum number of cached statements.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+/// Runs `f` on the cached prepared statement for `sql`, preparing and caching it on a miss.
+///
+/// The statement is reset once `f` returns. Statements that change the schema are not cached,
+/// and clear the cache of the connection instead. Remote connections always prepare a new
+/// statement, since a remote statement keeps the stream it was prepared on and reusing it after
+/// `BEGIN` would run it outside the transaction.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection whose cache is used.
+/// * `conn` - The connection to prepare the statement on.
+/// * `sql` - The SQL of the statement.
+/// * `f` - The function running the statement.
+///
+/// # Returns
+///
+/// The result of `f`, or a `PhpException` if the statement cannot be prepared.
+pub fn with_cached_statement<T>(
+    conn_id: &str,
+    conn: &libsql::Connection,
+    sql: &str,
+    f: impl FnOnce(&mut libsql::Statement) -> Result<T, PhpException>,
+) -> Result<T, PhpException> {
+    let cacheable = CONNECTION_MODE_REGISTRY.get(conn_id).as_deref() != Some("remote");
+    let cached = if cacheable {
+        STATEMENT_CACHE_REGISTRY
+            .with(conn_id, |cache| cache.take(sql))
+            .flatten()
+    } else {
+        None
+    };
+
+    let mut stmt = match cached {
+        Some(stmt) => stmt,
+        None => runtime()
+            .block_on(async { conn.prepare(sql).await })
+            .map_err(|e| {
+                LibSQLError::from_libsql(&e)
+                    .context("Prepare failed")
+                    .with_sql(sql)
+                    .with_connection(conn_id)
+            })?,
+    };
+
+    let result = f(&mut stmt);
+    stmt.reset();
+
+    if is_schema_change(sql) {
+        invalidate(conn_id);
+    } else if cacheable {
+        STATEMENT_CACHE_REGISTRY.with(conn_id, |cache| cache.put(sql, stmt));
+    }
+
+    result
+}
+
+/// Clears the statement cache of a connection if `sql` contains a schema change.
+///
+/// # Arguments
+///
+/// * `conn_id` - The ID of the connection whose cache is cleared.
+/// * `sql` - One or more SQL statements, separated by `;`.
+pub fn invalidate_on_schema_change(conn_id: &str, sql: &str) {
+    if is_schema_change(sql) {
+        invalidate(conn_id);
+    }
+}
+
+/// Clears the statement cache of a connection.
+fn invalidate(conn_id: &str) {
+    STATEMENT_CACHE_REGISTRY.with(conn_id, |cache| cache.clear());
+}
+
+/// Returns `true` if a statement of `sql` creates, alters or drops a schema object.
+fn is_schema_change(sql: &str) -> bool {
+    sql_lexer::leading_keywords(sql)
+        .iter()
+        .any(|keyword| matches!(keyword.as_str(), "CREATE" | "ALTER" | "DROP"))
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+
+use ext_php_rs::{convert::IntoZval, exception::PhpException, types::ZendCallable, types::Zval};
+
+use super::{column_info::ColumnInfo, runtime::convert_libsql_value_to_zval};
+
+/// `JSON_THROW_ON_ERROR`, so `json_decode` reports malformed documents instead of returning `null`.
+const JSON_THROW_ON_ERROR: i64 = 4194304;
+
+/// Represents the PHP type a column value is converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhpType {
+    /// Keeps the one-to-one conversion of `convert_libsql_value_to_zval`.
+    Auto,
+    Int,
+    Float,
+    String,
+    Bool,
+    DateTime,
+    Json,
+    Binary,
+}
+
+impl PhpType {
+    /// Parses a PHP type name as accepted by `enableTypeMapping` and per-query overrides.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The type name, e.g. `"bool"`, `"datetime"` or `"json"`.
+    ///
+    /// # Returns
+    ///
+    /// The matching `PhpType`, or `None` if the name is unknown.
+    pub fn parse(name: &str) -> Option<PhpType> {
+        match name.trim().to_lowercase().as_str() {
+            "auto" | "raw" => Some(PhpType::Auto),
+            "int" | "integer" => Some(PhpType::Int),
+            "float" | "double" | "real" => Some(PhpType::Float),
+            "string" | "text" => Some(PhpType::String),
+            "bool" | "boolean" => Some(PhpType::Bool),
+            "datetime" | "date" | "datetimeimmutable" => Some(PhpType::DateTime),
+            "json" | "array" => Some(PhpType::Json),
+            "binary" | "blob" => Some(PhpType::Binary),
+            _ => None,
+        }
+    }
+}
+
+/// Maps declared column types (and individual columns) to PHP types.
+#[derive(Debug, Clone, Default)]
+pub struct TypeMap {
+    /// Conversions keyed by normalized declared type, e.g. `BOOLEAN` or `DECIMAL`.
+    pub declared: HashMap<String, PhpType>,
+    /// Conversions keyed by result column name, taking precedence over declared types.
+    pub columns: HashMap<String, PhpType>,
+}
+
+impl TypeMap {
+    /// Creates the default type map used when type mapping is enabled.
+    ///
+    /// * `BOOLEAN`/`BOOL` become `bool`
+    /// * `DATETIME`/`DATE`/`TIMESTAMP` become `DateTimeImmutable`
+    /// * `JSON` is decoded into arrays
+    /// * `DECIMAL`/`NUMERIC` become strings to preserve precision
+    /// * `BLOB` becomes a binary string
+    pub fn defaults() -> Self {
+        let declared = [
+            ("BOOLEAN", PhpType::Bool),
+            ("BOOL", PhpType::Bool),
+            ("DATETIME", PhpType::DateTime),
+            ("DATE", PhpType::DateTime),
+            ("TIMESTAMP", PhpType::DateTime),
+            ("JSON", PhpType::Json),
+            ("DECIMAL", PhpType::String),
+            ("NUMERIC", PhpType::String),
+            ("BLOB", PhpType::Binary),
+        ]
+        .into_iter()
+        .map(|(decl, ty)| (decl.to_string(), ty))
+        .collect();
+
+        Self {
+            declared,
+            columns: HashMap::new(),
+        }
+    }
+
+    /// Adds declared type conversions on top of the current map.
+    ///
+    /// # Arguments
+    ///
+    /// * `types` - Declared type names mapped to PHP type names.
+    pub fn with_declared(mut self, types: HashMap<String, String>) -> Result<Self, PhpException> {
+        for (decl, name) in types {
+            let ty = PhpType::parse(&name).ok_or_else(|| {
+                PhpException::default(format!(
+                    "Unknown PHP type '{}' for declared type '{}'",
+                    name, decl
+                ))
+            })?;
+            self.declared.insert(normalize_decl_type(&decl), ty);
+        }
+
+        Ok(self)
+    }
+
+    /// Combines the type map of a connection with per-query column types.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The type map of the connection, if type mapping is enabled.
+    /// * `types` - Per-query PHP types keyed by column name.
+    ///
+    /// # Returns
+    ///
+    /// The type map to apply to the query result, or `None` if neither is set.
+    pub fn for_query(
+        base: Option<&TypeMap>,
+        types: Option<HashMap<String, String>>,
+    ) -> Result<Option<TypeMap>, PhpException> {
+        match (base, types) {
+            (Some(type_map), Some(types)) => Ok(Some(type_map.clone().with_columns(types)?)),
+            (None, Some(types)) => Ok(Some(TypeMap::default().with_columns(types)?)),
+            (type_map, None) => Ok(type_map.cloned()),
+        }
+    }
+
+    /// Adds per-column conversions on top of the current map.
+    ///
+    /// # Arguments
+    ///
+    /// * `types` - Result column names mapped to PHP type names.
+    pub fn with_columns(mut self, types: HashMap<String, String>) -> Result<Self, PhpException> {
+        for (column, name) in types {
+            let ty = PhpType::parse(&name).ok_or_else(|| {
+                PhpException::default(format!(
+                    "Unknown PHP type '{}' for column '{}'",
+                    name, column
+                ))
+            })?;
+            self.columns.insert(column, ty);
+        }
+
+        Ok(self)
+    }
+
+    /// Resolves the PHP type of every column of a result set.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - The column metadata, in result order.
+    ///
+    /// # Returns
+    ///
+    /// One `PhpType` per column, in result order.
+    pub fn resolve(&self, columns: &[ColumnInfo]) -> Vec<PhpType> {
+        columns
+            .iter()
+            .map(|column| {
+                self.columns
+                    .get(&column.name)
+                    .or_else(|| {
+                        column
+                            .decl_type
+                            .as_deref()
+                            .and_then(|decl| self.declared.get(&normalize_decl_type(decl)))
+                    })
+                    .copied()
+                    .unwrap_or(PhpType::Auto)
+            })
+            .collect()
+    }
+}
+
+/// Normalizes a declared type for lookups: `decimal(10, 2)` becomes `DECIMAL`.
+fn normalize_decl_type(decl: &str) -> String {
+    decl.split('(')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_uppercase()
+}
+
+/// Converts a LibSQL value to a Zval of the given PHP type.
+///
+/// # Arguments
+///
+/// * `value` - The LibSQL value to be converted.
+/// * `ty` - The PHP type to convert to.
+/// * `column` - The column name, used in error messages.
+///
+/// # Returns
+///
+/// A Result containing the converted Zval or a `PhpException` if the value cannot be converted.
+pub fn convert_value(
+    value: libsql::Value,
+    ty: PhpType,
+    column: &str,
+) -> Result<Zval, PhpException> {
+    let conversion_error = |value: &libsql::Value, target: &str| {
+        PhpException::default(format!(
+            "Cannot convert value {:?} of column '{}' to {}",
+            value, column, target
+        ))
+    };
+
+    let zval = match (ty, value) {
+        (_, libsql::Value::Null) => Ok(Zval::new()),
+        (PhpType::Auto, value) => convert_libsql_value_to_zval(value),
+        (PhpType::Int, libsql::Value::Integer(i)) => i.into_zval(false),
+        (PhpType::Int, libsql::Value::Real(f)) => (f as i64).into_zval(false),
+        (PhpType::Int, libsql::Value::Text(t)) => match t.trim().parse::<i64>() {
+            Ok(i) => i.into_zval(false),
+            Err(_) => return Err(conversion_error(&libsql::Value::Text(t), "int")),
+        },
+        (PhpType::Float, libsql::Value::Real(f)) => f.into_zval(false),
+        (PhpType::Float, libsql::Value::Integer(i)) => (i as f64).into_zval(false),
+        (PhpType::Float, libsql::Value::Text(t)) => match t.trim().parse::<f64>() {
+            Ok(f) => f.into_zval(false),
+            Err(_) => return Err(conversion_error(&libsql::Value::Text(t), "float")),
+        },
+        (PhpType::String, libsql::Value::Integer(i)) => i.to_string().into_zval(false),
+        (PhpType::String, libsql::Value::Real(f)) => f.to_string().into_zval(false),
+        (PhpType::String, libsql::Value::Text(t)) => t.into_zval(false),
+        (PhpType::Bool, libsql::Value::Integer(i)) => (i != 0).into_zval(false),
+        (PhpType::Bool, libsql::Value::Real(f)) => (f != 0.0).into_zval(false),
+        (PhpType::Bool, libsql::Value::Text(t)) => {
+            let truthy = !matches!(t.trim().to_lowercase().as_str(), "" | "0" | "false" | "f");
+            truthy.into_zval(false)
+        }
+        (PhpType::DateTime, libsql::Value::Integer(i)) => {
+            return create_datetime(&format!("@{}", i), column)
+        }
+        (PhpType::DateTime, libsql::Value::Real(f)) => {
+            return create_datetime(&format!("@{}", f), column)
+        }
+        (PhpType::DateTime, libsql::Value::Text(t)) => return create_datetime(&t, column),
+        (PhpType::Json, libsql::Value::Text(t)) => return decode_json(&t, column),
+        (PhpType::Binary | PhpType::String, libsql::Value::Blob(b)) => {
+            let mut zval = Zval::new();
+            zval.set_binary(b);
+            Ok(zval)
+        }
+        (PhpType::Binary, libsql::Value::Text(t)) => t.into_zval(false),
+        (ty, value) => {
+            return Err(conversion_error(
+                &value,
+                &format!("{:?}", ty).to_lowercase(),
+            ))
+        }
+    };
+
+    zval.map_err(|e| PhpException::default(e.to_string()))
+}
+
+/// Creates a `DateTimeImmutable` through `date_create_immutable`.
+fn create_datetime(value: &str, column: &str) -> Result<Zval, PhpException> {
+    let date_create = ZendCallable::try_from_name("date_create_immutable")
+        .map_err(|e| PhpException::default(e.to_string()))?;
+
+    match date_create.try_call(vec![&value]) {
+        Ok(datetime) if !datetime.is_false() => Ok(datetime),
+        _ => Err(PhpException::default(format!(
+            "Cannot convert value '{}' of column '{}' to DateTimeImmutable",
+            value, column
+        ))),
+    }
+}
+
+/// Decodes a JSON document into PHP arrays through `json_decode`.
+fn decode_json(value: &str, column: &str) -> Result<Zval, PhpException> {
+    let json_decode = ZendCallable::try_from_name("json_decode")
+        .map_err(|e| PhpException::default(e.to_string()))?;
+
+    json_decode
+        .try_call(vec![&value, &true, &512i64, &JSON_THROW_ON_ERROR])
+        .map_err(|_| PhpException::default(format!("Invalid JSON document in column '{}'", column)))
+}
@@ -0,0 +1,152 @@
+use std::{
+    collections::BTreeMap,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicPtr, AtomicU32, AtomicU64, Ordering},
+        Condvar, Mutex, PoisonError,
+    },
+    thread,
+    time::Instant,
+};
+
+use crate::utils::guard::LockExt;
+
+type Action = Box<dyn FnOnce() + Send>;
+
+/// Runs actions at their deadline from a single thread, shared by the whole process.
+///
+/// A local query blocks the PHP thread that runs it, so its timeout, like the one of a
+/// transaction, is watched from this thread instead.
+struct Watchdog {
+    /// The pending actions, by deadline and in the order they were scheduled.
+    deadlines: Mutex<BTreeMap<(Instant, u64), Action>>,
+
+    /// Wakes the watchdog thread when an earlier deadline is scheduled.
+    changed: Condvar,
+
+    next_id: AtomicU64,
+}
+
+/// A scheduled action, which is cancelled when the deadline is dropped.
+///
+/// Actions run while the deadlines are locked, so once the deadline is dropped the action is
+/// either done or will never run.
+pub struct Deadline {
+    key: (Instant, u64),
+    pid: u32,
+}
+
+/// The watchdog of the current process and the pid it was started in.
+static WATCHDOG: AtomicPtr<Watchdog> = AtomicPtr::new(std::ptr::null_mut());
+static WATCHDOG_PID: AtomicU32 = AtomicU32::new(0);
+
+/// Serializes starting the watchdog.
+static WATCHDOG_INIT: Mutex<()> = Mutex::new(());
+
+/// Schedules an action on the watchdog thread.
+///
+/// The action must return quickly, since it holds up the actions scheduled after it, and must
+/// not schedule or cancel actions itself.
+///
+/// # Arguments
+///
+/// * `at` - When the action runs.
+/// * `action` - The action.
+///
+/// # Returns
+///
+/// The deadline, to be kept until the action is no longer needed.
+pub fn schedule(at: Instant, action: impl FnOnce() + Send + 'static) -> Deadline {
+    let watchdog = watchdog();
+    let key = (at, watchdog.next_id.fetch_add(1, Ordering::Relaxed));
+
+    watchdog
+        .deadlines
+        .lock_or_recover()
+        .insert(key, Box::new(action));
+    watchdog.changed.notify_one();
+
+    Deadline {
+        key,
+        pid: std::process::id(),
+    }
+}
+
+impl Drop for Deadline {
+    fn drop(&mut self) {
+        // A deadline scheduled before a fork belongs to the watchdog of the parent
+        if self.pid != std::process::id() {
+            return;
+        }
+
+        watchdog().deadlines.lock_or_recover().remove(&self.key);
+    }
+}
+
+/// Retrieves the watchdog of the current process, starting it on first use.
+///
+/// As with the runtime, a fork starts a new watchdog, since its thread does not exist in the
+/// child.
+fn watchdog() -> &'static Watchdog {
+    let pid = std::process::id();
+
+    // The pid is stored after the watchdog, so a matching pid guarantees a current watchdog
+    if WATCHDOG_PID.load(Ordering::Acquire) == pid {
+        return unsafe { &*WATCHDOG.load(Ordering::Acquire) };
+    }
+
+    let _init = WATCHDOG_INIT.lock_or_recover();
+    if WATCHDOG_PID.load(Ordering::Acquire) == pid {
+        return unsafe { &*WATCHDOG.load(Ordering::Acquire) };
+    }
+
+    // The inherited watchdog is leaked, its deadlines may be locked by a thread that is gone
+    let watchdog: &'static Watchdog = Box::leak(Box::new(Watchdog {
+        deadlines: Mutex::new(BTreeMap::new()),
+        changed: Condvar::new(),
+        next_id: AtomicU64::new(0),
+    }));
+    thread::Builder::new()
+        .name("libsql-watchdog".to_string())
+        .spawn(move || watch(watchdog))
+        .expect("Failed to start the watchdog thread");
+
+    WATCHDOG.store(
+        watchdog as *const Watchdog as *mut Watchdog,
+        Ordering::Release,
+    );
+    WATCHDOG_PID.store(pid, Ordering::Release);
+
+    watchdog
+}
+
+/// Runs the actions of the watchdog as their deadlines pass.
+fn watch(watchdog: &'static Watchdog) {
+    let mut deadlines = watchdog.deadlines.lock_or_recover();
+
+    loop {
+        let now = Instant::now();
+        while let Some(entry) = deadlines.first_entry() {
+            if entry.key().0 > now {
+                break;
+            }
+
+            let action = entry.remove();
+            let _ = catch_unwind(AssertUnwindSafe(action));
+        }
+
+        deadlines = match deadlines.keys().next() {
+            Some(&(at, _)) => {
+                watchdog
+                    .changed
+                    .wait_timeout(deadlines, at.saturating_duration_since(now))
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .0
+            }
+            None => watchdog
+                .changed
+                .wait(deadlines)
+                .unwrap_or_else(PoisonError::into_inner),
+        };
+    }
+}
@@ -0,0 +1,81 @@
+<?php
+
+use Tests\TestCase;
+
+uses(TestCase::class);
+
+describe('Authorizer', function () {
+    beforeEach(function () {
+        $this->db->execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, secret TEXT)");
+        $this->db->execute("INSERT INTO users (name, secret) VALUES ('Jane Doe', 'hunter2')");
+    });
+
+    test('authorizer receives the action and object names', function () {
+        $actions = [];
+        $this->db->setAuthorizer(function (int $action, ?string $arg1, ?string $arg2, ?string $database) use (&$actions) {
+            $actions[] = [$action, $arg1, $arg2, $database];
+            return LibSQL::SQLITE_OK;
+        });
+
+        $this->db->query("SELECT name FROM users");
+
+        expect($actions)->toContain([LibSQL::SQLITE_SELECT, null, null, null])
+            ->and($actions)->toContain([LibSQL::SQLITE_READ, 'users', 'name', 'main']);
+    });
+
+    test('denied action throws', function () {
+        $this->db->setAuthorizer(fn (int $action) => $action === LibSQL::SQLITE_ATTACH ? LibSQL::SQLITE_DENY : LibSQL::SQLITE_OK);
+
+        expect(fn () => $this->db->execute("ATTACH DATABASE ':memory:' AS other"))
+            ->toThrow(AuthException::class);
+
+        expect($this->db->query("SELECT COUNT(*) FROM users")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(1);
+    });
+
+    test('ignored column reads as null', function () {
+        $this->db->setAuthorizer(function (int $action, ?string $table, ?string $column) {
+            return $action === LibSQL::SQLITE_READ && $column === 'secret' ? LibSQL::SQLITE_IGNORE : LibSQL::SQLITE_OK;
+        });
+
+        $row = $this->db->query("SELECT name, secret FROM users")->fetchSingle(LibSQL::LIBSQL_ASSOC);
+
+        expect($row)->toBe(['name' => 'Jane Doe', 'secret' => null]);
+    });
+
+    test('pragma writes and load_extension can be blocked', function () {
+        $this->db->setAuthorizer(function (int $action, ?string $arg1, ?string $arg2) {
+            return match (true) {
+                $action === LibSQL::SQLITE_PRAGMA && $arg2 !== null => LibSQL::SQLITE_DENY,
+                $action === LibSQL::SQLITE_FUNCTION && $arg2 === 'load_extension' => LibSQL::SQLITE_DENY,
+                default => LibSQL::SQLITE_OK,
+            };
+        });
+
+        expect(fn () => $this->db->execute("PRAGMA user_version = 5"))->toThrow(AuthException::class)
+            ->and(fn () => $this->db->query("SELECT load_extension('nope')"))->toThrow(AuthException::class)
+            ->and($this->db->query("PRAGMA user_version")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(0);
+    });
+
+    test('cached statements are authorized again', function () {
+        $this->db->query("SELECT name FROM users");
+
+        $this->db->setAuthorizer(fn (int $action) => $action === LibSQL::SQLITE_READ ? LibSQL::SQLITE_DENY : LibSQL::SQLITE_OK);
+
+        expect(fn () => $this->db->query("SELECT name FROM users"))->toThrow(AuthException::class);
+    });
+
+    test('throwing or returning an unknown value denies the action', function () {
+        $this->db->setAuthorizer(fn () => throw new RuntimeException('no'));
+        expect(fn () => $this->db->query("SELECT 1"))->toThrow(AuthException::class);
+
+        $this->db->setAuthorizer(fn () => 'yes');
+        expect(fn () => $this->db->query("SELECT 1"))->toThrow(AuthException::class);
+    });
+
+    test('removed authorizer allows everything again', function () {
+        $this->db->setAuthorizer(fn () => LibSQL::SQLITE_DENY);
+        $this->db->setAuthorizer(null);
+
+        expect($this->db->query("SELECT COUNT(*) FROM users")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(1);
+    });
+})->group('AuthorizerTest', 'Feature');
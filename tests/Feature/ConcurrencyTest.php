@@ -0,0 +1,78 @@
+<?php
+
+use Tests\TestCase;
+
+uses(TestCase::class);
+
+describe('Concurrency', function () {
+    test('threads run connections, statements and transactions concurrently', function () {
+        $work = function (int $rows): int {
+            $db = new LibSQL(':memory:');
+            $db->execute("CREATE TABLE hits (id INTEGER PRIMARY KEY, n INTEGER)");
+
+            $trx = $db->transaction();
+            $insert = $trx->prepare("INSERT INTO hits (n) VALUES (?)");
+            for ($n = 1; $n <= $rows; $n++) {
+                $insert->execute([$n]);
+            }
+            $insert->finalize();
+            $trx->commit();
+
+            $sum = $db->query("SELECT SUM(n) FROM hits")->fetchSingle(LibSQL::LIBSQL_NUM)[0];
+            $db->close();
+
+            return $sum;
+        };
+
+        $futures = [];
+        foreach ([100, 200, 300, 400] as $rows) {
+            $futures[$rows] = (new \parallel\Runtime())->run($work, [$rows]);
+        }
+
+        $this->db->execute("CREATE TABLE hits (id INTEGER PRIMARY KEY, n INTEGER)");
+        for ($n = 1; $n <= 50; $n++) {
+            $this->db->execute("INSERT INTO hits (n) VALUES (?)", [$n]);
+        }
+
+        foreach ($futures as $rows => $future) {
+            expect($future->value())->toBe($rows * ($rows + 1) / 2);
+        }
+
+        $result = $this->db->query("SELECT COUNT(*) FROM hits");
+        expect($result->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(50);
+    })->skip(! extension_loaded('parallel'), 'parallel is not available');
+
+    test('threads read one database file concurrently', function () {
+        $path = tempnam(sys_get_temp_dir(), 'libsql');
+        $db = new LibSQL("libsql:dbname={$path}");
+        $db->execute("CREATE TABLE accounts (id INTEGER PRIMARY KEY, balance INTEGER)");
+        $db->executeBatch("
+            INSERT INTO accounts (balance) VALUES (100);
+            INSERT INTO accounts (balance) VALUES (200);
+            INSERT INTO accounts (balance) VALUES (300);
+        ");
+
+        $read = function (string $path): int {
+            $db = new LibSQL("libsql:dbname={$path}");
+            $total = 0;
+            for ($i = 0; $i < 100; $i++) {
+                $total += $db->query("SELECT SUM(balance) FROM accounts")->fetchSingle(LibSQL::LIBSQL_NUM)[0];
+            }
+            $db->close();
+
+            return $total;
+        };
+
+        $futures = [];
+        for ($thread = 0; $thread < 4; $thread++) {
+            $futures[] = (new \parallel\Runtime())->run($read, [$path]);
+        }
+
+        foreach ($futures as $future) {
+            expect($future->value())->toBe(60000);
+        }
+
+        $db->close();
+        unlink($path);
+    })->skip(! extension_loaded('parallel'), 'parallel is not available');
+})->group('ConcurrencyTest', 'Feature');
@@ -0,0 +1,85 @@
+<?php
+
+use Tests\TestCase;
+
+uses(TestCase::class);
+
+describe('Exceptions', function () {
+    beforeEach(function () {
+        $this->db->executeBatch("
+            PRAGMA foreign_keys = ON;
+            CREATE TABLE teams (id INTEGER PRIMARY KEY);
+            CREATE TABLE users (
+                id INTEGER PRIMARY KEY,
+                email TEXT UNIQUE NOT NULL,
+                age INTEGER CHECK (age >= 0),
+                team_id INTEGER REFERENCES teams (id)
+            );
+            INSERT INTO users (email) VALUES ('jane@example.com');
+        ");
+    });
+
+    test('throws a unique constraint violation with its codes and SQL', function () {
+        $sql = "INSERT INTO users (email) VALUES (?)";
+
+        try {
+            $this->db->execute($sql, ['jane@example.com']);
+            $this->fail('Expected an exception');
+        } catch (UniqueConstraintViolationException $e) {
+            expect($e)->toBeInstanceOf(ConstraintViolationException::class)
+                ->and($e)->toBeInstanceOf(LibSQLException::class)
+                ->and($e)->toBeInstanceOf(Exception::class)
+                ->and($e->getCode())->toBe(2067)
+                ->and($e->getExtendedCode())->toBe(2067)
+                ->and($e->getSqliteCode())->toBe(19)
+                ->and($e->getSql())->toBe($sql)
+                ->and($e->getMode())->toBe('local')
+                ->and($e->getMessage())->toContain('UNIQUE constraint failed');
+        }
+    });
+
+    test('throws a not null constraint violation', function () {
+        expect(fn () => $this->db->execute("INSERT INTO users (email) VALUES (NULL)"))
+            ->toThrow(NotNullConstraintViolationException::class);
+    });
+
+    test('throws a check constraint violation', function () {
+        expect(fn () => $this->db->execute("INSERT INTO users (email, age) VALUES ('john@example.com', -1)"))
+            ->toThrow(CheckConstraintViolationException::class);
+    });
+
+    test('throws a foreign key constraint violation', function () {
+        expect(fn () => $this->db->execute("INSERT INTO users (email, team_id) VALUES ('john@example.com', 42)"))
+            ->toThrow(ForeignKeyConstraintViolationException::class);
+    });
+
+    test('throws a syntax exception', function () {
+        expect(fn () => $this->db->query("SELEC * FROM users"))->toThrow(SyntaxException::class);
+        expect(fn () => $this->db->prepare("SELECT * FROM"))->toThrow(SyntaxException::class);
+    });
+
+    test('throws from prepared statements', function () {
+        $stmt = $this->db->prepare("INSERT INTO users (email) VALUES (?)");
+
+        expect(fn () => $stmt->execute(['jane@example.com']))
+            ->toThrow(UniqueConstraintViolationException::class);
+    });
+
+    test('does not mistake a 401 in the message for rejected credentials', function () {
+        try {
+            $this->db->query("SELECT * FROM unauthorized_401");
+            $this->fail('Expected an exception');
+        } catch (LibSQLException $e) {
+            expect($e)->not->toBeInstanceOf(AuthException::class);
+        }
+    });
+
+    test('keeps the connection usable after an exception', function () {
+        try {
+            $this->db->execute("INSERT INTO users (email) VALUES ('jane@example.com')");
+        } catch (LibSQLException) {
+        }
+
+        expect($this->db->execute("INSERT INTO users (email) VALUES ('john@example.com')"))->toBe(1);
+    });
+})->group('ExceptionTest', 'Feature');
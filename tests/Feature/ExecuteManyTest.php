@@ -0,0 +1,52 @@
+<?php
+
+use Tests\TestCase;
+
+uses(TestCase::class);
+
+describe('Execute Many', function () {
+    beforeEach(function () {
+        $this->db->execute("CREATE TABLE cities (id INTEGER PRIMARY KEY, name TEXT UNIQUE, population INTEGER)");
+    });
+
+    test('runs a statement once per parameter set', function () {
+        $result = $this->db->executeMany(
+            "INSERT INTO cities (name, population) VALUES (?, ?)",
+            [['Paris', 2161000], ['London', 8982000], ['Berlin', 3769000]]
+        );
+
+        expect($result)->toBe(['rowsChanged' => 3, 'lastInsertIds' => [1, 2, 3]]);
+    });
+
+    test('accepts generators and named parameters', function () {
+        $sets = (function () {
+            yield [':name' => 'Paris', ':population' => 2161000];
+            yield [':name' => 'London', ':population' => 8982000];
+        })();
+
+        $stmt = $this->db->prepare("INSERT INTO cities (name, population) VALUES (:name, :population)");
+        $result = $stmt->executeMany($sets);
+
+        expect($result['rowsChanged'])->toBe(2)
+            ->and($this->db->query("SELECT COUNT(*) FROM cities")->fetchArray(LibSQL::LIBSQL_NUM)[0][0])->toBe(2);
+    });
+
+    test('rolls back every execution when one fails', function () {
+        expect(fn () => $this->db->executeMany(
+            "INSERT INTO cities (name, population) VALUES (?, ?)",
+            [['Paris', 2161000], ['Paris', 2161000]]
+        ))->toThrow(Exception::class);
+
+        expect($this->db->query("SELECT COUNT(*) FROM cities")->fetchArray(LibSQL::LIBSQL_NUM)[0][0])->toBe(0)
+            ->and($this->db->isAutocommit())->toBeTrue();
+    });
+
+    test('validates every parameter set before executing', function () {
+        expect(fn () => $this->db->executeMany(
+            "INSERT INTO cities (name, population) VALUES (?, ?)",
+            [['Paris', 2161000], ['London']]
+        ))->toThrow(Exception::class, 'Missing value for parameter 2');
+
+        expect($this->db->query("SELECT COUNT(*) FROM cities")->fetchArray(LibSQL::LIBSQL_NUM)[0][0])->toBe(0);
+    });
+})->group('ExecuteManyTest', 'Feature');
@@ -0,0 +1,93 @@
+<?php
+
+use Tests\TestCase;
+
+uses(TestCase::class);
+
+describe('Insert Many', function () {
+    beforeEach(function () {
+        $this->db->execute("CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT UNIQUE, name TEXT, age INTEGER)");
+    });
+
+    test('inserts rows in chunks', function () {
+        $rows = (function () {
+            for ($i = 1; $i <= 25; $i++) {
+                yield ['email' => "user$i@example.com", 'name' => "User $i", 'age' => 20 + $i];
+            }
+        })();
+
+        $result = $this->db->insertMany('users', $rows, ['chunkSize' => 10]);
+
+        expect($result)->toBe(['inserted' => 25, 'updated' => 0])
+            ->and($this->db->query("SELECT COUNT(*) FROM users")->fetchArray(LibSQL::LIBSQL_NUM)[0][0])->toBe(25);
+    });
+
+    test('aligns columns listed in a different order', function () {
+        $this->db->insertMany('users', [
+            ['email' => 'jane@example.com', 'name' => 'Jane'],
+            ['name' => 'John', 'email' => 'john@example.com'],
+        ]);
+
+        $names = $this->db->query("SELECT email, name FROM users ORDER BY id")->fetchArray(LibSQL::LIBSQL_ASSOC);
+
+        expect($names[1])->toBe(['email' => 'john@example.com', 'name' => 'John']);
+    });
+
+    test('upserts rows and counts inserted and updated rows', function () {
+        $this->db->execute("INSERT INTO users (email, name, age) VALUES ('jane@example.com', 'Jane', 30)");
+
+        $result = $this->db->insertMany('users', [
+            ['email' => 'jane@example.com', 'name' => 'Jane Doe', 'age' => 31],
+            ['email' => 'john@example.com', 'name' => 'John', 'age' => 25],
+        ], ['conflict' => 'email', 'update' => ['name']]);
+
+        $jane = $this->db->query("SELECT name, age FROM users WHERE email = 'jane@example.com'")
+            ->fetchSingle(LibSQL::LIBSQL_ASSOC);
+
+        expect($result)->toBe(['inserted' => 1, 'updated' => 1])
+            ->and($jane)->toBe(['name' => 'Jane Doe', 'age' => 30]);
+    });
+
+    test('skips conflicting rows with an empty update list', function () {
+        $this->db->execute("INSERT INTO users (email, name) VALUES ('jane@example.com', 'Jane')");
+
+        $result = $this->db->insertMany('users', [
+            ['email' => 'jane@example.com', 'name' => 'Jane Doe'],
+            ['email' => 'john@example.com', 'name' => 'John'],
+        ], ['conflict' => ['email'], 'update' => []]);
+
+        expect($result)->toBe(['inserted' => 1, 'updated' => 0]);
+    });
+
+    test('counts repeated and composite conflict keys across chunks', function () {
+        $this->db->execute("CREATE TABLE scores (player TEXT, game TEXT, score INTEGER, UNIQUE (player, game))");
+        $this->db->execute("INSERT INTO scores VALUES ('ann', 'chess', 1)");
+
+        $result = $this->db->insertMany('scores', [
+            ['player' => 'ann', 'game' => 'chess', 'score' => 2],
+            ['player' => 'ann', 'game' => 'go', 'score' => 3],
+            ['player' => 'ann', 'game' => 'go', 'score' => 4],
+            ['player' => 'bob', 'game' => 'go', 'score' => 5],
+            ['player' => 'bob', 'game' => 'go', 'score' => 6],
+        ], ['conflict' => ['player', 'game'], 'chunkSize' => 4]);
+
+        expect($result)->toBe(['inserted' => 2, 'updated' => 3])
+            ->and($this->db->query("SELECT SUM(score) FROM scores")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(12);
+    });
+
+    test('rolls back every chunk when one fails', function () {
+        expect(fn () => $this->db->insertMany('users', [
+            ['email' => 'jane@example.com', 'name' => 'Jane'],
+            ['email' => 'jane@example.com', 'name' => 'Jane again'],
+        ], ['chunkSize' => 1]))->toThrow(Exception::class);
+
+        expect($this->db->query("SELECT COUNT(*) FROM users")->fetchArray(LibSQL::LIBSQL_NUM)[0][0])->toBe(0);
+    });
+
+    test('rows must share the columns of the first row', function () {
+        $this->db->insertMany('users', [
+            ['email' => 'jane@example.com', 'name' => 'Jane'],
+            ['email' => 'john@example.com', 'age' => 25],
+        ]);
+    })->throws(Exception::class, 'Row 1 has different columns than the first row');
+})->group('InsertManyTest', 'Feature');
@@ -0,0 +1,174 @@
+<?php
+
+use Tests\TestCase;
+
+uses(TestCase::class);
+
+describe('Parameter Binding', function () {
+    beforeEach(function () {
+        $this->db->execute("CREATE TABLE items (
+            id INTEGER PRIMARY KEY,
+            flag,
+            created_at,
+            meta,
+            label,
+            content
+        )");
+    });
+
+    test('binds bools, dates, arrays and stringables', function () {
+        $label = new class implements Stringable {
+            public function __toString(): string
+            {
+                return 'stringable';
+            }
+        };
+
+        $this->db->execute(
+            "INSERT INTO items (id, flag, created_at, meta, label) VALUES (?, ?, ?, ?, ?)",
+            [1, true, new DateTimeImmutable('2024-05-01 10:00:00+00:00'), ['tags' => ['php']], $label]
+        );
+
+        $item = $this->db->query("SELECT * FROM items")->fetchSingle(LibSQL::LIBSQL_ASSOC);
+
+        expect($item['flag'])->toBe(1)
+            ->and($item['created_at'])->toBe('2024-05-01T10:00:00+00:00')
+            ->and($item['meta'])->toBe('{"tags":["php"]}')
+            ->and($item['label'])->toBe('stringable');
+    });
+
+    test('binds dates as unix timestamps when configured', function () {
+        $this->db->setDateTimeBinding('unix');
+
+        $this->db->execute(
+            "INSERT INTO items (id, created_at) VALUES (:id, :created_at)",
+            [':id' => 1, ':created_at' => new DateTimeImmutable('@1714557600')]
+        );
+
+        $item = $this->db->query("SELECT created_at FROM items")->fetchSingle(LibSQL::LIBSQL_ASSOC);
+
+        expect($item['created_at'])->toBe(1714557600);
+    });
+
+    test('date binding is configured per connection', function () {
+        $db = new LibSQL(["url" => ":memory:", "dateTimeBinding" => "unix"]);
+        $date = new DateTimeImmutable('@1714557600');
+
+        expect($db->query("SELECT ?", [$date])->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(1714557600)
+            ->and($this->db->query("SELECT ?", [$date])->fetchSingle(LibSQL::LIBSQL_NUM)[0])
+            ->toBe('2024-05-01T10:00:00+00:00');
+
+        $db->close();
+    });
+
+    test('binds LibSQLBlob and PARAM_BLOB values as BLOB', function () {
+        $this->db->execute("INSERT INTO items (id, content) VALUES (?, ?)", [1, new LibSQLBlob('plain text')]);
+
+        $stmt = $this->db->prepare("UPDATE items SET label = ? WHERE id = 1");
+        $stmt->bindValue(1, hex2bin('FFFE'), LibSQL::PARAM_BLOB);
+        $stmt->execute();
+
+        $item = $this->db->query("SELECT typeof(content) AS content, typeof(label) AS label FROM items")
+            ->fetchSingle(LibSQL::LIBSQL_ASSOC);
+
+        expect($item)->toBe(['content' => 'blob', 'label' => 'blob']);
+    });
+
+    test('invalid UTF-8 strings are rejected', function () {
+        $this->db->execute("INSERT INTO items (id, label) VALUES (?, ?)", [1, hex2bin('FFFE')]);
+    })->throws(Exception::class, 'String is not valid UTF-8');
+
+    test('unsupported values throw a descriptive exception', function () {
+        $this->db->execute("INSERT INTO items (id, label) VALUES (?, ?)", [1, new stdClass()]);
+    })->throws(Exception::class, 'Cannot bind object of class stdClass');
+
+    test('mixed positional and named parameters are rejected', function () {
+        $this->db->execute("INSERT INTO items (id, label) VALUES (:id, :label)", [1, ':label' => 'mixed']);
+    })->throws(Exception::class, 'Cannot mix positional and named parameters');
+
+    test('missing named parameters are reported by name', function () {
+        $this->db->execute("INSERT INTO items (id, label) VALUES (:id, :label)", [':id' => 1]);
+    })->throws(Exception::class, "Missing value for named parameter ':label'");
+
+    test('unknown named parameters are rejected', function () {
+        $this->db->execute("INSERT INTO items (id) VALUES (:id)", [':id' => 1, ':label' => 'extra']);
+    })->throws(Exception::class, "Unknown named parameter 'label'");
+
+    test('positional parameter counts must match the statement', function () {
+        expect(fn () => $this->db->execute("INSERT INTO items (id, label) VALUES (?, ?)", [1]))
+            ->toThrow(Exception::class, 'Missing value for parameter 2')
+            ->and(fn () => $this->db->query("SELECT * FROM items WHERE id = ?", [1, 2]))
+            ->toThrow(Exception::class, 'Unexpected parameter 2');
+    });
+
+    test('expands lists bound inside IN (...)', function () {
+        $this->db->executeMany("INSERT INTO items (id, label) VALUES (?, ?)", [[1, 'a'], [2, 'b'], [3, 'c']]);
+
+        $named = $this->db->query("SELECT id FROM items WHERE id IN (:ids) AND label != :label ORDER BY id", [
+            ':ids' => [1, 2, 3],
+            ':label' => 'b',
+        ])->fetchArray(LibSQL::LIBSQL_NUM);
+        $positional = $this->db->query("SELECT id FROM items WHERE id IN (?) ORDER BY id", [[2, 3]])
+            ->fetchArray(LibSQL::LIBSQL_NUM);
+        $empty = $this->db->query("SELECT id FROM items WHERE id IN (:ids)", [':ids' => []])
+            ->fetchArray(LibSQL::LIBSQL_NUM);
+
+        expect(array_column($named, 0))->toBe([1, 3])
+            ->and(array_column($positional, 0))->toBe([2, 3])
+            ->and($empty)->toBe([])
+            ->and($this->db->execute("DELETE FROM items WHERE id IN (?)", [[1, 2]]))->toBe(2);
+    });
+
+    test('expands lists bound to prepared statements', function () {
+        $this->db->executeMany("INSERT INTO items (id) VALUES (?)", [[1], [2], [3]]);
+        $stmt = $this->db->prepare("SELECT COUNT(*) FROM items WHERE id IN (:ids)");
+
+        expect($stmt->query([':ids' => [1, 2]])->fetchArray(LibSQL::LIBSQL_NUM)[0][0])->toBe(2)
+            ->and($stmt->query([':ids' => [1, 2, 3]])->fetchArray(LibSQL::LIBSQL_NUM)[0][0])->toBe(3);
+    });
+
+    test('lists outside IN (...) still bind as JSON', function () {
+        $this->db->execute("INSERT INTO items (id, meta) VALUES (?, ?)", [1, [1, 2]]);
+
+        expect($this->db->query("SELECT meta FROM items")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe('[1,2]');
+    });
+
+    test('expanded lists are capped at the variable limit', function () {
+        $this->db->query("SELECT * FROM items WHERE id IN (?)", [range(1, 40000)]);
+    })->throws(Exception::class, 'more than the limit of 32766');
+
+    test('positional values map to every placeholder syntax', function () {
+        $numbered = $this->db->query("SELECT $2 AS b, $1 AS a", [1, 2])->fetchSingle(LibSQL::LIBSQL_ASSOC);
+        $literal = $this->db->query("SELECT '?' AS q, ? AS v -- ?", [5])->fetchSingle(LibSQL::LIBSQL_ASSOC);
+        $gaps = $this->db->query("SELECT ?1 AS a, ?3 AS c", [1, 2, 3])->fetchSingle(LibSQL::LIBSQL_ASSOC);
+        $mixed = $this->db->query("SELECT ? AS a, ?3 AS c, ? AS d", [1, 2, 3, 4])->fetchSingle(LibSQL::LIBSQL_ASSOC);
+
+        expect($numbered)->toBe(['b' => 2, 'a' => 1])
+            ->and($literal)->toBe(['q' => '?', 'v' => 5])
+            ->and($gaps)->toBe(['a' => 1, 'c' => 3])
+            ->and($mixed)->toBe(['a' => 1, 'c' => 3, 'd' => 4]);
+    });
+
+    test('unused parameter indexes may be skipped', function () {
+        $stmt = $this->db->prepare("SELECT ?1 AS a, ?3 AS c");
+        $stmt->bindValue(1, 'first');
+        $stmt->bindValue(3, 'third');
+
+        expect($stmt->query()->fetchSingle(LibSQL::LIBSQL_ASSOC))->toBe(['a' => 'first', 'c' => 'third'])
+            ->and(fn () => $this->db->query("SELECT ?, ?, ?", [0 => 1, 2 => 3]))
+            ->toThrow(Exception::class, 'Missing value for parameter 2');
+    });
+
+    test('positional indexes beyond the variable limit are rejected', function () {
+        expect(fn () => $this->db->query("SELECT ?", [PHP_INT_MAX => 1]))
+            ->toThrow(Exception::class, 'Invalid positional parameter index ' . PHP_INT_MAX)
+            ->and(fn () => $this->db->query("SELECT ?", [1 << 40 => 1]))
+            ->toThrow(Exception::class, 'Invalid positional parameter index ' . (1 << 40));
+    });
+
+    test('named parameters bind with or without their prefix', function () {
+        $this->db->execute("INSERT INTO items (id, label) VALUES (:id, :label)", ['id' => 1, 'label' => 'bare']);
+
+        expect($this->db->query("SELECT label FROM items")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe('bare');
+    });
+})->group('ParameterBindingTest', 'Feature');
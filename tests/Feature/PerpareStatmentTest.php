@@ -46,4 +46,83 @@ describe('Prepared Statements', function () {
         $result = $this->db->query("SELECT name FROM products WHERE id = 1");
         expect($result->fetchSingle(LibSQL::LIBSQL_ASSOC)['name'])->toBe('Test');
     });
+
+    test('bound values keep their types', function () {
+        $stmt = $this->db->prepare("INSERT INTO products (id, name, price) VALUES (?, ?, ?)");
+        $stmt->bindValue(1, 1);
+        $stmt->bindValue(2, null);
+        $stmt->bindValue(3, '9.5', LibSQL::PARAM_FLOAT);
+        $stmt->execute();
+
+        $row = $this->db->query("SELECT typeof(id), typeof(name), typeof(price) FROM products")
+            ->fetchSingle(LibSQL::LIBSQL_NUM);
+
+        expect($row)->toBe(['integer', 'null', 'real']);
+    });
+
+    test('bound parameters are read on every execution', function () {
+        $stmt = $this->db->prepare("INSERT INTO products (name, price) VALUES (:name, :price)");
+        $stmt->bindParam(':name', $name);
+        $stmt->bindParam('price', $price);
+
+        foreach ([['Laptop', 999.99], ['Phone', 699.99]] as [$name, $price]) {
+            $stmt->execute();
+        }
+
+        $result = $this->db->query("SELECT name, price FROM products ORDER BY id");
+        expect($result->fetchArray(LibSQL::LIBSQL_NUM))->toBe([['Laptop', 999.99], ['Phone', 699.99]]);
+
+        $stmt->clearBindings();
+        expect(fn () => $stmt->execute())->toThrow(Exception::class, "Missing value for named parameter ':name'");
+    });
+
+    test('reset removes bound values', function () {
+        $stmt = $this->db->prepare("INSERT INTO products (name, price) VALUES (?, ?)");
+        $stmt->bindValue(1, 'Laptop');
+        $stmt->bindParam(2, $price);
+        $stmt->reset();
+
+        expect(fn () => $stmt->execute())->toThrow(Exception::class);
+    });
+
+    test('bound references survive the end of the binding scope', function () {
+        $stmt = $this->db->prepare("INSERT INTO products (name, price) VALUES (?, ?)");
+        (function () use ($stmt) {
+            $name = 'Laptop';
+            $price = 999.99;
+            $stmt->bindParam(1, $name);
+            $stmt->bindParam(2, $price);
+        })();
+
+        $stmt->execute();
+
+        $result = $this->db->query("SELECT name, price FROM products");
+        expect($result->fetchSingle(LibSQL::LIBSQL_NUM))->toBe(['Laptop', 999.99]);
+    });
+
+    test('queries run on the prepared statement', function () {
+        $this->db->execute("INSERT INTO products (id, name, price) VALUES (1, 'Laptop', 999.99), (2, 'Phone', 699.99)");
+
+        $stmt = $this->db->prepare("SELECT name FROM products WHERE id = ?");
+
+        foreach ([[1, 'Laptop'], [2, 'Phone'], [1, 'Laptop']] as [$id, $name]) {
+            expect($stmt->query([$id])->fetchSingle(LibSQL::LIBSQL_NUM))->toBe([$name]);
+        }
+
+        $stmt->finalize();
+        expect(fn () => $stmt->query([1]))->toThrow(Exception::class, 'Statement not found');
+    });
+
+    test('preparing invalid SQL throws instead of crashing', function () {
+        expect(fn () => $this->db->prepare("SELEC name FROM products"))->toThrow(LibSQLException::class)
+            ->and(fn () => $this->db->prepare("SELECT name FROM missing_table"))->toThrow(LibSQLException::class);
+
+        expect($this->db->prepare("SELECT COUNT(*) FROM products")->query()->fetchSingle(LibSQL::LIBSQL_NUM))->toBe([0]);
+    });
+
+    test('loading a missing extension throws', function () {
+        $this->db->enableLoadExtension(true);
+
+        expect(fn () => $this->db->loadExtensions('/nonexistent/extension.so'))->toThrow(LibSQLException::class);
+    });
 })->group("PreparedStatementTest", "Feature");
\ No newline at end of file
@@ -0,0 +1,74 @@
+<?php
+
+use Tests\TestCase;
+
+uses(TestCase::class);
+
+describe('QueryLimits', function () {
+    test('progress handler returning true aborts the query', function () {
+        $calls = 0;
+        $this->db->setProgressHandler(100, function () use (&$calls) {
+            return ++$calls >= 10;
+        });
+
+        expect(fn () => $this->db->query("WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) SELECT MAX(i) FROM n"))
+            ->toThrow(TimeoutException::class);
+        expect($calls)->toBe(10);
+    });
+
+    test('progress handler returning false lets the query finish', function () {
+        $calls = 0;
+        $this->db->setProgressHandler(10, function () use (&$calls) {
+            $calls++;
+            return false;
+        });
+
+        $result = $this->db->query("WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 1000) SELECT SUM(i) FROM n");
+
+        expect($result->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(500500)
+            ->and($calls)->toBeGreaterThan(0);
+    });
+
+    test('removed progress handler is no longer called', function () {
+        $calls = 0;
+        $this->db->setProgressHandler(10, function () use (&$calls) {
+            $calls++;
+            return true;
+        });
+        $this->db->setProgressHandler(0, null);
+
+        $result = $this->db->query("WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 1000) SELECT COUNT(*) FROM n");
+
+        expect($result->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(1000)
+            ->and($calls)->toBe(0);
+    });
+
+    test('setLimit returns the previous value', function () {
+        $original = $this->db->getLimit(LibSQL::SQLITE_LIMIT_LENGTH);
+
+        expect($this->db->setLimit(LibSQL::SQLITE_LIMIT_LENGTH, 100))->toBe($original)
+            ->and($this->db->getLimit(LibSQL::SQLITE_LIMIT_LENGTH))->toBe(100);
+    });
+
+    test('queries exceeding a limit fail', function () {
+        $this->db->setLimit(LibSQL::SQLITE_LIMIT_LENGTH, 100);
+        expect(fn () => $this->db->query("SELECT ?", [str_repeat('x', 200)]))->toThrow(LibSQLException::class);
+
+        $this->db->setLimit(LibSQL::SQLITE_LIMIT_SQL_LENGTH, 20);
+        expect(fn () => $this->db->query("SELECT 1 AS a_rather_long_column_name"))->toThrow(LibSQLException::class);
+
+        $this->db->setLimit(LibSQL::SQLITE_LIMIT_SQL_LENGTH, 1000);
+        $this->db->setLimit(LibSQL::SQLITE_LIMIT_VARIABLE_NUMBER, 2);
+        expect(fn () => $this->db->query("SELECT ?, ?, ?", [1, 2, 3]))->toThrow(LibSQLException::class);
+    });
+
+    test('unknown limit throws', function () {
+        expect(fn () => $this->db->getLimit(42))
+            ->toThrow(LibSQLException::class, 'Unknown limit 42');
+    });
+
+    test('negative limit value throws', function () {
+        expect(fn () => $this->db->setLimit(LibSQL::SQLITE_LIMIT_COLUMN, -1))
+            ->toThrow(LibSQLException::class, 'Invalid limit value -1');
+    });
+})->group('QueryLimitsTest', 'Feature');
@@ -0,0 +1,71 @@
+<?php
+
+use Tests\TestCase;
+
+uses(TestCase::class);
+
+const RUNAWAY_QUERY = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) SELECT MAX(i) FROM n";
+
+describe('QueryTimeout', function () {
+    test('query exceeding the connection timeout throws', function () {
+        $db = new LibSQL(["url" => ":memory:", "queryTimeout" => 50]);
+
+        expect(fn () => $db->query(RUNAWAY_QUERY))
+            ->toThrow(TimeoutException::class, 'Query timed out after 50 ms');
+
+        $db->close();
+    });
+
+    test('connection remains usable after a timeout', function () {
+        $db = new LibSQL(["url" => ":memory:", "queryTimeout" => 50]);
+        $db->execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)");
+
+        expect(fn () => $db->query(RUNAWAY_QUERY))->toThrow(TimeoutException::class);
+
+        $db->execute("INSERT INTO users (name) VALUES (?)", ["Jane Doe"]);
+        $result = $db->query("SELECT name FROM users");
+
+        expect($result->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe("Jane Doe");
+
+        $db->close();
+    });
+
+    test('per-call timeout overrides the connection timeout', function () {
+        expect(fn () => $this->db->query(RUNAWAY_QUERY, [], false, null, 50))
+            ->toThrow(TimeoutException::class, 'Query timed out after 50 ms');
+
+        $this->db->execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)", [], 1000);
+        expect($this->db->query("SELECT COUNT(*) FROM users", [], false, null, 1000)->fetchSingle(LibSQL::LIBSQL_NUM)[0])
+            ->toBe(0);
+    });
+
+    test('execute honours the timeout', function () {
+        $this->db->execute("CREATE TABLE counts (n INTEGER)");
+
+        $insert = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) INSERT INTO counts SELECT MAX(i) FROM n";
+
+        expect(fn () => $this->db->execute($insert, [], 50))->toThrow(TimeoutException::class);
+
+        expect($this->db->query("SELECT COUNT(*) FROM counts")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(0);
+    });
+
+    test('deadlines of queries that finished in time do not fire later', function () {
+        $db = new LibSQL(["url" => ":memory:", "queryTimeout" => 20]);
+
+        for ($i = 0; $i < 200; $i++) {
+            $db->query("SELECT ?", [$i])->fetchArray();
+        }
+        usleep(50000);
+
+        expect($db->query("SELECT 1")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(1)
+            ->and(fn () => $db->query(RUNAWAY_QUERY))->toThrow(TimeoutException::class, 'Query timed out after 20 ms');
+
+        $db->close();
+    });
+
+    test('interrupt without a running query has no effect', function () {
+        $this->db->interrupt();
+
+        expect($this->db->query("SELECT 1")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(1);
+    });
+})->group('QueryTimeoutTest', 'Feature');
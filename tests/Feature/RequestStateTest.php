@@ -0,0 +1,59 @@
+<?php
+
+use Tests\TestCase;
+
+uses(TestCase::class);
+
+describe('Request state', function () {
+    test('resetRequestState releases what the request left open', function () {
+        $this->db->execute("CREATE TABLE accounts (id INTEGER PRIMARY KEY, balance INTEGER)");
+        $trx = $this->db->transaction();
+        $trx->execute("INSERT INTO accounts (balance) VALUES (100)");
+        $stmt = $this->db->prepare("SELECT * FROM accounts");
+
+        LibSQL::resetRequestState();
+
+        expect($trx->isActive())->toBeFalse()
+            ->and(fn () => $stmt->execute())->toThrow(Exception::class, 'Statement not found')
+            ->and(fn () => $this->db->query("SELECT 1"))->toThrow(Exception::class, 'Connection not found');
+
+        $this->db = new LibSQL(':memory:');
+    });
+
+    test('persistent connection survives resetRequestState', function () {
+        $persistent = new LibSQL(['url' => ':memory:', 'persistent' => true]);
+        $persistent->execute("CREATE TABLE accounts (id INTEGER PRIMARY KEY, balance INTEGER)");
+        $persistent->execute("INSERT INTO accounts (balance) VALUES (100)");
+
+        LibSQL::resetRequestState();
+
+        $result = $persistent->query("SELECT balance FROM accounts");
+        expect($result->fetchArray(LibSQL::LIBSQL_NUM))->toBe([[100]]);
+
+        $persistent->close();
+        $this->db = new LibSQL(':memory:');
+    });
+
+    test('opening a persistent config again reuses its connection', function () {
+        LibSQL::resetRequestState();
+        $config = ['url' => ':memory:', 'persistent' => true];
+        $first = new LibSQL($config);
+        $first->execute("CREATE TABLE accounts (id INTEGER PRIMARY KEY, balance INTEGER)");
+        $first->execute("INSERT INTO accounts (balance) VALUES (100)");
+        $connections = LibSQL::openHandles()['connections'];
+
+        LibSQL::resetRequestState();
+        $second = new LibSQL($config);
+
+        expect(LibSQL::openHandles()['connections'])->toBe($connections)
+            ->and($second->query("SELECT balance FROM accounts")->fetchArray(LibSQL::LIBSQL_NUM))->toBe([[100]]);
+
+        $second->close();
+        $third = new LibSQL($config);
+
+        expect(fn () => $third->query("SELECT balance FROM accounts"))->toThrow(LibSQLException::class);
+
+        $third->close();
+        $this->db = new LibSQL(':memory:');
+    });
+})->group('RequestStateTest', 'Feature');
@@ -0,0 +1,91 @@
+<?php
+
+use Tests\TestCase;
+
+uses(TestCase::class);
+
+describe('Resource lifetimes', function () {
+    beforeEach(function () {
+        $this->db->execute("CREATE TABLE accounts (id INTEGER PRIMARY KEY, balance INTEGER)");
+
+        $this->warnings = [];
+        set_error_handler(function (int $errno, string $message) {
+            $this->warnings[] = $message;
+
+            return true;
+        }, E_WARNING);
+    });
+
+    afterEach(function () {
+        restore_error_handler();
+        LibSQL::setLeakDebugging(false);
+    });
+
+    test('abandoned transaction is rolled back with a warning', function () {
+        $transactions = LibSQL::openHandles()['transactions'];
+
+        (function () {
+            $trx = $this->db->transaction();
+            $trx->execute("INSERT INTO accounts (balance) VALUES (100)");
+        })();
+
+        expect($this->warnings)->toHaveCount(1)
+            ->and($this->warnings[0])->toContain('LibSQLTransaction destroyed without commit() or rollback()')
+            ->and(LibSQL::openHandles()['transactions'])->toBe($transactions)
+            ->and($this->db->isAutocommit())->toBeTrue();
+
+        $result = $this->db->query("SELECT COUNT(*) FROM accounts");
+        expect($result->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(0);
+    });
+
+    test('abandoned nested transaction rolls back only its own changes', function () {
+        $trx = $this->db->transaction();
+        $trx->execute("INSERT INTO accounts (balance) VALUES (100)");
+
+        (function () use ($trx) {
+            $nested = $trx->transaction();
+            $nested->execute("INSERT INTO accounts (balance) VALUES (200)");
+        })();
+
+        $trx->commit();
+
+        $result = $this->db->query("SELECT balance FROM accounts");
+        expect($this->warnings)->toHaveCount(1)
+            ->and($result->fetchArray(LibSQL::LIBSQL_NUM))->toBe([[100]]);
+    });
+
+    test('ended transaction is destroyed silently', function () {
+        (function () {
+            $trx = $this->db->transaction();
+            $trx->execute("INSERT INTO accounts (balance) VALUES (100)");
+            $trx->commit();
+        })();
+
+        expect($this->warnings)->toBe([]);
+    });
+
+    test('statement is freed with its object', function () {
+        $statements = LibSQL::openHandles()['statements'];
+
+        $stmt = $this->db->prepare("SELECT * FROM accounts");
+        expect(LibSQL::openHandles()['statements'])->toBe($statements + 1);
+
+        unset($stmt);
+        expect(LibSQL::openHandles()['statements'])->toBe($statements)
+            ->and($this->warnings)->toBe([]);
+    });
+
+    test('leak debugging reports statements that were not finalized', function () {
+        LibSQL::setLeakDebugging(true);
+
+        $finalized = $this->db->prepare("SELECT 1");
+        $finalized->finalize();
+        unset($finalized);
+
+        $leaked = $this->db->prepare("SELECT * FROM accounts");
+        unset($leaked);
+
+        expect($this->warnings)->toHaveCount(1)
+            ->and($this->warnings[0])->toContain('LibSQLStatement destroyed without finalize(): SELECT * FROM accounts');
+    });
+})->group('ResourceLifetimeTest', 'Feature');
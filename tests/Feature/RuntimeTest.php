@@ -0,0 +1,80 @@
+<?php
+
+use Tests\TestCase;
+
+uses(TestCase::class);
+
+describe('Runtime', function () {
+    test('unknown runtime flavor throws', function () {
+        expect(fn () => LibSQL::configureRuntime('green_threads'))
+            ->toThrow(LibSQLException::class, "Unknown runtime flavor 'green_threads'");
+    });
+
+    test('current_thread runtime rejects worker threads', function () {
+        expect(fn () => LibSQL::configureRuntime('current_thread', 4))
+            ->toThrow(LibSQLException::class, 'A current_thread runtime has no worker threads');
+    });
+
+    test('runtime cannot be reconfigured once it is running', function () {
+        $this->db->execute("CREATE TABLE accounts (id INTEGER PRIMARY KEY, balance INTEGER)");
+
+        expect(fn () => LibSQL::configureRuntime('current_thread'))
+            ->toThrow(LibSQLException::class, 'The runtime is already running');
+
+        LibSQL::configureRuntime('multi_thread');
+    });
+
+    test('forked child opens its own connections', function () {
+        $path = tempnam(sys_get_temp_dir(), 'libsql');
+        $db = new LibSQL("libsql:dbname={$path}");
+        $db->execute("CREATE TABLE jobs (pid INTEGER)");
+
+        $pid = pcntl_fork();
+        if ($pid === 0) {
+            $child = new LibSQL("libsql:dbname={$path}");
+            $child->execute("INSERT INTO jobs (pid) VALUES (?)", [getmypid()]);
+            $child->close();
+            exit(0);
+        }
+
+        pcntl_waitpid($pid, $status);
+
+        $result = $db->query("SELECT pid FROM jobs");
+        expect(pcntl_wexitstatus($status))->toBe(0)
+            ->and($result->fetchArray(LibSQL::LIBSQL_NUM))->toBe([[$pid]]);
+
+        $db->close();
+        unlink($path);
+    })->skip(! function_exists('pcntl_fork'), 'pcntl is not available');
+
+    test('forked child cannot use the connections of its parent', function () {
+        $db = new LibSQL(':memory:');
+        $db->execute("CREATE TABLE jobs (pid INTEGER)");
+        $result = $db->query("SELECT pid FROM jobs");
+
+        $pid = pcntl_fork();
+        if ($pid === 0) {
+            $failures = 0;
+            foreach ([
+                fn () => $db->lastInsertedId(),
+                fn () => $db->totalChanges(),
+                fn () => $db->interrupt(),
+                fn () => $result->reset(),
+            ] as $call) {
+                try {
+                    $call();
+                } catch (Exception $e) {
+                    $failures += $e->getMessage() === 'Connection not found' ? 1 : 0;
+                }
+            }
+            exit($failures === 4 ? 0 : 1);
+        }
+
+        pcntl_waitpid($pid, $status);
+
+        expect(pcntl_wexitstatus($status))->toBe(0)
+            ->and($db->totalChanges())->toBe(0);
+
+        $db->close();
+    })->skip(! function_exists('pcntl_fork'), 'pcntl is not available');
+})->group('RuntimeTest', 'Feature');
@@ -43,4 +43,20 @@ describe('Schema Operations', function () {
         expect(fn() => $this->db->execute("INSERT INTO users (email, age) VALUES ('test@test.com', 20)"))
             ->toThrow(Exception::class);
     });
+
+    it('exposes column metadata for empty results', function () {
+        $result = $this->db->query("SELECT id, name, age + 1 AS next_age FROM users");
+
+        expect($result->numColumns())->toBe(3)
+            ->and($result->columnName(1))->toBe('name')
+            ->and($result->columnType(0))->toBe('INTEGER')
+            ->and($result->columnType(2))->toBeNull()
+            ->and($result->columns()[1])->toMatchArray([
+                'name' => 'name',
+                'decl_type' => 'TEXT',
+                'table_name' => 'users',
+                'origin_name' => 'name',
+                'database_name' => 'main',
+            ]);
+    });
 })->group('SchemaOperationsTest', 'Feature');
\ No newline at end of file
@@ -0,0 +1,95 @@
+<?php
+
+use Tests\TestCase;
+
+uses(TestCase::class);
+
+describe('Statement Cache', function () {
+    beforeEach(function () {
+        $this->db->execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)");
+    });
+
+    test('repeated statements are served from the cache', function () {
+        $this->db->clearStatementCache();
+        $before = $this->db->statementCacheStats();
+
+        foreach ([1, 2, 3] as $id) {
+            $this->db->execute("INSERT INTO users (id, name) VALUES (?, ?)", [$id, "user $id"]);
+        }
+
+        $stats = $this->db->statementCacheStats();
+
+        expect($stats['misses'] - $before['misses'])->toBe(1)
+            ->and($stats['hits'] - $before['hits'])->toBe(2)
+            ->and($stats['size'])->toBe(1)
+            ->and($stats['capacity'])->toBe(100);
+    });
+
+    test('schema changes invalidate the cache', function () {
+        $this->db->query("SELECT * FROM users")->fetchArray();
+        expect($this->db->statementCacheStats()['size'])->toBe(1);
+
+        $this->db->execute("ALTER TABLE users ADD COLUMN email TEXT");
+
+        expect($this->db->statementCacheStats()['size'])->toBe(0)
+            ->and($this->db->query("SELECT * FROM users")->numColumns())->toBe(3);
+    });
+
+    test('schema changes after a comment or in a batch invalidate the cache', function () {
+        $this->db->query("SELECT * FROM users")->fetchArray();
+
+        $this->db->execute("-- add a column\nALTER TABLE users ADD COLUMN email TEXT");
+        expect($this->db->statementCacheStats()['size'])->toBe(0);
+
+        $this->db->query("SELECT * FROM users")->fetchArray();
+        $this->db->executeBatch("INSERT INTO users (name) VALUES ('a;b'); /* drop */ DROP TABLE users");
+        expect($this->db->statementCacheStats()['size'])->toBe(0);
+    });
+
+    test('transaction statements are served from the cache', function () {
+        $this->db->clearStatementCache();
+        $before = $this->db->statementCacheStats();
+
+        $trx = $this->db->transaction();
+        foreach ([1, 2] as $id) {
+            $trx->execute("INSERT INTO users (id, name) VALUES (?, ?)", [$id, "user $id"]);
+            $trx->query("SELECT name FROM users WHERE id = ?", [$id])->fetchArray();
+        }
+        $trx->commit();
+
+        $stats = $this->db->statementCacheStats();
+
+        expect($stats['misses'] - $before['misses'])->toBe(2)
+            ->and($stats['hits'] - $before['hits'])->toBe(2)
+            ->and($this->db->query("SELECT count(*) FROM users")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(2);
+    });
+
+    test('schema changes in transactions and prepared statements invalidate the cache', function () {
+        $this->db->query("SELECT * FROM users")->fetchArray();
+
+        $trx = $this->db->transaction();
+        $trx->execute("ALTER TABLE users ADD COLUMN email TEXT");
+        $trx->commit();
+        expect($this->db->statementCacheStats()['size'])->toBe(0);
+
+        $this->db->query("SELECT * FROM users")->fetchArray();
+        $this->db->prepare("CREATE INDEX users_name ON users (name)")->execute();
+        expect($this->db->statementCacheStats()['size'])->toBe(0);
+    });
+
+    test('common table expressions are cached', function () {
+        $this->db->clearStatementCache();
+        $this->db->query("WITH ids AS (SELECT id FROM users) SELECT * FROM ids")->fetchArray();
+
+        expect($this->db->statementCacheStats()['size'])->toBe(1);
+    });
+
+    test('cache size is configurable', function () {
+        $db = new LibSQL(['url' => ':memory:', 'statementCacheSize' => 0]);
+        $db->query("SELECT 1");
+
+        expect($db->statementCacheStats())->toBe(['hits' => 0, 'misses' => 0, 'size' => 0, 'capacity' => 0]);
+
+        $db->close();
+    });
+})->group('StatementCacheTest', 'Feature');
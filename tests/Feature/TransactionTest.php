@@ -34,4 +34,248 @@ describe('Transactions', function () {
         $result = $this->db->query("SELECT COUNT(*) FROM accounts");
         expect($result->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(0);
     });
+
+    test('exclusive transaction', function () {
+        $trx = $this->db->transaction('exclusive');
+        $trx->execute("INSERT INTO accounts (balance) VALUES (100)");
+
+        expect($trx->behavior())->toBe('EXCLUSIVE');
+        $trx->commit();
+
+        $result = $this->db->query("SELECT COUNT(*) FROM accounts");
+        expect($result->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(1);
+    });
+
+    test('unknown transaction behavior throws', function () {
+        expect(fn () => $this->db->transaction('EXCLUSIV'))
+            ->toThrow(LibSQLException::class, "Unknown transaction behavior 'EXCLUSIV'");
+
+        expect($this->db->isAutocommit())->toBeTrue();
+    });
+
+    test('transaction is rolled back after its timeout', function () {
+        $trx = $this->db->transaction('IMMEDIATE', 50);
+        $trx->execute("INSERT INTO accounts (balance) VALUES (100)");
+
+        usleep(300_000);
+
+        expect($trx->isActive())->toBeFalse()
+            ->and(fn () => $trx->execute("INSERT INTO accounts (balance) VALUES (200)"))->toThrow(TimeoutException::class)
+            ->and(fn () => $trx->commit())->toThrow(TimeoutException::class)
+            ->and($this->db->isAutocommit())->toBeTrue();
+
+        $result = $this->db->query("SELECT COUNT(*) FROM accounts");
+        expect($result->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(0);
+    });
+
+    test('timed out transaction is rolled back on commit', function () {
+        $trx = $this->db->transaction('IMMEDIATE', 50);
+        $trx->execute("INSERT INTO accounts (balance) VALUES (100)");
+
+        usleep(100_000);
+
+        expect(fn () => $trx->commit())->toThrow(TimeoutException::class)
+            ->and($this->db->isAutocommit())->toBeTrue()
+            ->and($this->db->query("SELECT COUNT(*) FROM accounts")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(0);
+    });
+
+    test('timed out transaction is rolled back by the next statement on its connection', function () {
+        $trx = $this->db->transaction('IMMEDIATE', 50);
+        $trx->execute("INSERT INTO accounts (balance) VALUES (100)");
+
+        usleep(100_000);
+
+        expect(fn () => $this->db->execute("INSERT INTO accounts (balance) VALUES (200)"))->toThrow(TimeoutException::class)
+            ->and($trx->isActive())->toBeFalse()
+            ->and($this->db->isAutocommit())->toBeTrue();
+
+        $next = $this->db->transaction();
+        $next->execute("INSERT INTO accounts (balance) VALUES (300)");
+        $next->commit();
+
+        expect($this->db->query("SELECT SUM(balance) FROM accounts")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(300);
+    });
+
+    test('timed out transaction is not nested into', function () {
+        $trx = $this->db->transaction('IMMEDIATE', 50);
+        $trx->execute("INSERT INTO accounts (balance) VALUES (100)");
+
+        usleep(100_000);
+
+        $next = $this->db->transaction();
+        $next->execute("INSERT INTO accounts (balance) VALUES (200)");
+        $next->commit();
+
+        expect($trx->isActive())->toBeFalse()
+            ->and($this->db->query("SELECT SUM(balance) FROM accounts")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(200);
+    });
+
+    test('transaction query returns a LibSQLResult', function () {
+        $this->db->enableTypeMapping();
+        $trx = $this->db->transaction();
+        $trx->execute("INSERT INTO accounts (balance) VALUES (?)", [1000]);
+
+        $result = $trx->query("SELECT id, balance FROM accounts WHERE balance > ?", [500]);
+        $typed = $trx->query("SELECT balance FROM accounts", [], ['balance' => 'string']);
+
+        expect($result)->toBeInstanceOf(LibSQLResult::class)
+            ->and($result->fetchArray(LibSQL::LIBSQL_ASSOC))->toBe([['id' => 1, 'balance' => 1000]])
+            ->and($trx->query("SELECT COUNT(*) FROM accounts")->fetchSingle(LibSQL::LIBSQL_NUM))->toBe([1])
+            ->and($typed->fetchSingle(LibSQL::LIBSQL_NUM))->toBe(['1000']);
+
+        $trx->rollback();
+    });
+
+    test('nested transaction rolls back only its own changes', function () {
+        $trx = $this->db->transaction();
+        $trx->execute("INSERT INTO accounts (balance) VALUES (100)");
+
+        $nested = $this->db->transaction();
+        $nested->execute("INSERT INTO accounts (balance) VALUES (200)");
+        $nested->rollback();
+
+        $inner = $trx->transaction();
+        $inner->execute("INSERT INTO accounts (balance) VALUES (300)");
+        $inner->commit();
+
+        expect($trx->isAutocommit())->toBeFalse();
+        $trx->commit();
+
+        $result = $this->db->query("SELECT balance FROM accounts ORDER BY id");
+        expect($result->fetchArray(LibSQL::LIBSQL_NUM))->toBe([[100], [300]]);
+    });
+
+    test('nested transaction changes are undone with the outer transaction', function () {
+        $trx = $this->db->transaction();
+
+        $nested = $trx->transaction();
+        $nested->execute("INSERT INTO accounts (balance) VALUES (200)");
+        $nested->commit();
+
+        $trx->rollback();
+
+        $result = $this->db->query("SELECT COUNT(*) FROM accounts");
+        expect($result->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(0);
+    });
+
+    test('named savepoints', function () {
+        $trx = $this->db->transaction();
+        $trx->execute("INSERT INTO accounts (balance) VALUES (100)");
+
+        $trx->savepoint('before_bonus');
+        $trx->execute("UPDATE accounts SET balance = balance + 50");
+        $trx->rollbackTo('before_bonus');
+        $trx->releaseSavepoint('before_bonus');
+        $trx->commit();
+
+        $result = $this->db->query("SELECT balance FROM accounts");
+        expect($result->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(100);
+    });
+
+    test('unknown savepoint throws', function () {
+        $trx = $this->db->transaction();
+
+        expect(fn () => $trx->rollbackTo('missing'))->toThrow(LibSQLException::class, 'no such savepoint');
+
+        $trx->rollback();
+    });
+
+    test('nesting inside a finished transaction throws', function () {
+        $trx = $this->db->transaction();
+        $trx->commit();
+
+        expect(fn () => $trx->transaction())->toThrow(Exception::class, 'Transaction is no longer active');
+    });
+
+    test('using a finished transaction throws', function () {
+        $trx = $this->db->transaction('WRITE');
+        $insert = $trx->prepare("INSERT INTO accounts (balance) VALUES (?)");
+        $insert->execute([100]);
+
+        expect($trx->isActive())->toBeTrue()
+            ->and($trx->behavior())->toBe('WRITE');
+
+        $trx->commit();
+
+        expect($trx->isActive())->toBeFalse()
+            ->and(fn () => $trx->execute("INSERT INTO accounts (balance) VALUES (200)"))
+            ->toThrow(LibSQLException::class, 'Transaction is no longer active')
+            ->and(fn () => $insert->execute([300]))
+            ->toThrow(LibSQLException::class, 'Transaction is no longer active')
+            ->and(fn () => $trx->commit())
+            ->toThrow(LibSQLException::class, 'Transaction is no longer active');
+
+        $result = $this->db->query("SELECT balance FROM accounts");
+        expect($result->fetchArray(LibSQL::LIBSQL_NUM))->toBe([[100]]);
+    });
+
+    test('nested transaction ends with its outer transaction', function () {
+        $trx = $this->db->transaction();
+        $nested = $trx->transaction();
+
+        $trx->rollback();
+
+        expect($nested->isActive())->toBeFalse()
+            ->and(fn () => $nested->query("SELECT 1"))
+            ->toThrow(LibSQLException::class, 'Transaction is no longer active');
+    });
+
+    test('transactional commits and returns the callable result', function () {
+        $result = $this->db->transactional(function (LibSQLTransaction $trx) {
+            $trx->execute("INSERT INTO accounts (balance) VALUES (100)");
+            $trx->execute("INSERT INTO accounts (balance) VALUES (200)");
+
+            return 'done';
+        });
+
+        $count = $this->db->query("SELECT COUNT(*) FROM accounts")->fetchSingle(LibSQL::LIBSQL_NUM)[0];
+        expect($result)->toBe('done')
+            ->and($count)->toBe(2)
+            ->and($this->db->isAutocommit())->toBeTrue();
+    });
+
+    test('transactional rolls back and rethrows', function () {
+        expect(fn () => $this->db->transactional(function (LibSQLTransaction $trx) {
+            $trx->execute("INSERT INTO accounts (balance) VALUES (100)");
+
+            throw new DomainException('insufficient funds');
+        }))->toThrow(DomainException::class, 'insufficient funds');
+
+        $count = $this->db->query("SELECT COUNT(*) FROM accounts")->fetchSingle(LibSQL::LIBSQL_NUM)[0];
+        expect($count)->toBe(0)
+            ->and($this->db->isAutocommit())->toBeTrue();
+    });
+
+    test('transactional retries when the database is busy', function () {
+        $attempts = 0;
+
+        $result = $this->db->transactional(function (LibSQLTransaction $trx) use (&$attempts) {
+            $attempts++;
+            $trx->execute("INSERT INTO accounts (balance) VALUES (?)", [$attempts]);
+
+            if ($attempts < 3) {
+                throw new BusyException('database is locked', 5);
+            }
+
+            return $attempts;
+        }, retries: 3, backoff: 1);
+
+        $balances = $this->db->query("SELECT balance FROM accounts")->fetchArray(LibSQL::LIBSQL_NUM);
+        expect($result)->toBe(3)
+            ->and($balances)->toBe([[3]]);
+    });
+
+    test('transactional gives up after the configured retries', function () {
+        $attempts = 0;
+
+        expect(function () use (&$attempts) {
+            $this->db->transactional(function () use (&$attempts) {
+                $attempts++;
+
+                throw new BusyException('database is locked', 5);
+            }, retries: 2, backoff: 1);
+        })->toThrow(BusyException::class);
+
+        expect($attempts)->toBe(3);
+    });
 })->group('TransactionTest', 'Feature');
\ No newline at end of file
@@ -0,0 +1,54 @@
+<?php
+
+use Tests\TestCase;
+
+uses(TestCase::class);
+
+describe('Type Mapping', function () {
+    beforeEach(function () {
+        $this->db->execute("CREATE TABLE events (
+            id INTEGER PRIMARY KEY,
+            is_public BOOLEAN,
+            starts_at DATETIME,
+            payload JSON,
+            price DECIMAL(10, 2),
+            thumbnail BLOB
+        )");
+        $this->db->execute(
+            "INSERT INTO events VALUES (1, 1, '2024-05-01 10:00:00', '{\"tags\":[\"php\"]}', 19.99, X'DEADBEEF')"
+        );
+    });
+
+    test('values keep their storage types by default', function () {
+        $event = $this->db->query("SELECT is_public, starts_at FROM events")->fetchSingle(LibSQL::LIBSQL_ASSOC);
+
+        expect($event['is_public'])->toBe(1)
+            ->and($event['starts_at'])->toBe('2024-05-01 10:00:00');
+    });
+
+    test('declared types are converted when type mapping is enabled', function () {
+        $this->db->enableTypeMapping();
+
+        $event = $this->db->query("SELECT * FROM events")->fetchSingle(LibSQL::LIBSQL_ASSOC);
+
+        expect($event['is_public'])->toBeTrue()
+            ->and($event['starts_at'])->toBeInstanceOf(DateTimeImmutable::class)
+            ->and($event['starts_at']->format('Y-m-d H:i'))->toBe('2024-05-01 10:00')
+            ->and($event['payload'])->toBe(['tags' => ['php']])
+            ->and($event['price'])->toBe('19.99')
+            ->and($event['thumbnail'])->toBe(hex2bin('DEADBEEF'));
+    });
+
+    test('per-query overrides are keyed by column name', function () {
+        $event = $this->db->query("SELECT id, is_public FROM events", [], false, ['id' => 'string', 'is_public' => 'bool'])
+            ->fetchSingle(LibSQL::LIBSQL_ASSOC);
+
+        expect($event['id'])->toBe('1')
+            ->and($event['is_public'])->toBeTrue();
+    });
+
+    test('unknown PHP types are rejected', function () {
+        expect(fn() => $this->db->enableTypeMapping(['MONEY' => 'currency']))
+            ->toThrow(Exception::class);
+    });
+})->group('TypeMappingTest', 'Feature');
@@ -16,4 +16,25 @@ test('embedded replica connection + offline write', function () use ($config) {
         config: $config, 
         offline_writes: true
     ))->not->toThrow(Exception::class);
-})->group('OfflineWriteConnectionTest', 'DatabaseConnectionTest');
\ No newline at end of file
+})->group('OfflineWriteConnectionTest', 'DatabaseConnectionTest');
+
+test('sqld offline write executeMany rolls back every row when one fails', function () use ($authToken) {
+    $db = new LibSQL(
+        config: [
+            "url" => "file:sqld-offline-execute-many.db",
+            "authToken" => $authToken,
+            "syncUrl" => "http://127.0.0.1:8080",
+        ],
+        sqld_offline_mode: true,
+        offline_writes: true
+    );
+    $db->execute("CREATE TABLE IF NOT EXISTS offline_items (id INTEGER PRIMARY KEY)");
+    $db->execute("DELETE FROM offline_items");
+
+    $insert = "INSERT INTO offline_items (id) VALUES (?)";
+    expect(fn () => $db->executeMany($insert, [[1], [2], [1]]))->toThrow(Exception::class)
+        ->and($db->query("SELECT count(*) FROM offline_items")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(0)
+        ->and($db->executeMany($insert, [[1], [2]])['rowsChanged'])->toBe(2);
+
+    $db->close();
+})->group('OfflineWriteConnectionTest', 'DatabaseConnectionTest');
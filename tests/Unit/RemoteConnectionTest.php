@@ -10,3 +10,96 @@ test('remote connection using libsql protocol', function () use ($authToken) {
     expect(fn() => new LibSQL('libsql:dbname=libsql://127.0.0.1:8080;authToken=' . $authToken))->not->toThrow(Exception::class);
 })->group('RemoteConnectionTest', 'DatabaseConnectionTest');
 
+test('remote connection binds positional and named parameters', function () use ($authToken) {
+    $db = new LibSQL('libsql:dbname=http://127.0.0.1:8080;authToken=' . $authToken);
+
+    expect($db->query("SELECT ? AS a, ?2 AS b", [1, 'two'])->fetchSingle(LibSQL::LIBSQL_ASSOC))
+        ->toBe(['a' => 1, 'b' => 'two'])
+        ->and($db->query("SELECT :name AS name", [':name' => 'libsql'])->fetchSingle(LibSQL::LIBSQL_ASSOC))
+        ->toBe(['name' => 'libsql']);
+
+    $stmt = $db->prepare("SELECT :x + :y AS sum");
+    $stmt->bindNamed([':x' => 2, ':y' => 3]);
+
+    expect($stmt->query()->fetchSingle(LibSQL::LIBSQL_ASSOC))->toBe(['sum' => 5]);
+
+    $db->close();
+})->group('RemoteConnectionTest', 'DatabaseConnectionTest');
+
+test('remote statements run inside the open transaction', function () use ($authToken) {
+    $db = new LibSQL('libsql:dbname=http://127.0.0.1:8080;authToken=' . $authToken);
+    $db->execute("CREATE TABLE IF NOT EXISTS cache_items (id INTEGER PRIMARY KEY)");
+    $db->execute("DELETE FROM cache_items");
+
+    $insert = "INSERT INTO cache_items (id) VALUES (?)";
+    $db->execute($insert, [1]);
+
+    $db->execute("BEGIN");
+    $db->execute($insert, [2]);
+    $db->execute("ROLLBACK");
+
+    expect($db->query("SELECT count(*) FROM cache_items")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(1)
+        ->and($db->statementCacheStats()['size'])->toBe(0);
+
+    $db->execute("DROP TABLE cache_items");
+    $db->close();
+})->group('RemoteConnectionTest', 'DatabaseConnectionTest');
+
+test('remote executeMany runs every execution in one transaction', function () use ($authToken) {
+    $db = new LibSQL('libsql:dbname=http://127.0.0.1:8080;authToken=' . $authToken);
+    $db->execute("CREATE TABLE IF NOT EXISTS many_items (id INTEGER PRIMARY KEY)");
+    $db->execute("DELETE FROM many_items");
+
+    $insert = "INSERT INTO many_items (id) VALUES (?)";
+    expect($db->executeMany($insert, [[1], [2]]))->toBe(['rowsChanged' => 2, 'lastInsertIds' => [1, 2]])
+        ->and(fn () => $db->executeMany($insert, [[3], [1]]))->toThrow(Exception::class);
+
+    $db->execute("BEGIN");
+    $db->prepare($insert)->executeMany([[4], [5]]);
+    $db->execute("ROLLBACK");
+
+    expect($db->query("SELECT count(*) FROM many_items")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(2);
+
+    $db->execute("DROP TABLE many_items");
+    $db->close();
+})->group('RemoteConnectionTest', 'DatabaseConnectionTest');
+
+test('remote errors mentioning 401 are not authentication errors', function () use ($authToken) {
+    $db = new LibSQL('libsql:dbname=http://127.0.0.1:8080;authToken=' . $authToken);
+
+    try {
+        $db->query("SELECT * FROM unauthorized_401");
+        $this->fail('Expected an exception');
+    } catch (LibSQLException $e) {
+        expect($e)->not->toBeInstanceOf(AuthException::class);
+    }
+
+    $db->close();
+})->group('RemoteConnectionTest', 'DatabaseConnectionTest');
+
+test('remote connection runs queries after a query timeout', function () use ($authToken) {
+    $db = new LibSQL('libsql:dbname=http://127.0.0.1:8080;authToken=' . $authToken);
+    $slow = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 50000000) SELECT MAX(i) FROM n";
+
+    expect(fn () => $db->execute($slow, [], 50))->toThrow(TimeoutException::class)
+        ->and($db->query("SELECT 1")->fetchSingle(LibSQL::LIBSQL_NUM)[0])->toBe(1);
+
+    $db->close();
+})->group('RemoteConnectionTest', 'DatabaseConnectionTest');
+
+test('remote insertMany counts inserted and updated rows', function () use ($authToken) {
+    $db = new LibSQL('libsql:dbname=http://127.0.0.1:8080;authToken=' . $authToken);
+    $db->execute("CREATE TABLE IF NOT EXISTS upsert_items (id INTEGER PRIMARY KEY, label TEXT)");
+    $db->execute("DELETE FROM upsert_items");
+    $db->execute("INSERT INTO upsert_items VALUES (1, 'one')");
+
+    $result = $db->insertMany('upsert_items', [
+        ['id' => 1, 'label' => 'uno'],
+        ['id' => 2, 'label' => 'dos'],
+    ], ['conflict' => 'id']);
+
+    expect($result)->toBe(['inserted' => 1, 'updated' => 1]);
+
+    $db->execute("DROP TABLE upsert_items");
+    $db->close();
+})->group('RemoteConnectionTest', 'DatabaseConnectionTest');